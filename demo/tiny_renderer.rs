@@ -1,24 +1,4 @@
-use std::{cell::RefCell, f32::consts::PI, rc::Rc};
-
-const ORBIT_SENSITIVITY: f32 = 0.005;
-
-struct OrbitState {
-  azimuth: f32,
-  elevation: f32,
-  distance: f32,
-  target: math::Vec3,
-  last_mouse: Option<(i32, i32)>,
-}
-
-impl OrbitState {
-  fn camera_position(&self) -> math::Vec3 {
-    math::Vec3::new(
-      self.target.x + self.distance * self.elevation.cos() * self.azimuth.sin(),
-      self.target.y + self.distance * self.elevation.sin(),
-      self.target.z + self.distance * self.elevation.cos() * self.azimuth.cos(),
-    )
-  }
-}
+use std::{cell::RefCell, rc::Rc};
 
 const RESOURCE_PATH: &str = "./resources";
 const FOLDER: &str = "freihand";
@@ -42,6 +22,8 @@ use tinyrenderer::{
   model::{self, from_obj_path, Model, Scene, Vertex},
   obj_loader::material::{self, Material, Texture},
   renderer::{
+    controls::OrbitController,
+    light::Light,
     renderer::Renderer,
     shader::{
       gouraud::make_gouraud_shader, phong::make_phong_shader, shadow::make_shadow_shader,
@@ -104,12 +86,7 @@ fn direct_light_shading(
   let mut texture = Texture::load("233", path, 1).unwrap();
 
   for model in &scene.models {
-    let vertext_number = model.vertices.len();
-    for i in 0..vertext_number / 3 {
-      let v0 = model.vertices[i * 3 + 0];
-      let v1 = model.vertices[i * 3 + 1];
-      let v2 = model.vertices[i * 3 + 2];
-
+    for [v0, v1, v2] in model.triangles() {
       let (v01, v02) = (v1.position - v0.position, v2.position - v0.position);
       let face_normal = v02
         .truncated_to_vec3()
@@ -177,7 +154,7 @@ fn main() {
   // let sandbox = sandbox::Sandbox::new(WINDOW_WIDTH as i32, WINDOW_HEIGHT as i32, false);
   // let sandbox = sandbox::Sandbox::new(WINDOW_WIDTH as i32, WINDOW_HEIGHT as i32, true);
   let sandbox = sandbox::Sandbox::new(WINDOW_WIDTH as i32, WINDOW_HEIGHT as i32, true);
-  let draw_image = sandbox.make_draw_image();
+  let draw_image = sandbox.make_draw_rgba_image();
 
   // let _ = scene.textures.load(tag_path, "african_head_diffuse");
 
@@ -212,33 +189,39 @@ fn main() {
 
   let mut material = Material::default();
 
-  // material.shader = make_gouraud_shader(Vec3::new(1.0, 1.0, 1.0));
-  material.shader = make_wireframe_shader(0.01, Vec3::new(1.0, 1.0, 1.0));
-  // material.shader = make_phong_shader(Vec3::new(1.0, 1.0, 1.0));
+  // material.shader = make_gouraud_shader();
+  material.shader = make_wireframe_shader(0.01);
+  // material.shader = make_phong_shader();
   // material.shader = make_shadow_shader();
   // renderer.camera.lookat(Vec3::new(0.0, 0.0, 0.0));
   // renderer.camera.set_rotation(Vec3::new(0.0, 0.0, 0.0));
 
   let scene = from_obj_path(OBJ_PATH, "hand").unwrap();
 
-  // initial camera position matches old Vec3::new(1.0, 2.0, 5.0)
-  let orbit = Rc::new(RefCell::new(OrbitState {
-    azimuth: 0.197,
-    elevation: 0.381,
-    distance: 5.477,
-    target: math::Vec3::new(0.0, 0.0, 0.0),
-    last_mouse: None,
-  }));
+  let orbit = Rc::new(RefCell::new(OrbitController::new(
+    Vec3::new(1.0, 2.0, 5.0),
+    Vec3::zero(),
+  )));
   let orbit_draw = orbit.clone();
+  let mut last_mouse: Option<(i32, i32)> = None;
+
+  // the left & right keys turn the light around the model
+  let light_angle = Rc::new(RefCell::new(45f32.to_radians()));
+  let light_angle_draw = light_angle.clone();
 
   sandbox.run_fltk_with_events(
     move |_| {
-      let state = orbit_draw.borrow();
-      let pos = state.camera_position();
-      let target = state.target;
-      drop(state);
-      renderer.camera.move_to(pos);
-      renderer.camera.lookat(target);
+      orbit_draw.borrow().update(&mut renderer.camera);
+
+      let angle = *light_angle_draw.borrow();
+      renderer.lights = vec![
+        Light::directional(
+          Vec3::new(angle.sin(), 1.0, angle.cos()),
+          Vec3::new(1.0, 1.0, 1.0),
+          0.8,
+        ),
+        Light::point(Vec3::new(0.0, 3.0, 3.0), Vec3::new(1.0, 0.9, 0.8), 6.0),
+      ];
 
       let model = math::apply_translate(&math::Vec3::new(0.0, 0.0, 0.0))
         * math::apply_eular_rotate_y(180f32.to_radians());
@@ -250,22 +233,20 @@ fn main() {
       use fltk::enums::Event;
       match event {
         Event::Push => {
-          orbit.borrow_mut().last_mouse = Some((fltk::app::event_x(), fltk::app::event_y()));
+          last_mouse = Some((fltk::app::event_x(), fltk::app::event_y()));
           true
         }
         Event::Released => {
-          orbit.borrow_mut().last_mouse = None;
+          last_mouse = None;
           true
         }
         Event::Drag => {
           let (mx, my) = (fltk::app::event_x(), fltk::app::event_y());
-          let mut state = orbit.borrow_mut();
-          if let Some((lx, ly)) = state.last_mouse {
-            let dx = -1.0 * (mx - lx) as f32 * ORBIT_SENSITIVITY;
-            let dy = -1.0 * (my - ly) as f32 * ORBIT_SENSITIVITY;
-            state.azimuth += dx;
-            state.elevation = (state.elevation - dy).clamp(-PI / 2.0 + 0.01, PI / 2.0 - 0.01);
-            state.last_mouse = Some((mx, my));
+          if let Some((lx, ly)) = last_mouse {
+            orbit
+              .borrow_mut()
+              .rotate((mx - lx) as f32, (my - ly) as f32);
+            last_mouse = Some((mx, my));
           }
           true
         }
@@ -276,8 +257,20 @@ fn main() {
             MouseWheel::Down => 1.0f32,
             _ => 0.0f32,
           };
-          let mut state = orbit.borrow_mut();
-          state.distance = (state.distance - delta * 0.5).max(0.5);
+          orbit.borrow_mut().zoom(delta);
+          true
+        }
+        Event::KeyDown => {
+          use fltk::enums::Key;
+          let key = fltk::app::event_key();
+          let step = if key == Key::Left {
+            -0.1
+          } else if key == Key::Right {
+            0.1
+          } else {
+            return false;
+          };
+          *light_angle.borrow_mut() += step;
           true
         }
         _ => false,
@@ -2,6 +2,7 @@ use std::ops::{Add, Mul};
 
 use super::Vec2;
 
+#[derive(Debug, Clone, Copy)]
 pub struct Barycentric {
   alpha: f32,
   beta: f32,
@@ -28,6 +29,11 @@ impl Barycentric {
     Self { alpha, beta, gamma }
   }
 
+  /// weights that already sum up to one, e.g. from `EdgeRasterizer`
+  pub fn from_weights(alpha: f32, beta: f32, gamma: f32) -> Self {
+    Self { alpha, beta, gamma }
+  }
+
   pub fn is_inside(&self) -> bool {
     self.alpha + self.beta + self.gamma < 1.00001
   }
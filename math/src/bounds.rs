@@ -0,0 +1,145 @@
+use super::{Mat4, Vec3, Vec4};
+
+/// Axis aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+  pub min: Vec3,
+  pub max: Vec3,
+}
+
+impl BoundingBox {
+  /// `None` without any point
+  pub fn from_points(points: &[Vec3]) -> Option<Self> {
+    let first = *points.first()?;
+    Some(points.iter().fold(
+      Self {
+        min: first,
+        max: first,
+      },
+      |b, p| Self {
+        min: Vec3::new(b.min.x.min(p.x), b.min.y.min(p.y), b.min.z.min(p.z)),
+        max: Vec3::new(b.max.x.max(p.x), b.max.y.max(p.y), b.max.z.max(p.z)),
+      },
+    ))
+  }
+
+  pub fn center(&self) -> Vec3 {
+    (self.min + self.max) / 2.0
+  }
+
+  pub fn half_extents(&self) -> Vec3 {
+    (self.max - self.min) / 2.0
+  }
+
+  pub fn corners(&self) -> [Vec3; 8] {
+    let (a, b) = (self.min, self.max);
+    [
+      Vec3::new(a.x, a.y, a.z),
+      Vec3::new(b.x, a.y, a.z),
+      Vec3::new(a.x, b.y, a.z),
+      Vec3::new(b.x, b.y, a.z),
+      Vec3::new(a.x, a.y, b.z),
+      Vec3::new(b.x, a.y, b.z),
+      Vec3::new(a.x, b.y, b.z),
+      Vec3::new(b.x, b.y, b.z),
+    ]
+  }
+
+  /// the box around the transformed corners, an affine `matrix` is expected
+  pub fn transform(&self, matrix: &Mat4) -> Self {
+    let corners = self
+      .corners()
+      .map(|c| (*matrix * Vec4::from_vec3(&c, 1.0)).truncated_to_vec3());
+    Self::from_points(&corners).unwrap()
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+  pub center: Vec3,
+  pub radius: f32,
+}
+
+impl BoundingSphere {
+  /// Centred on the bounding box of the points, not the smallest sphere but
+  /// close to it for the usual meshes. `None` without any point.
+  pub fn from_points(points: &[Vec3]) -> Option<Self> {
+    let center = BoundingBox::from_points(points)?.center();
+    let radius = points
+      .iter()
+      .map(|p| (*p - center).length_square())
+      .fold(0.0, f32::max)
+      .sqrt();
+    Some(Self { center, radius })
+  }
+
+  /// the radius grows with the largest scale of the affine `matrix`
+  pub fn transform(&self, matrix: &Mat4) -> Self {
+    let center = (*matrix * Vec4::from_vec3(&self.center, 1.0)).truncated_to_vec3();
+    let scale = (0..3)
+      .map(|axis| {
+        let column = Vec3::new(
+          matrix.get(axis, 0),
+          matrix.get(axis, 1),
+          matrix.get(axis, 2),
+        );
+        column.length()
+      })
+      .fold(0.0, f32::max);
+    Self {
+      center,
+      radius: self.radius * scale,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{apply_eular_rotate_z, apply_scale, apply_translate};
+  use std::f32::consts::FRAC_PI_2;
+
+  fn points() -> [Vec3; 3] {
+    [
+      Vec3::new(1.0, 0.0, -1.0),
+      Vec3::new(3.0, 2.0, -1.0),
+      Vec3::new(2.0, 1.0, 1.0),
+    ]
+  }
+
+  #[test]
+  fn from_points() {
+    assert!(BoundingBox::from_points(&[]).is_none());
+    assert!(BoundingSphere::from_points(&[]).is_none());
+
+    let aabb = BoundingBox::from_points(&points()).unwrap();
+    assert_eq!(aabb.min, Vec3::new(1.0, 0.0, -1.0));
+    assert_eq!(aabb.max, Vec3::new(3.0, 2.0, 1.0));
+    assert_eq!(aabb.center(), Vec3::new(2.0, 1.0, 0.0));
+
+    let sphere = BoundingSphere::from_points(&points()).unwrap();
+    assert_eq!(sphere.center, aabb.center());
+    assert!(points()
+      .iter()
+      .all(|p| (*p - sphere.center).length() <= sphere.radius + 1e-6));
+  }
+
+  #[test]
+  fn transform() {
+    // a quarter turn around z, then moved along x
+    let matrix = apply_translate(&Vec3::new(10.0, 0.0, 0.0)) * apply_eular_rotate_z(FRAC_PI_2);
+    let aabb = BoundingBox::from_points(&points())
+      .unwrap()
+      .transform(&matrix);
+    assert!((aabb.min - Vec3::new(8.0, 1.0, -1.0)).length() < 1e-5);
+    assert!((aabb.max - Vec3::new(10.0, 3.0, 1.0)).length() < 1e-5);
+
+    let sphere = BoundingSphere {
+      center: Vec3::new(1.0, 0.0, 0.0),
+      radius: 2.0,
+    };
+    let scaled = sphere.transform(&(matrix * apply_scale(&Vec3::new(1.0, 3.0, 2.0))));
+    assert!((scaled.center - Vec3::new(10.0, 1.0, 0.0)).length() < 1e-5);
+    assert!((scaled.radius - 6.0).abs() < 1e-5);
+  }
+}
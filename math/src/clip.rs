@@ -0,0 +1,247 @@
+use super::{lerp, Vec3, Vec4};
+
+/// Clip planes in homogeneous space, a point `p` is inside when `plane · p >= 0`.
+/// In order: left, right, bottom, top, near, far.
+#[rustfmt::skip]
+const CLIP_PLANES: [Vec4; 6] = [
+  Vec4::new( 1.0,  0.0,  0.0, 1.0),
+  Vec4::new(-1.0,  0.0,  0.0, 1.0),
+  Vec4::new( 0.0,  1.0,  0.0, 1.0),
+  Vec4::new( 0.0, -1.0,  0.0, 1.0),
+  Vec4::new( 0.0,  0.0,  1.0, 1.0),
+  Vec4::new( 0.0,  0.0, -1.0, 1.0),
+];
+
+/// A vertex of the clipped polygon. `weights` are the barycentric weights
+/// relative to the three vertices of the source triangle, so that every
+/// attribute can be re-interpolated for the newly created vertices.
+#[derive(Debug, Clone, Copy)]
+pub struct ClipVertex {
+  pub position: Vec4,
+  pub weights: Vec3,
+}
+
+pub enum ClipResult {
+  /// the triangle lies completely inside the view volume
+  Inside,
+  /// the triangle lies completely outside the view volume
+  Outside,
+  /// convex polygon left after the triangle is cut by the view volume
+  Clipped(Vec<ClipVertex>),
+}
+
+/// inside the view volume, on its planes included
+pub fn is_inside(position: &Vec4) -> bool {
+  CLIP_PLANES.iter().all(|plane| plane.dot(position) >= 0.0)
+}
+
+/// Sutherland–Hodgman clipping of a clip space (before perspective divide)
+/// triangle against the six planes of the view volume.
+pub fn clip_triangle(positions: &[Vec4; 3]) -> ClipResult {
+  if positions.iter().all(is_inside) {
+    return ClipResult::Inside;
+  }
+
+  for plane in &CLIP_PLANES {
+    if positions.iter().all(|p| plane.dot(p) < 0.0) {
+      return ClipResult::Outside;
+    }
+  }
+
+  let weights = [
+    Vec3::new(1.0, 0.0, 0.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    Vec3::new(0.0, 0.0, 1.0),
+  ];
+
+  let mut polygon: Vec<ClipVertex> = positions
+    .iter()
+    .zip(weights)
+    .map(|(position, weights)| ClipVertex {
+      position: *position,
+      weights,
+    })
+    .collect();
+
+  for plane in &CLIP_PLANES {
+    if polygon.is_empty() {
+      break;
+    }
+
+    let mut next = Vec::with_capacity(polygon.len() + 1);
+    for i in 0..polygon.len() {
+      let current = polygon[i];
+      let following = polygon[(i + 1) % polygon.len()];
+
+      let d_current = plane.dot(&current.position);
+      let d_following = plane.dot(&following.position);
+
+      if d_current >= 0.0 {
+        next.push(current);
+      }
+
+      // the edge crosses the plane, emit the intersection
+      if (d_current >= 0.0) != (d_following >= 0.0) {
+        let t = d_current / (d_current - d_following);
+        next.push(ClipVertex {
+          position: lerp(current.position, following.position, t),
+          weights: lerp(current.weights, following.weights, t),
+        });
+      }
+    }
+
+    polygon = next;
+  }
+
+  if polygon.len() < 3 {
+    ClipResult::Outside
+  } else {
+    ClipResult::Clipped(polygon)
+  }
+}
+
+/// Fan triangulation of the clipped convex polygon.
+pub fn triangulate(polygon: &[ClipVertex]) -> Vec<[ClipVertex; 3]> {
+  (1..polygon.len() - 1)
+    .map(|i| [polygon[0], polygon[i], polygon[i + 1]])
+    .collect()
+}
+
+/// Clips a clip space segment against the view volume, the ends of what is
+/// left carry the weights of the two source ends in `x` and `y`. `None` when
+/// nothing is left.
+pub fn clip_segment(positions: &[Vec4; 2]) -> Option<[ClipVertex; 2]> {
+  let (mut t0, mut t1) = (0.0f32, 1.0f32);
+  for plane in &CLIP_PLANES {
+    let d0 = plane.dot(&positions[0]);
+    let d1 = plane.dot(&positions[1]);
+    match (d0 >= 0.0, d1 >= 0.0) {
+      (true, true) => {}
+      (false, false) => return None,
+      // the parameter where the segment crosses the plane
+      (true, false) => t1 = t1.min(d0 / (d0 - d1)),
+      (false, true) => t0 = t0.max(d0 / (d0 - d1)),
+    }
+  }
+  if t0 > t1 {
+    return None;
+  }
+
+  let at = |t: f32| ClipVertex {
+    position: lerp(positions[0], positions[1], t),
+    weights: Vec3::new(1.0 - t, t, 0.0),
+  };
+  Some([at(t0), at(t1)])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// the clip space position is the weighted sum of the source positions
+  fn assert_weights(clipped: &ClipVertex, positions: &[Vec4]) {
+    let w = [clipped.weights.x, clipped.weights.y, clipped.weights.z];
+    let blended = positions
+      .iter()
+      .zip(w)
+      .fold(Vec4::new(0.0, 0.0, 0.0, 0.0), |sum, (p, w)| sum + *p * w);
+    assert!((blended - clipped.position).length() < 1e-5);
+  }
+
+  fn inside() -> [Vec4; 3] {
+    [
+      Vec4::new(-0.5, -0.5, 0.0, 1.0),
+      Vec4::new(0.5, -0.5, 0.0, 1.0),
+      Vec4::new(0.0, 0.5, 0.0, 1.0),
+    ]
+  }
+
+  /// the polygon left is in the view volume, made of the source positions
+  fn clipped(positions: &[Vec4; 3]) -> Vec<ClipVertex> {
+    let ClipResult::Clipped(polygon) = clip_triangle(positions) else {
+      panic!("{positions:?} should be cut by the view volume");
+    };
+    for vertex in &polygon {
+      assert!(CLIP_PLANES
+        .iter()
+        .all(|plane| plane.dot(&vertex.position) >= -1e-5));
+      assert_weights(vertex, positions);
+    }
+    polygon
+  }
+
+  #[test]
+  fn inside_and_outside() {
+    assert!(matches!(clip_triangle(&inside()), ClipResult::Inside));
+
+    let outside = inside().map(|p| p + Vec4::new(3.0, 0.0, 0.0, 0.0));
+    assert!(matches!(clip_triangle(&outside), ClipResult::Outside));
+
+    // past the far plane
+    let outside = inside().map(|p| p + Vec4::new(0.0, 0.0, 2.0, 0.0));
+    assert!(matches!(clip_triangle(&outside), ClipResult::Outside));
+  }
+
+  #[test]
+  fn side_plane_split() {
+    // one corner past the right plane, cut into a quad
+    let positions = [inside()[0], Vec4::new(2.0, -0.5, 0.0, 1.0), inside()[2]];
+    let polygon = clipped(&positions);
+    assert_eq!(polygon.len(), 4);
+    assert_eq!(triangulate(&polygon).len(), 2);
+  }
+
+  #[test]
+  fn near_plane_split() {
+    // two corners in front of the near plane, z < -w
+    let positions = [
+      Vec4::new(-0.5, 0.0, -2.0, 1.0),
+      Vec4::new(0.5, 0.0, -2.0, 1.0),
+      Vec4::new(0.0, 0.0, 0.5, 1.0),
+    ];
+    let polygon = clipped(&positions);
+    assert_eq!(polygon.len(), 3);
+    let on_near = polygon
+      .iter()
+      .filter(|v| (v.position.z + v.position.w).abs() < 1e-5);
+    assert_eq!(on_near.count(), 2);
+  }
+
+  #[test]
+  fn crossing_w_zero() {
+    // a corner behind the eye, with a negative w, is cut by the near plane
+    // rather than projected through the eye
+    let positions = [
+      Vec4::new(-0.5, 0.0, 0.5, 1.0),
+      Vec4::new(0.5, 0.0, 0.5, 1.0),
+      Vec4::new(0.0, 0.0, -3.0, -1.0),
+    ];
+    let polygon = clipped(&positions);
+    assert_eq!(polygon.len(), 4);
+    assert!(polygon.iter().all(|v| v.position.w > 0.0));
+  }
+
+  #[test]
+  fn segment() {
+    let positions = [
+      Vec4::new(-3.0, 0.0, 0.0, 1.0),
+      Vec4::new(0.0, 0.0, 0.0, 1.0),
+    ];
+    let [a, b] = clip_segment(&positions).unwrap();
+    assert!((a.position - Vec4::new(-1.0, 0.0, 0.0, 1.0)).length() < 1e-5);
+    assert_eq!(b.position, positions[1]);
+    assert_weights(&a, &positions);
+    assert_weights(&b, &positions);
+
+    let outside = positions.map(|p| p + Vec4::new(0.0, 2.0, 0.0, 0.0));
+    assert!(clip_segment(&outside).is_none());
+
+    // the segment misses the corner of the view volume, though each end is
+    // only outside of one plane
+    let corner = [
+      Vec4::new(-2.0, 0.5, 0.0, 1.0),
+      Vec4::new(-0.5, 2.0, 0.0, 1.0),
+    ];
+    assert!(clip_segment(&corner).is_none());
+  }
+}
@@ -0,0 +1,132 @@
+use super::{Vec3, Vec4};
+
+/// How the values of an image relate to the light they stand for.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+  /// proportional to the light, the data maps and the lighting
+  #[default]
+  Linear,
+  /// gamma encoded, the color maps painted or photographed for a display
+  Srgb,
+}
+
+/// decodes one sRGB channel in [0, 1]
+pub fn srgb_to_linear(c: f32) -> f32 {
+  if c <= 0.04045 {
+    c / 12.92
+  } else {
+    ((c + 0.055) / 1.055).powf(2.4)
+  }
+}
+
+/// encodes one linear channel, clamped to [0, 1]
+pub fn linear_to_srgb(c: f32) -> f32 {
+  let c = c.clamp(0.0, 1.0);
+  if c <= 0.0031308 {
+    c * 12.92
+  } else {
+    1.055 * c.powf(1.0 / 2.4) - 0.055
+  }
+}
+
+/// the rgb channels are decoded, the alpha is linear already
+pub fn srgb_to_linear_color(color: &Vec4) -> Vec4 {
+  Vec4::new(
+    srgb_to_linear(color.x),
+    srgb_to_linear(color.y),
+    srgb_to_linear(color.z),
+    color.w,
+  )
+}
+
+/// the rgb channels are encoded, the alpha is only clamped
+pub fn linear_to_srgb_color(color: &Vec4) -> Vec4 {
+  Vec4::new(
+    linear_to_srgb(color.x),
+    linear_to_srgb(color.y),
+    linear_to_srgb(color.z),
+    color.w.clamp(0.0, 1.0),
+  )
+}
+
+/// Compresses the linear colors of a frame, which may go past 1, into the
+/// displayable [0, 1] range.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMapping {
+  /// clamps, what the 8 bit buffers used to do
+  #[default]
+  None,
+  /// `1 - e^-c`, as a film exposed for the time given by the exposure
+  Exposure,
+  /// `c / (1 + c)`
+  Reinhard,
+  /// Narkowicz's fit of the ACES filmic curve
+  AcesFilmic,
+}
+
+impl ToneMapping {
+  /// maps the linear `color`, scaled by `exposure` first
+  pub fn apply(&self, color: &Vec3, exposure: f32) -> Vec3 {
+    let map = |c: f32| {
+      let c = (c * exposure).max(0.0);
+      match self {
+        ToneMapping::None => c,
+        ToneMapping::Exposure => 1.0 - (-c).exp(),
+        ToneMapping::Reinhard => c / (1.0 + c),
+        ToneMapping::AcesFilmic => {
+          let (a, b, c2, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+          (c * (a * c + b)) / (c * (c2 * c + d) + e)
+        }
+      }
+      .clamp(0.0, 1.0)
+    };
+
+    Vec3::new(map(color.x), map(color.y), map(color.z))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn srgb_round_trip() {
+    for i in 0..=255 {
+      let c = i as f32 / 255.0;
+      assert!((linear_to_srgb(srgb_to_linear(c)) - c).abs() < 1e-5, "{c}");
+      assert!((srgb_to_linear(linear_to_srgb(c)) - c).abs() < 1e-5, "{c}");
+    }
+
+    // the mid grey of a display is about a fifth of the light
+    assert!((srgb_to_linear(0.5) - 0.214).abs() < 1e-3);
+    assert!((linear_to_srgb(2.0) - 1.0).abs() < 1e-6);
+    assert_eq!(linear_to_srgb(-1.0), 0.0);
+
+    let color = Vec4::new(0.2, 0.5, 0.8, 0.5);
+    let back = linear_to_srgb_color(&srgb_to_linear_color(&color));
+    assert!((back - color).length() < 1e-5);
+    assert_eq!(srgb_to_linear_color(&color).w, 0.5);
+  }
+
+  #[test]
+  fn tone_mapping() {
+    let cases = [
+      (ToneMapping::None, 1.0),
+      (ToneMapping::Exposure, 1.0 - (-1f32).exp()),
+      (ToneMapping::Reinhard, 0.5),
+      (ToneMapping::AcesFilmic, 2.54 / 3.16),
+    ];
+    for (tone_mapping, at_one) in cases {
+      let map = |c: f32| tone_mapping.apply(&Vec3::new(c, c, c), 1.0).x;
+      assert_eq!(map(0.0), 0.0, "{tone_mapping:?}");
+      assert!((map(1.0) - at_one).abs() < 1e-5, "{tone_mapping:?}");
+      // the bright colors saturate, but never go past 1
+      assert!((map(1e4) - 1.0).abs() < 1e-3, "{tone_mapping:?}");
+      assert_eq!(map(-1.0), 0.0, "{tone_mapping:?}");
+    }
+
+    // the exposure scales the color first
+    let halved = ToneMapping::Reinhard.apply(&Vec3::new(2.0, 2.0, 2.0), 0.5);
+    assert!((halved.x - 0.5).abs() < 1e-5);
+  }
+}
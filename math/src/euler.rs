@@ -87,6 +87,25 @@ impl From<Mat4> for Euler {
       (m32.atan2(m22), 0.0)
     };
 
-    Self { x, y, z }
+    Self {
+      x: x.to_degrees(),
+      y: y.to_degrees(),
+      z: z.to_degrees(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn from_matrix_in_degrees() {
+    let euler = Euler::from(Euler::apply_eular_rotate_x(30f32.to_radians()));
+    assert!((euler.x - 30.0).abs() < 1e-3 && euler.y.abs() < 1e-3 && euler.z.abs() < 1e-3);
+    let euler = Euler::from(Euler::apply_eular_rotate_y(-45f32.to_radians()));
+    assert!((euler.y + 45.0).abs() < 1e-3 && euler.x.abs() < 1e-3 && euler.z.abs() < 1e-3);
+    let euler = Euler::from(Euler::apply_eular_rotate_z(60f32.to_radians()));
+    assert!((euler.z - 60.0).abs() < 1e-3 && euler.x.abs() < 1e-3 && euler.y.abs() < 1e-3);
   }
 }
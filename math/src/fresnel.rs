@@ -0,0 +1,11 @@
+/// Schlick's approximation of the Fresnel reflectance, `f0` being the
+/// reflectance at normal incidence.
+pub fn schlick(cosine: f32, f0: f32) -> f32 {
+  f0 + (1.0 - f0) * (1.0 - cosine).powi(5)
+}
+
+/// the reflectance of a dielectric of `refraction_index` in the air
+pub fn reflectance(cosine: f32, refraction_index: f32) -> f32 {
+  let r0 = (1.0 - refraction_index) / (1.0 + refraction_index);
+  schlick(cosine, r0 * r0)
+}
@@ -1,4 +1,4 @@
-use super::{Mat4, Vec3};
+use super::{BoundingBox, BoundingSphere, Mat4, Vec3};
 
 pub struct Frustum {
   near: f32,
@@ -61,3 +61,191 @@ impl Frustum {
       || pt.z <= -self.far)
   }
 }
+
+/// `normal · p + d`, positive on the inner side of the plane.
+#[derive(Debug, Clone, Copy)]
+pub struct Plane {
+  pub normal: Vec3,
+  pub d: f32,
+}
+
+impl Plane {
+  pub fn distance(&self, point: &Vec3) -> f32 {
+    self.normal.dot(point) + self.d
+  }
+}
+
+/// The planes of the view volume of any view-projection matrix, perspective
+/// or orthographic, in the space the matrix transforms from. A point is inside
+/// when `-w <= x, y, z <= w` in clip space.
+#[derive(Debug, Clone, Copy)]
+pub struct FrustumPlanes {
+  /// left, right, bottom, top, near, far
+  planes: [Plane; 6],
+}
+
+impl FrustumPlanes {
+  /// Gribb & Hartmann, every plane is the sum or the difference of the last
+  /// row of the matrix and one of the others.
+  pub fn from_matrix(matrix: &Mat4) -> Self {
+    let row = |y: usize| [0, 1, 2, 3].map(|x| matrix.get(x, y));
+    let w = row(3);
+    let plane = |r: [f32; 4], sign: f32| {
+      let normal = Vec3::new(
+        w[0] + sign * r[0],
+        w[1] + sign * r[1],
+        w[2] + sign * r[2],
+      );
+      let length = normal.length();
+      Plane {
+        normal: normal / length,
+        d: (w[3] + sign * r[3]) / length,
+      }
+    };
+
+    let (x, y, z) = (row(0), row(1), row(2));
+    Self {
+      planes: [
+        plane(x, 1.0),
+        plane(x, -1.0),
+        plane(y, 1.0),
+        plane(y, -1.0),
+        plane(z, 1.0),
+        plane(z, -1.0),
+      ],
+    }
+  }
+
+  pub fn get_planes(&self) -> &[Plane; 6] {
+    &self.planes
+  }
+
+  pub fn contains(&self, point: &Vec3) -> bool {
+    self.planes.iter().all(|p| p.distance(point) >= 0.0)
+  }
+
+  pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+    self
+      .planes
+      .iter()
+      .all(|p| p.distance(&sphere.center) >= -sphere.radius)
+  }
+
+  /// Conservative, a box next to an edge of the volume may pass while it is
+  /// outside.
+  pub fn intersects_box(&self, aabb: &BoundingBox) -> bool {
+    self.planes.iter().all(|p| {
+      // the corner furthest along the normal
+      let corner = Vec3::new(
+        if p.normal.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+        if p.normal.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+        if p.normal.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+      );
+      p.distance(&corner) >= 0.0
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::apply_translate;
+
+  /// 45 degrees on each side, from 1 to 100 units in front of the camera
+  fn perspective() -> FrustumPlanes {
+    let frustum = Frustum::new(1.0, 100.0, 1.0, 45f32.to_radians());
+    FrustumPlanes::from_matrix(frustum.get_projection_matrix())
+  }
+
+  /// the box 2 units on the left & right, 1 above & below, from 1 to 10 units
+  /// in front of the camera
+  #[rustfmt::skip]
+  fn orthographic() -> FrustumPlanes {
+    let (near, far) = (1.0, 10.0);
+    FrustumPlanes::from_matrix(&Mat4::from_row([
+      0.5, 0.0, 0.0,                0.0,
+      0.0, 1.0, 0.0,                0.0,
+      0.0, 0.0, 2.0 / (near - far), (far + near) / (near - far),
+      0.0, 0.0, 0.0,                1.0,
+    ]))
+  }
+
+  #[test]
+  fn perspective_planes() {
+    let planes = perspective();
+    assert!(planes.contains(&Vec3::new(0.0, 0.0, -10.0)));
+    assert!(planes.contains(&Vec3::new(9.0, -9.0, -10.0)));
+    assert!(!planes.contains(&Vec3::new(11.0, 0.0, -10.0)));
+    assert!(!planes.contains(&Vec3::new(0.0, 0.0, -0.5)));
+    assert!(!planes.contains(&Vec3::new(0.0, 0.0, -101.0)));
+    assert!(!planes.contains(&Vec3::new(0.0, 0.0, 10.0)));
+
+    // normalized, the distances are in world units
+    let [left, _, _, _, near, far] = planes.get_planes();
+    let p = Vec3::new(0.0, 0.0, -10.0);
+    assert!((left.distance(&p) - 10.0 / 2f32.sqrt()).abs() < 1e-3);
+    assert!((near.distance(&p) - 9.0).abs() < 1e-3);
+    assert!((far.distance(&p) - 90.0).abs() < 1e-2);
+  }
+
+  #[test]
+  fn orthographic_planes() {
+    let planes = orthographic();
+    assert!(planes.contains(&Vec3::new(1.9, 0.9, -9.9)));
+    assert!(!planes.contains(&Vec3::new(2.1, 0.0, -5.0)));
+    assert!(!planes.contains(&Vec3::new(0.0, -1.1, -5.0)));
+    assert!(!planes.contains(&Vec3::new(0.0, 0.0, -10.5)));
+
+    let [left, right, bottom, top, near, far] = planes.get_planes();
+    let p = Vec3::new(0.0, 0.0, -5.0);
+    let distances = [left, right, bottom, top, near, far].map(|plane| plane.distance(&p));
+    for (distance, expected) in distances.iter().zip([2.0, 2.0, 1.0, 1.0, 4.0, 5.0]) {
+      assert!((distance - expected).abs() < 1e-5, "{distances:?}");
+    }
+  }
+
+  #[test]
+  fn spheres() {
+    let planes = perspective();
+    let sphere = |x: f32, radius: f32| BoundingSphere {
+      center: Vec3::new(x, 0.0, -10.0),
+      radius,
+    };
+    assert!(planes.intersects_sphere(&sphere(0.0, 1.0)));
+    // the centre is 1 / sqrt(2) out of the right plane
+    assert!(planes.intersects_sphere(&sphere(11.0, 1.0)));
+    assert!(!planes.intersects_sphere(&sphere(11.0, 0.5)));
+    assert!(!planes.intersects_sphere(&sphere(20.0, 1.0)));
+  }
+
+  #[test]
+  fn boxes() {
+    let planes = orthographic();
+    let cases = [
+      // inside, across the left plane, around the whole volume
+      ((-1.0, -1.0, -6.0), (1.0, 1.0, -4.0), true),
+      ((-3.0, 0.0, -6.0), (-1.0, 1.0, -4.0), true),
+      ((-9.0, -9.0, -20.0), (9.0, 9.0, 0.0), true),
+      // behind the camera, beyond the top plane
+      ((-1.0, -1.0, 1.0), (1.0, 1.0, 2.0), false),
+      ((-1.0, 1.5, -6.0), (1.0, 2.0, -4.0), false),
+    ];
+    for (min, max, expected) in cases {
+      let aabb = BoundingBox {
+        min: Vec3::new(min.0, min.1, min.2),
+        max: Vec3::new(max.0, max.1, max.2),
+      };
+      assert_eq!(planes.intersects_box(&aabb), expected, "{aabb:?}");
+    }
+  }
+
+  #[test]
+  fn model_space() {
+    // the camera 5 units further back, the volume moves along
+    let frustum = Frustum::new(1.0, 100.0, 1.0, 45f32.to_radians());
+    let view = apply_translate(&Vec3::new(0.0, 0.0, -5.0));
+    let planes = FrustumPlanes::from_matrix(&(*frustum.get_projection_matrix() * view));
+    assert!(planes.contains(&Vec3::new(0.0, 0.0, 0.0)));
+    assert!(!planes.contains(&Vec3::new(0.0, 0.0, 4.5)));
+  }
+}
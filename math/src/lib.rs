@@ -6,9 +6,25 @@ pub use self::vec::*;
 pub use barycentric::Barycentric;
 mod boundary_box;
 pub use self::boundary_box::BoundaryBox;
+mod bounds;
+pub use self::bounds::{BoundingBox, BoundingSphere};
+mod rasterizer;
+pub use self::rasterizer::{Coverage, EdgeRasterizer, GUARD_BAND, SUBPIXEL_BITS};
+pub mod multisample;
+mod polygon;
+pub use self::polygon::{triangulate_face, triangulate_polygon};
+mod obj;
+pub use self::obj::{parse_obj_vertices, resolve_obj_index, ObjCounts, ObjVertex};
+mod normals;
+pub use self::normals::{generate_normals, NormalGeneration, NormalWeighting};
+mod tangents;
+pub use self::tangents::generate_tangents;
+pub mod clip;
+pub mod color;
 pub mod data_array;
 pub mod euler;
 pub mod frustum;
+pub mod fresnel;
 mod quaternion;
 pub use quaternion::*;
 mod rotate;
@@ -61,16 +61,16 @@ macro_rules! define_mat {
       }
 
       pub fn set_col(&mut self, col: usize, column: $t) {
-        $(
-          self.set(col, 0, column.$p);
-        )+
+        for (row, value) in [$(column.$p),+].into_iter().enumerate() {
+          self.set(col, row, value);
+        }
       }
 
       pub fn get_col(&self, col: usize) -> $t {
-        let mut res  = $t::zero();
-        $(
-          res.$p = self.get(col, 0);
-        )+
+        let mut res = $t::zero();
+        for (row, value) in [$(&mut res.$p),+].into_iter().enumerate() {
+          *value = self.get(col, row);
+        }
         res
       }
 
@@ -205,7 +205,7 @@ impl Mat3 {
       self.get(1, 0) * self.get(2, 1) - self.get(2, 0) * self.get(1, 1),
       self.get(2, 1) * self.get(0, 2) - self.get(0, 1) * self.get(2, 2),
       self.get(0, 0) * self.get(2, 2) - self.get(2, 0) * self.get(0, 2),
-      self.get(0, 1) * self.get(2, 1) - self.get(0, 0) * self.get(2, 0),
+      self.get(2, 0) * self.get(0, 1) - self.get(0, 0) * self.get(2, 1),
       self.get(0, 1) * self.get(1, 2) - self.get(1, 1) * self.get(0, 2),
       self.get(1, 0) * self.get(0, 2) - self.get(0, 0) * self.get(1, 2),
       self.get(0, 0) * self.get(1, 1) - self.get(1, 0) * self.get(0, 1),
@@ -286,6 +286,17 @@ impl Mat4 {
     self.inverse_transpose().map(|v| v.transpose())
   }
 
+  /// the upper left 3x3 block
+  pub fn truncated_to_mat3(&self) -> Mat3 {
+    let mut result = Mat3::zeros();
+    for x in 0..3 {
+      for y in 0..3 {
+        result.set(x, y, self.get(x, y));
+      }
+    }
+    result
+  }
+
   pub fn compose(position: Vec3, rotation: Vec3, scale: Vec3) -> Self {
     let mut result = Self::identity();
     result =
@@ -294,6 +305,38 @@ impl Mat4 {
   }
 }
 
+/// the translation of an affine matrix
+pub fn extract_position(matrix: Mat4) -> Vec3 {
+  matrix.get_col(3).truncated_to_vec3()
+}
+
+/// Splits an affine matrix into its translation, rotation and scale, the
+/// rotation being returned as a matrix. A mirroring is put into the x scale.
+pub fn decompose(matrix: Mat4) -> (Vec3, Mat4, Vec3) {
+  let axis = |col: usize| matrix.get_col(col).truncated_to_vec3();
+  let mut scale = Vec3::new(axis(0).length(), axis(1).length(), axis(2).length());
+  if matrix.truncated_to_mat3().det() < 0.0 {
+    scale.x = -scale.x;
+  }
+
+  let mut rotation = Mat4::identity();
+  for (col, s) in [scale.x, scale.y, scale.z].into_iter().enumerate() {
+    let column = if s != 0.0 { axis(col) / s } else { axis(col) };
+    rotation.set_col(col, Vec4::from_vec3(&column, 0.0));
+  }
+
+  (extract_position(matrix), rotation, scale)
+}
+
+/// The matrix taking the normals along with the positions transformed by
+/// `matrix`, the inverse transpose of its upper 3x3 block.
+pub fn extract_normal_matrix(matrix: Mat4) -> Mat3 {
+  matrix
+    .truncated_to_mat3()
+    .inverse()
+    .map_or(Mat3::identity(), |m| m.transpose())
+}
+
 #[rustfmt::skip]
 pub fn apply_translate(offset: &Vec3) -> Mat4 {
   Mat4::from_row([
@@ -356,3 +399,48 @@ pub fn apply_eular_rotate_xyz(rotation: &Vec3) -> Mat4 {
     * apply_eular_rotate_y(rotation.y)
     * apply_eular_rotate_x(rotation.x)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_mat_near<const N: usize>(a: [f32; N], b: [f32; N]) {
+    for (a, b) in a.iter().zip(b) {
+      assert!((a - b).abs() < 1e-5, "{a:?} != {b:?}");
+    }
+  }
+
+  #[test]
+  fn col_round_trip() {
+    let mut m = Mat4::zeros();
+    let column = Vec4::new(1.0, 2.0, 3.0, 4.0);
+    m.set_col(2, column);
+    assert_eq!(m.get_col(2), column);
+    assert_eq!((m.get(2, 0), m.get(2, 3)), (1.0, 4.0));
+    assert_eq!(m.get_col(1), Vec4::zero());
+
+    let mut m = Mat3::identity();
+    m.set_col(0, Vec3::new(5.0, 6.0, 7.0));
+    assert_eq!(m.get_col(0), Vec3::new(5.0, 6.0, 7.0));
+    assert_eq!(m.get_col(1), Vec3::new(0.0, 1.0, 0.0));
+  }
+
+  #[test]
+  #[rustfmt::skip]
+  fn inverse() {
+    let m = Mat3::from_row([
+      2.0, 1.0, 0.0,
+      0.0, 3.0, 4.0,
+      5.0, 0.0, 1.0,
+    ]);
+    assert_mat_near((m * m.inverse().unwrap()).data, Mat3::identity().data);
+    assert_mat_near((m.inverse().unwrap() * m).data, Mat3::identity().data);
+
+    let m = apply_translate(&Vec3::new(1.0, -2.0, 3.0))
+      * apply_eular_rotate_xyz(&Vec3::new(0.3, -1.2, 2.0))
+      * apply_scale(&Vec3::new(2.0, 0.5, 3.0));
+    assert_mat_near((m * m.inverse().unwrap()).data, Mat4::identity().data);
+
+    assert!(Mat3::from_row([1.0, 2.0, 3.0, 2.0, 4.0, 6.0, 0.0, 1.0, 0.0]).inverse().is_none());
+  }
+}
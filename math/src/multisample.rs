@@ -0,0 +1,214 @@
+use super::Vec4;
+
+pub const MAX_SAMPLES: usize = 8;
+
+/// Rotated grid patterns, offsets from the top left corner of the pixel.
+const PIXEL_CENTRE: [(f32, f32); 1] = [(0.5, 0.5)];
+const PATTERN_2X: [(f32, f32); 2] = [(0.75, 0.75), (0.25, 0.25)];
+const PATTERN_4X: [(f32, f32); 4] = [
+  (0.375, 0.125),
+  (0.875, 0.375),
+  (0.125, 0.625),
+  (0.625, 0.875),
+];
+const PATTERN_8X: [(f32, f32); 8] = [
+  (0.5625, 0.3125),
+  (0.4375, 0.6875),
+  (0.8125, 0.5625),
+  (0.3125, 0.1875),
+  (0.1875, 0.8125),
+  (0.0625, 0.4375),
+  (0.6875, 0.9375),
+  (0.9375, 0.0625),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleCount {
+  X2,
+  X4,
+  X8,
+}
+
+impl SampleCount {
+  pub fn count(&self) -> usize {
+    self.positions().len()
+  }
+
+  pub fn positions(&self) -> &'static [(f32, f32)] {
+    match self {
+      SampleCount::X2 => &PATTERN_2X,
+      SampleCount::X4 => &PATTERN_4X,
+      SampleCount::X8 => &PATTERN_8X,
+    }
+  }
+}
+
+/// `Msaa` shades once per pixel and keeps the coverage and the depth per
+/// sample, `Ssaa` shades every sample.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Antialiasing {
+  #[default]
+  None,
+  Msaa(SampleCount),
+  Ssaa(SampleCount),
+}
+
+impl Antialiasing {
+  /// a single sample at the pixel centre without antialiasing
+  pub fn sample_positions(&self) -> &'static [(f32, f32)] {
+    match self {
+      Antialiasing::None => &PIXEL_CENTRE,
+      Antialiasing::Msaa(count) | Antialiasing::Ssaa(count) => count.positions(),
+    }
+  }
+
+  pub fn sample_count(&self) -> usize {
+    self.sample_positions().len()
+  }
+
+  pub fn shade_per_sample(&self) -> bool {
+    !matches!(self, Antialiasing::Msaa(_))
+  }
+}
+
+/// Color and depth of every sample, the samples of a pixel sit next to each
+/// other. `resolve` averages them down to one color per pixel.
+#[derive(Debug, Default)]
+pub struct SampleBuffer {
+  w: u32,
+  h: u32,
+  samples: usize,
+  color: Vec<Vec4>,
+  depth: Vec<f32>,
+}
+
+impl SampleBuffer {
+  pub fn new(w: u32, h: u32, samples: usize) -> Self {
+    let len = (w * h) as usize * samples;
+    Self {
+      w,
+      h,
+      samples,
+      color: vec![Vec4::zero(); len],
+      depth: vec![0.0; len],
+    }
+  }
+
+  pub fn width(&self) -> u32 {
+    self.w
+  }
+
+  pub fn height(&self) -> u32 {
+    self.h
+  }
+
+  pub fn samples(&self) -> usize {
+    self.samples
+  }
+
+  pub fn clear(&mut self, color: &Vec4, depth: f32) {
+    self.color.fill(*color);
+    self.depth.fill(depth);
+  }
+
+  /// Splits the buffer into bands of `rows` rows, see `SampleRows`.
+  pub fn split_rows_mut(&mut self, rows: u32) -> Vec<SampleRows<'_>> {
+    let (w, samples) = (self.w, self.samples);
+    let chunk = (w * rows.max(1)) as usize * samples;
+    if chunk == 0 {
+      return vec![];
+    }
+
+    self
+      .color
+      .chunks_mut(chunk)
+      .zip(self.depth.chunks_mut(chunk))
+      .enumerate()
+      .map(|(i, (color, depth))| {
+        let y_min = i as u32 * rows.max(1);
+        let y_max = y_min + (color.len() / (w as usize * samples)) as u32;
+        SampleRows {
+          color,
+          depth,
+          w,
+          samples,
+          y_min,
+          y_max,
+        }
+      })
+      .collect()
+  }
+
+  /// the whole buffer as a single band
+  pub fn as_rows_mut(&mut self) -> SampleRows<'_> {
+    let (w, h, samples) = (self.w, self.h, self.samples);
+    SampleRows {
+      color: &mut self.color,
+      depth: &mut self.depth,
+      w,
+      samples,
+      y_min: 0,
+      y_max: h,
+    }
+  }
+
+  /// Box filters the samples of every pixel, `f` receives the final color.
+  pub fn resolve(&self, mut f: impl FnMut(u32, u32, Vec4)) {
+    let samples = self.samples.max(1);
+    for (i, pixel) in self.color.chunks(samples).enumerate() {
+      let sum = pixel.iter().fold(Vec4::zero(), |sum, c| sum + *c);
+      let (x, y) = (i as u32 % self.w, i as u32 / self.w);
+      f(x, y, sum / samples as f32);
+    }
+  }
+}
+
+/// A band of rows `[y_min, y_max)` borrowed from a `SampleBuffer`, addressed
+/// with the coordinates of the whole buffer.
+pub struct SampleRows<'a> {
+  color: &'a mut [Vec4],
+  depth: &'a mut [f32],
+  w: u32,
+  samples: usize,
+  y_min: u32,
+  y_max: u32,
+}
+
+impl<'a> SampleRows<'a> {
+  pub fn y_min(&self) -> u32 {
+    self.y_min
+  }
+
+  /// exclusive
+  pub fn y_max(&self) -> u32 {
+    self.y_max
+  }
+
+  fn offset(&self, x: u32, y: u32, sample: usize) -> usize {
+    (x + (y - self.y_min) * self.w) as usize * self.samples + sample
+  }
+
+  pub fn get_color(&self, x: u32, y: u32, sample: usize) -> Vec4 {
+    self.color[self.offset(x, y, sample)]
+  }
+
+  /// the color is linear and may go past 1, only the alpha is clamped
+  pub fn set_color(&mut self, x: u32, y: u32, sample: usize, color: &Vec4) {
+    let p = self.offset(x, y, sample);
+    self.color[p] = Vec4::new(
+      color.x.max(0.0),
+      color.y.max(0.0),
+      color.z.max(0.0),
+      color.w.clamp(0.0, 1.0),
+    );
+  }
+
+  pub fn get_depth(&self, x: u32, y: u32, sample: usize) -> f32 {
+    self.depth[self.offset(x, y, sample)]
+  }
+
+  pub fn set_depth(&mut self, x: u32, y: u32, sample: usize, depth: f32) {
+    let p = self.offset(x, y, sample);
+    self.depth[p] = depth;
+  }
+}
@@ -0,0 +1,185 @@
+use super::Vec3;
+
+/// How the normals of the faces around a vertex are averaged.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NormalWeighting {
+  /// by the area of the faces
+  Area,
+  /// by the angle of the faces at the vertex, independent of the tessellation
+  #[default]
+  Angle,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct NormalGeneration {
+  pub weighting: NormalWeighting,
+  /// faces meeting at a sharper angle than this, in radians, keep a hard edge
+  pub crease_angle: f32,
+}
+
+impl Default for NormalGeneration {
+  fn default() -> Self {
+    Self {
+      weighting: NormalWeighting::default(),
+      crease_angle: 60f32.to_radians(),
+    }
+  }
+}
+
+/// Generates a normal for every corner of the `triangles`, which index into
+/// `positions`.
+///
+/// The faces sharing a position are averaged when they are in the same non
+/// zero smoothing group and their normals are within the crease angle of each
+/// other, the faces of the group `0` are flat. `smoothing_groups` has one entry
+/// per triangle, an empty slice puts all of them in the same group.
+pub fn generate_normals(
+  positions: &[Vec3],
+  triangles: &[[usize; 3]],
+  smoothing_groups: &[u32],
+  options: &NormalGeneration,
+) -> Vec<[Vec3; 3]> {
+  // zero for the degenerate faces & edges, rather than NaN
+  let unit = |v: Vec3| {
+    if v.length_square() > 0.0 {
+      v.normalize()
+    } else {
+      Vec3::zero()
+    }
+  };
+  let group = |face: usize| smoothing_groups.get(face).copied().unwrap_or(1);
+
+  // the length of the cross product is twice the area
+  let face_normals: Vec<Vec3> = triangles
+    .iter()
+    .map(|[a, b, c]| (positions[*b] - positions[*a]).cross(&(positions[*c] - positions[*a])))
+    .collect();
+
+  let mut corners_at = vec![vec![]; positions.len()];
+  for (face, triangle) in triangles.iter().enumerate() {
+    for (corner, position) in triangle.iter().enumerate() {
+      corners_at[*position].push((face, corner));
+    }
+  }
+
+  let weight = |face: usize, corner: usize| -> Vec3 {
+    let normal = face_normals[face];
+    match options.weighting {
+      NormalWeighting::Area => normal,
+      NormalWeighting::Angle => {
+        let triangle = &triangles[face];
+        let p = positions[triangle[corner]];
+        let u = unit(positions[triangle[(corner + 1) % 3]] - p);
+        let v = unit(positions[triangle[(corner + 2) % 3]] - p);
+        unit(normal) * u.dot(&v).clamp(-1.0, 1.0).acos()
+      }
+    }
+  };
+
+  let cos_crease = options.crease_angle.cos();
+  triangles
+    .iter()
+    .enumerate()
+    .map(|(face, triangle)| {
+      let flat = unit(face_normals[face]);
+      if group(face) == 0 {
+        return [flat; 3];
+      }
+
+      triangle.map(|position| {
+        let sum = corners_at[position]
+          .iter()
+          .filter(|(other, _)| {
+            *other == face
+              || (group(*other) == group(face)
+                && unit(face_normals[*other]).dot(&flat) >= cos_crease)
+          })
+          .fold(Vec3::zero(), |sum, (other, corner)| {
+            sum + weight(*other, *corner)
+          });
+
+        match unit(sum) {
+          sum if sum.length_square() > 0.0 => sum,
+          _ => flat,
+        }
+      })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// two faces folded at a right angle along the edge 0-1, facing +z and +y
+  fn fold() -> (Vec<Vec3>, Vec<[usize; 3]>) {
+    let positions = vec![
+      Vec3::new(0.0, 0.0, 0.0),
+      Vec3::new(1.0, 0.0, 0.0),
+      Vec3::new(0.0, 1.0, 0.0),
+      Vec3::new(0.0, 0.0, 1.0),
+    ];
+    (positions, vec![[0, 1, 2], [0, 3, 1]])
+  }
+
+  fn assert_near(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-5, "{a:?} != {b:?}");
+  }
+
+  fn with_crease(degrees: f32) -> NormalGeneration {
+    NormalGeneration {
+      crease_angle: degrees.to_radians(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn crease_angle() {
+    let (positions, triangles) = fold();
+    let (z, y) = (Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 0.0));
+
+    // sharper than the crease angle, a hard edge
+    let normals = generate_normals(&positions, &triangles, &[], &with_crease(60.0));
+    assert!(normals[0].iter().all(|n| (*n - z).length() < 1e-5));
+    assert!(normals[1].iter().all(|n| (*n - y).length() < 1e-5));
+
+    // within it, the corners on the edge are averaged, the others stay flat
+    let normals = generate_normals(&positions, &triangles, &[], &with_crease(120.0));
+    let smooth = (y + z).normalize();
+    assert_near(normals[0][0], smooth);
+    assert_near(normals[0][1], smooth);
+    assert_near(normals[0][2], z);
+    assert_near(normals[1][0], smooth);
+    assert_near(normals[1][1], y);
+    assert_near(normals[1][2], smooth);
+  }
+
+  #[test]
+  fn smoothing_groups() {
+    let (positions, triangles) = fold();
+    let (z, y) = (Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 0.0));
+    let smooth = (y + z).normalize();
+    let options = with_crease(120.0);
+
+    let normals = generate_normals(&positions, &triangles, &[1, 1], &options);
+    assert_near(normals[0][0], smooth);
+
+    // different groups, or the group 0, do not blend
+    for groups in [[1, 2], [0, 1], [0, 0]] {
+      let normals = generate_normals(&positions, &triangles, &groups, &options);
+      assert_near(normals[0][0], z);
+      assert_near(normals[1][0], y);
+    }
+  }
+
+  #[test]
+  fn degenerate_face() {
+    let positions = [
+      Vec3::zero(),
+      Vec3::new(1.0, 0.0, 0.0),
+      Vec3::new(2.0, 0.0, 0.0),
+    ];
+    let normals = generate_normals(&positions, &[[0, 1, 2]], &[], &Default::default());
+    assert!(normals[0].iter().all(|n| *n == Vec3::zero()));
+  }
+}
@@ -0,0 +1,128 @@
+/// The indices of a vertex of an OBJ `f`, `l` or `p` element, from 0, the
+/// relative ones resolved by `parse_obj_vertices`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjVertex {
+  pub position: u32,
+  pub uv: Option<u32>,
+  pub normal: Option<u32>,
+}
+
+/// How many `v`, `vt` and `vn` were defined before the element, the negative
+/// indices count back from them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ObjCounts {
+  pub positions: usize,
+  pub uvs: usize,
+  pub normals: usize,
+}
+
+/// OBJ indices start at 1, the negative ones count back from the last
+/// element defined so far.
+pub fn resolve_obj_index(token: &str, count: usize) -> Result<u32, String> {
+  let invalid = || format!("vertex index {}", token);
+  let index = token.parse::<i64>().map_err(|_| invalid())?;
+  let resolved = match index {
+    i if i > 0 => i - 1,
+    i if i < 0 => count as i64 + i,
+    _ => -1,
+  };
+
+  if resolved < 0 {
+    return Err(invalid());
+  }
+  Ok(resolved as u32)
+}
+
+/// The `v`, `v/vt`, `v/vt/vn` or `v//vn` tokens of an element, the error
+/// describes the faulty token.
+pub fn parse_obj_vertices<'a>(
+  tokens: impl Iterator<Item = &'a str>,
+  counts: ObjCounts,
+) -> Result<Vec<ObjVertex>, String> {
+  let mut vertices = vec![];
+
+  for token in tokens {
+    let splited: Vec<&str> = token.split('/').collect();
+    let (mut uv, mut normal) = (None, None);
+
+    match splited[..] {
+      [_, second, third] => {
+        if !second.is_empty() {
+          uv = Some(resolve_obj_index(second, counts.uvs)?);
+        }
+        normal = Some(resolve_obj_index(third, counts.normals)?);
+      }
+      [_, second] => {
+        uv = Some(resolve_obj_index(second, counts.uvs)?);
+      }
+      [_] => {}
+      _ => return Err(format!("vertex format {}", token)),
+    }
+
+    let position = resolve_obj_index(splited[0], counts.positions)?;
+    vertices.push(ObjVertex {
+      position,
+      uv,
+      normal,
+    });
+  }
+
+  Ok(vertices)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const COUNTS: ObjCounts = ObjCounts {
+    positions: 8,
+    uvs: 4,
+    normals: 6,
+  };
+
+  fn parse(line: &str) -> Result<Vec<ObjVertex>, String> {
+    parse_obj_vertices(line.split_whitespace(), COUNTS)
+  }
+
+  #[test]
+  fn absolute_and_relative_indices() {
+    assert_eq!(resolve_obj_index("1", 8), Ok(0));
+    assert_eq!(resolve_obj_index("8", 8), Ok(7));
+    assert_eq!(resolve_obj_index("-1", 8), Ok(7));
+    assert_eq!(resolve_obj_index("-8", 8), Ok(0));
+
+    assert!(resolve_obj_index("0", 8).is_err());
+    assert!(resolve_obj_index("-9", 8).is_err());
+    assert!(resolve_obj_index("x", 8).is_err());
+  }
+
+  #[test]
+  fn vertex_formats() {
+    let vertex = |position, uv, normal| ObjVertex {
+      position,
+      uv,
+      normal,
+    };
+
+    assert_eq!(
+      parse("1 -1 2").unwrap(),
+      [
+        vertex(0, None, None),
+        vertex(7, None, None),
+        vertex(1, None, None)
+      ]
+    );
+    assert_eq!(
+      parse("-4/-4 -3/-1").unwrap(),
+      [vertex(4, Some(0), None), vertex(5, Some(3), None)]
+    );
+    assert_eq!(
+      parse("1/2/3 -1//-1").unwrap(),
+      [vertex(0, Some(1), Some(2)), vertex(7, None, Some(5))]
+    );
+
+    assert!(parse("1/2/3/4").is_err());
+    assert!(parse("1 -9").is_err());
+    assert!(parse("1//0").is_err());
+  }
+}
@@ -0,0 +1,195 @@
+use super::{Vec2, Vec3};
+
+/// Splits a simple polygon into triangles by ear clipping, the result indexes
+/// into `points` and keeps their winding. The polygon is projected onto the
+/// plane of its Newell normal, so it may be slightly non planar. Degenerate
+/// or self intersecting polygons, where no ear can be found, fall back to a fan.
+pub fn triangulate_polygon(points: &[Vec3]) -> Vec<[usize; 3]> {
+  let n = points.len();
+  if n < 3 {
+    return vec![];
+  }
+  if n == 3 {
+    return vec![[0, 1, 2]];
+  }
+
+  let mut normal = Vec3::zero();
+  for (i, a) in points.iter().enumerate() {
+    let b = points[(i + 1) % n];
+    normal.x += (a.y - b.y) * (a.z + b.z);
+    normal.y += (a.z - b.z) * (a.x + b.x);
+    normal.z += (a.x - b.x) * (a.y + b.y);
+  }
+
+  // drop the dominant axis of the normal
+  let (nx, ny, nz) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+  let projected: Vec<Vec2> = points
+    .iter()
+    .map(|p| {
+      if nx >= ny && nx >= nz {
+        Vec2::new(p.y, p.z)
+      } else if ny >= nz {
+        Vec2::new(p.z, p.x)
+      } else {
+        Vec2::new(p.x, p.y)
+      }
+    })
+    .collect();
+
+  let mut remaining: Vec<usize> = (0..n).collect();
+  let mut triangles = Vec::with_capacity(n - 2);
+
+  let area: f32 = (0..n)
+    .map(|i| projected[i].cross(&projected[(i + 1) % n]))
+    .sum();
+  if area.abs() <= f32::EPSILON {
+    return fan(&remaining);
+  }
+  let orientation = area.signum();
+
+  while remaining.len() > 3 {
+    let len = remaining.len();
+    let ear = (0..len).find(|&i| {
+      let prev = remaining[(i + len - 1) % len];
+      let cur = remaining[i];
+      let next = remaining[(i + 1) % len];
+      let [a, b, c] = [projected[prev], projected[cur], projected[next]];
+
+      let convex = (b - a).cross(&(c - b)) * orientation > 0.0;
+      convex
+        && remaining
+          .iter()
+          .filter(|&&j| j != prev && j != cur && j != next)
+          .all(|&j| !in_triangle(&projected[j], &a, &b, &c))
+    });
+
+    match ear {
+      Some(i) => {
+        let prev = remaining[(i + len - 1) % len];
+        let next = remaining[(i + 1) % len];
+        triangles.push([prev, remaining[i], next]);
+        remaining.remove(i);
+      }
+      None => {
+        triangles.extend(fan(&remaining));
+        return triangles;
+      }
+    }
+  }
+
+  triangles.push([remaining[0], remaining[1], remaining[2]]);
+  triangles
+}
+
+/// `triangulate_polygon` of the `points` at `indices`, a face given by the
+/// indices of its vertices. A fan is kept when an index is out of `points`,
+/// the positions not being parsed yet.
+pub fn triangulate_face(points: &[Vec3], indices: &[usize]) -> Vec<[usize; 3]> {
+  let positions: Option<Vec<Vec3>> = indices.iter().map(|&i| points.get(i).copied()).collect();
+  match positions {
+    Some(positions) => triangulate_polygon(&positions),
+    None if indices.len() >= 3 => fan(&(0..indices.len()).collect::<Vec<_>>()),
+    None => vec![],
+  }
+}
+
+fn fan(indices: &[usize]) -> Vec<[usize; 3]> {
+  (1..indices.len() - 1)
+    .map(|i| [indices[0], indices[i], indices[i + 1]])
+    .collect()
+}
+
+/// on the edges counts as inside, for either winding
+fn in_triangle(p: &Vec2, a: &Vec2, b: &Vec2, c: &Vec2) -> bool {
+  let d0 = (*b - *a).cross(&(*p - *a));
+  let d1 = (*c - *b).cross(&(*p - *b));
+  let d2 = (*a - *c).cross(&(*p - *c));
+
+  let has_negative = d0 < 0.0 || d1 < 0.0 || d2 < 0.0;
+  let has_positive = d0 > 0.0 || d1 > 0.0 || d2 > 0.0;
+  !(has_negative && has_positive)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// twice the signed area of the triangles, projected onto the xy plane
+  fn area(points: &[Vec3], triangles: &[[usize; 3]]) -> Vec<f32> {
+    triangles
+      .iter()
+      .map(|&[a, b, c]| {
+        let (a, b, c) = (points[a], points[b], points[c]);
+        Vec2::new(b.x - a.x, b.y - a.y).cross(&Vec2::new(c.x - a.x, c.y - a.y))
+      })
+      .collect()
+  }
+
+  #[test]
+  fn concave_polygon() {
+    // an arrow pointing up, counter clockwise, whose notch at 4 is concave
+    let points = [
+      Vec3::new(0.0, 0.0, 0.0),
+      Vec3::new(2.0, 2.0, 0.0),
+      Vec3::new(4.0, 0.0, 0.0),
+      Vec3::new(4.0, 4.0, 0.0),
+      Vec3::new(2.0, 6.0, 0.0),
+      Vec3::new(0.0, 4.0, 0.0),
+    ];
+    let triangles = triangulate_polygon(&points);
+    assert_eq!(triangles.len(), points.len() - 2);
+
+    // the same winding, and the area of the polygon
+    let areas = area(&points, &triangles);
+    assert!(areas.iter().all(|a| *a > 0.0), "{triangles:?}");
+    assert!((areas.iter().sum::<f32>() - 2.0 * 16.0).abs() < 1e-4);
+
+    // clockwise in, clockwise out
+    let reversed: Vec<Vec3> = points.iter().rev().copied().collect();
+    let triangles = triangulate_polygon(&reversed);
+    assert_eq!(triangles.len(), points.len() - 2);
+    assert!(area(&reversed, &triangles).iter().all(|a| *a < 0.0));
+  }
+
+  #[test]
+  fn non_axis_aligned_polygon() {
+    // an L shape, counter clockwise seen from +z, in the x = z plane
+    let uvs = [
+      (0.0, 0.0),
+      (2.0, 0.0),
+      (2.0, 1.0),
+      (1.0, 1.0),
+      (1.0, 3.0),
+      (0.0, 3.0),
+    ];
+    let points: Vec<Vec3> = uvs.iter().map(|&(u, v)| Vec3::new(u, v, u)).collect();
+    let triangles = triangulate_polygon(&points);
+    assert_eq!(triangles.len(), 4);
+
+    let areas = area(&points, &triangles);
+    assert!(areas.iter().all(|a| *a > 0.0), "{triangles:?}");
+    // 4 unit squares, none of the triangles covers the missing corner
+    assert!((areas.iter().sum::<f32>() - 2.0 * 4.0).abs() < 1e-4);
+  }
+
+  #[test]
+  fn face_indices() {
+    let points = [
+      Vec3::new(0.0, 0.0, 0.0),
+      Vec3::new(1.0, 0.0, 0.0),
+      Vec3::new(1.0, 1.0, 0.0),
+      Vec3::new(0.0, 1.0, 0.0),
+    ];
+    // the triangles index into the face, not into the points
+    let triangles = triangulate_face(&points, &[3, 2, 1, 0]);
+    assert_eq!(triangles.len(), 2);
+    assert!(triangles.iter().flatten().all(|&i| i < 4));
+
+    // the fan of a face whose positions are unknown
+    assert_eq!(
+      triangulate_face(&points, &[0, 1, 7, 2]),
+      [[0, 1, 2], [0, 2, 3]]
+    );
+    assert!(triangulate_face(&points, &[0, 9]).is_empty());
+  }
+}
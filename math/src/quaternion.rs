@@ -1,7 +1,7 @@
 use std::ops::{Div, Mul};
 
 use super::{Mat4, Vec3, Vec4};
-#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Quaternion {
   /// q = w + xi + yj + zk
   pub(super) w: f32,
@@ -10,6 +10,13 @@ pub struct Quaternion {
   pub(super) z: f32,
 }
 
+/// the identity, a zero quaternion does not rotate
+impl Default for Quaternion {
+  fn default() -> Self {
+    Self::identity()
+  }
+}
+
 impl Mul<f32> for Quaternion {
   type Output = Self;
 
@@ -69,7 +76,7 @@ impl Quaternion {
   }
 
   pub fn conjugate(&self) -> Self {
-    Self::new(-self.x, -self.y, -self.z, self.w)
+    Self::new(self.w, -self.x, -self.y, -self.z)
   }
 
   pub fn inverse(&self) -> Self {
@@ -97,7 +104,7 @@ impl Quaternion {
 
     res.set_col(0, Vec4::new(1.0 - (yy + zz), xy + wz, xz - wy, 0.0));
     res.set_col(1, Vec4::new(xy - wz, 1.0 - (xx + zz), yz + wx, 0.0));
-    res.set_col(2, Vec4::new(xz + wy, yz - wx, xx + yy, 0.0));
+    res.set_col(2, Vec4::new(xz + wy, yz - wx, 1.0 - (xx + yy), 0.0));
 
     res
   }
@@ -162,3 +169,43 @@ impl From<Mat4> for Quaternion {
     Self { w, x, y, z }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::apply_eular_rotate_z;
+
+  #[test]
+  fn identity() {
+    assert_eq!(Quaternion::default(), Quaternion::identity());
+    assert_eq!(Quaternion::default().make_rotate_matrix(), Mat4::identity());
+  }
+
+  #[test]
+  fn conjugate() {
+    let q = Quaternion::from_axis_angle(Vec3::new(1.0, 2.0, 3.0), 0.7);
+    let c = q.conjugate();
+    assert_eq!((c.w, c.x, c.y, c.z), (q.w, -q.x, -q.y, -q.z));
+
+    let p = q * q.inverse();
+    assert!((p.w - 1.0).abs() < 1e-6);
+    assert!(p.x.abs() < 1e-6 && p.y.abs() < 1e-6 && p.z.abs() < 1e-6);
+  }
+
+  #[test]
+  fn rotate_matrix() {
+    let angle = std::f32::consts::FRAC_PI_3;
+    let m = Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), angle).make_rotate_matrix();
+    let expected = apply_eular_rotate_z(angle);
+    for col in 0..4 {
+      let (a, b) = (m.get_col(col), expected.get_col(col));
+      assert!((a - b).length() < 1e-6, "{a:?} != {b:?}");
+    }
+
+    // a quarter turn around x takes y to z
+    let m = Quaternion::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), std::f32::consts::FRAC_PI_2)
+      .make_rotate_matrix();
+    let v = m * Vec4::new(0.0, 1.0, 0.0, 0.0);
+    assert!((v - Vec4::new(0.0, 0.0, 1.0, 0.0)).length() < 1e-6);
+  }
+}
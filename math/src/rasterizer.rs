@@ -0,0 +1,353 @@
+use std::ops::Range;
+
+use super::{multisample::MAX_SAMPLES, Barycentric, Vec2};
+
+/// Bits of sub-pixel precision the vertices are snapped to, 1/256 of a pixel.
+pub const SUBPIXEL_BITS: u32 = 8;
+const ONE: i64 = 1 << SUBPIXEL_BITS;
+const HALF: i64 = ONE / 2;
+
+/// The vertices lie within this many pixels around the origin, so that the
+/// edge functions, products of two snapped coordinates, fit in an `i64`.
+/// Larger triangles are to be clipped first.
+pub const GUARD_BAND: f32 = (1 << 20) as f32;
+
+fn snap(v: &Vec2) -> [i64; 2] {
+  [
+    (v.x * ONE as f32).round() as i64,
+    (v.y * ONE as f32).round() as i64,
+  ]
+}
+
+/// `E(p) = a * p.x + b * p.y + c`, positive on the inner side of the edge.
+/// Everything is in fixed point, `E` in squared sub-pixel units.
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+  a: i64,
+  b: i64,
+  c: i64,
+  /// `0` for the top and left edges, `-1` otherwise, so a sample exactly on
+  /// an edge shared by two triangles is owned by one of them only
+  bias: i64,
+}
+
+impl Edge {
+  fn new(from: [i64; 2], to: [i64; 2]) -> Self {
+    let a = from[1] - to[1];
+    let b = to[0] - from[0];
+    let c = from[0] * to[1] - from[1] * to[0];
+
+    // y points down on screen and the triangle turns clockwise, so a top edge
+    // is horizontal and goes right, a left edge goes up
+    let is_top = a == 0 && b > 0;
+    let is_left = a > 0;
+    let bias = if is_top || is_left { 0 } else { -1 };
+
+    Self { a, b, c, bias }
+  }
+
+  fn eval(&self, x: i64, y: i64) -> i64 {
+    self.a * x + self.b * y + self.c
+  }
+}
+
+/// The samples of a pixel covered by a triangle, with the barycentric weights
+/// at each of them and at the pixel centre.
+#[derive(Debug, Clone, Copy)]
+pub struct Coverage {
+  mask: u32,
+  centre: Barycentric,
+  samples: [Barycentric; MAX_SAMPLES],
+}
+
+impl Coverage {
+  /// bit `i` is set when the sample `i` is covered
+  pub fn mask(&self) -> u32 {
+    self.mask
+  }
+
+  pub fn is_covered(&self, sample: usize) -> bool {
+    self.mask & (1 << sample) != 0
+  }
+
+  /// the weights at the pixel centre, extrapolated when the centre itself
+  /// is not covered
+  pub fn centre(&self) -> &Barycentric {
+    &self.centre
+  }
+
+  pub fn sample(&self, sample: usize) -> &Barycentric {
+    &self.samples[sample]
+  }
+}
+
+/// Scan converts a screen space triangle with incremental edge functions.
+///
+/// The vertices are snapped to `SUBPIXEL_BITS` of fixed point precision and
+/// the pixels are sampled at their centres, `(x + 0.5, y + 0.5)`, or at the
+/// given sample positions for the multisampled targets. Samples on an edge
+/// follow the top-left rule, so the triangles sharing an edge cover every
+/// sample along it exactly once.
+#[derive(Debug, Clone)]
+pub struct EdgeRasterizer {
+  edges: [Edge; 3],
+  /// twice the area in fixed point, always positive
+  area: i64,
+  /// the vertex order was flipped to make the triangle turn clockwise
+  flipped: bool,
+  x_range: Range<u32>,
+  y_range: Range<u32>,
+}
+
+impl EdgeRasterizer {
+  /// `None` for the degenerate triangles, whose area snaps to zero, for the
+  /// ones outside of the `width` x `height` screen, and for the ones reaching
+  /// beyond the `GUARD_BAND`.
+  pub fn new(vertices: &[Vec2; 3], width: u32, height: u32) -> Option<Self> {
+    let in_guard_band = |v: &Vec2| v.x.abs() <= GUARD_BAND && v.y.abs() <= GUARD_BAND;
+    if !vertices.iter().all(in_guard_band) {
+      return None;
+    }
+
+    let [p0, mut p1, mut p2] = vertices.map(|v| snap(&v));
+
+    let area = (p1[0] - p0[0]) * (p2[1] - p0[1]) - (p1[1] - p0[1]) * (p2[0] - p0[0]);
+    if area == 0 {
+      return None;
+    }
+    let flipped = area < 0;
+    if flipped {
+      std::mem::swap(&mut p1, &mut p2);
+    }
+
+    // the first & last pixel overlapping the snapped bounds, no sample sits
+    // on the left or top border of a pixel
+    let first = |min: i64| min.div_euclid(ONE).max(0);
+    let last = |max: i64, size: u32| (max - 1).div_euclid(ONE).min(size as i64 - 1);
+
+    let xs = [p0[0], p1[0], p2[0]];
+    let ys = [p0[1], p1[1], p2[1]];
+    let x_min = first(*xs.iter().min().unwrap());
+    let x_max = last(*xs.iter().max().unwrap(), width);
+    let y_min = first(*ys.iter().min().unwrap());
+    let y_max = last(*ys.iter().max().unwrap(), height);
+    if x_min > x_max || y_min > y_max {
+      return None;
+    }
+
+    Some(Self {
+      edges: [Edge::new(p1, p2), Edge::new(p2, p0), Edge::new(p0, p1)],
+      area: area.abs(),
+      flipped,
+      x_range: x_min as u32..x_max as u32 + 1,
+      y_range: y_min as u32..y_max as u32 + 1,
+    })
+  }
+
+  /// the pixels the triangle may cover, clamped to the screen
+  pub fn x_range(&self) -> Range<u32> {
+    self.x_range.clone()
+  }
+
+  pub fn y_range(&self) -> Range<u32> {
+    self.y_range.clone()
+  }
+
+  /// Calls `f` with every pixel of the `rows` whose centre is covered, and
+  /// the barycentric weights there, relative to the vertices as they were given.
+  pub fn rasterize(&self, rows: Range<u32>, mut f: impl FnMut(u32, u32, &Barycentric)) {
+    self.scan(rows, |x, y, w| {
+      if self.is_inside(&w) {
+        f(x, y, &self.barycentric(&w));
+      }
+    });
+  }
+
+  /// Calls `f` with every pixel of the `rows` where at least one of the
+  /// sample `positions`, offsets from the top left corner of the pixel, is covered.
+  pub fn rasterize_samples(
+    &self,
+    rows: Range<u32>,
+    positions: &[(f32, f32)],
+    mut f: impl FnMut(u32, u32, &Coverage),
+  ) {
+    let positions = &positions[..positions.len().min(MAX_SAMPLES)];
+    // the sample offsets from the pixel centre in fixed point, per edge
+    let offsets: Vec<[i64; 3]> = positions
+      .iter()
+      .map(|(x, y)| {
+        let dx = (x * ONE as f32).round() as i64 - HALF;
+        let dy = (y * ONE as f32).round() as i64 - HALF;
+        self.edges.map(|e| e.a * dx + e.b * dy)
+      })
+      .collect();
+
+    self.scan(rows, |x, y, centre| {
+      let mut coverage = Coverage {
+        mask: 0,
+        centre: self.barycentric(&centre),
+        samples: [Barycentric::from_weights(0.0, 0.0, 0.0); MAX_SAMPLES],
+      };
+
+      for (i, offset) in offsets.iter().enumerate() {
+        let w = [0, 1, 2].map(|e| centre[e] + offset[e]);
+        if self.is_inside(&w) {
+          coverage.mask |= 1 << i;
+          coverage.samples[i] = self.barycentric(&w);
+        }
+      }
+
+      if coverage.mask != 0 {
+        f(x, y, &coverage);
+      }
+    });
+  }
+
+  fn is_inside(&self, w: &[i64; 3]) -> bool {
+    self.edges.iter().zip(w).all(|(e, w)| w + e.bias >= 0)
+  }
+
+  fn barycentric(&self, w: &[i64; 3]) -> Barycentric {
+    let area = self.area as f32;
+    let (alpha, beta, gamma) = (w[0] as f32 / area, w[1] as f32 / area, w[2] as f32 / area);
+    if self.flipped {
+      Barycentric::from_weights(alpha, gamma, beta)
+    } else {
+      Barycentric::from_weights(alpha, beta, gamma)
+    }
+  }
+
+  /// Walks the pixels of the `rows` within the bounds, `f` receives the
+  /// edge functions at the pixel centre.
+  fn scan(&self, rows: Range<u32>, mut f: impl FnMut(u32, u32, [i64; 3])) {
+    let y_start = self.y_range.start.max(rows.start);
+    let y_end = self.y_range.end.min(rows.end);
+    if y_start >= y_end {
+      return;
+    }
+
+    let x_start = self.x_range.start;
+    let mut row = self
+      .edges
+      .map(|e| e.eval(x_start as i64 * ONE + HALF, y_start as i64 * ONE + HALF));
+
+    for y in y_start..y_end {
+      let mut w = row;
+      for x in self.x_range.clone() {
+        f(x, y, w);
+
+        for (w, e) in w.iter_mut().zip(&self.edges) {
+          *w += e.a * ONE;
+        }
+      }
+
+      for (w, e) in row.iter_mut().zip(&self.edges) {
+        *w += e.b * ONE;
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// how many of the `triangles` cover the centre of each pixel
+  fn coverage_counts(triangles: &[[Vec2; 3]], width: u32, height: u32) -> Vec<u32> {
+    let mut counts = vec![0; (width * height) as usize];
+    for triangle in triangles {
+      let rasterizer = EdgeRasterizer::new(triangle, width, height).unwrap();
+      rasterizer.rasterize(0..height, |x, y, _| counts[(x + y * width) as usize] += 1);
+    }
+    counts
+  }
+
+  #[test]
+  fn shared_diagonal_edge() {
+    // the diagonal runs through the centres of the pixels (i, i)
+    let (a, b, c, d) = (
+      Vec2::new(0.5, 0.5),
+      Vec2::new(8.5, 0.5),
+      Vec2::new(8.5, 8.5),
+      Vec2::new(0.5, 8.5),
+    );
+    let counts = coverage_counts(&[[a, b, c], [a, c, d]], 10, 10);
+    for i in 0..8 {
+      assert_eq!(counts[(i + i * 10) as usize], 1, "pixel ({i}, {i})");
+    }
+    assert!(counts.iter().all(|&n| n <= 1));
+  }
+
+  #[test]
+  fn shared_vertical_and_horizontal_edges() {
+    // the edges x = 4.5 and y = 4.5 run through pixel centres, either winding
+    let centre = Vec2::new(4.5, 4.5);
+    let corners = [
+      Vec2::new(4.5, 0.0),
+      Vec2::new(9.0, 4.5),
+      Vec2::new(4.5, 9.0),
+      Vec2::new(0.0, 4.5),
+    ];
+    let triangles: Vec<_> = (0..4)
+      .map(|i| {
+        let triangle = [centre, corners[i], corners[(i + 1) % 4]];
+        if i % 2 == 0 {
+          triangle
+        } else {
+          [triangle[0], triangle[2], triangle[1]]
+        }
+      })
+      .collect();
+
+    let counts = coverage_counts(&triangles, 9, 9);
+    for i in 1..8 {
+      assert_eq!(counts[(4 + i * 9) as usize], 1, "pixel (4, {i})");
+      assert_eq!(counts[(i + 4 * 9) as usize], 1, "pixel ({i}, 4)");
+    }
+    assert!(counts.iter().all(|&n| n <= 1));
+  }
+
+  #[test]
+  fn rejected_triangles() {
+    // collinear, or snapping to a line
+    let line = [
+      Vec2::new(0.0, 0.0),
+      Vec2::new(2.0, 2.0),
+      Vec2::new(5.0, 5.0),
+    ];
+    assert!(EdgeRasterizer::new(&line, 8, 8).is_none());
+    let thin = [
+      Vec2::new(0.0, 0.0),
+      Vec2::new(4.0, 0.0),
+      Vec2::new(2.0, 0.0001),
+    ];
+    assert!(EdgeRasterizer::new(&thin, 8, 8).is_none());
+
+    let off_screen = [
+      Vec2::new(-9.0, 1.0),
+      Vec2::new(-1.0, 1.0),
+      Vec2::new(-5.0, 6.0),
+    ];
+    assert!(EdgeRasterizer::new(&off_screen, 8, 8).is_none());
+    let below = [
+      Vec2::new(1.0, 9.0),
+      Vec2::new(6.0, 9.0),
+      Vec2::new(3.0, 12.0),
+    ];
+    assert!(EdgeRasterizer::new(&below, 8, 8).is_none());
+
+    // the fixed point products would overflow
+    let huge = [
+      Vec2::new(4e9, 1.0),
+      Vec2::new(1.0, 1.0),
+      Vec2::new(1.0, 6.0),
+    ];
+    assert!(EdgeRasterizer::new(&huge, 8, 8).is_none());
+    let nan = [
+      Vec2::new(f32::NAN, 1.0),
+      Vec2::new(1.0, 1.0),
+      Vec2::new(1.0, 6.0),
+    ];
+    assert!(EdgeRasterizer::new(&nan, 8, 8).is_none());
+  }
+}
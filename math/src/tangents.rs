@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use super::{Vec2, Vec3, Vec4};
+
+/// Generates a tangent for every corner of the `triangles`, which index into
+/// `positions`, following MikkTSpace so that the normal maps baked by other
+/// tools come out right.
+///
+/// `w` is the sign of the bitangent, `bitangent = w * normal.cross(tangent)`,
+/// the normal being the interpolated one, not renormalized. The corners sharing
+/// a position, normal, uv and uv winding are averaged, weighted by their angle.
+/// The faces whose uvs are degenerate take the tangent of their neighbours,
+/// or any one perpendicular to the normal.
+pub fn generate_tangents(
+  positions: &[Vec3],
+  triangles: &[[usize; 3]],
+  normals: &[[Vec3; 3]],
+  uvs: &[[Vec2; 3]],
+) -> Vec<[Vec4; 3]> {
+  let unit = |v: Vec3| {
+    if v.length_square() > 0.0 {
+      v.normalize()
+    } else {
+      Vec3::zero()
+    }
+  };
+  let key = |position: usize, normal: &Vec3, uv: &Vec2, preserving: bool| {
+    (
+      position,
+      [normal.x, normal.y, normal.z, uv.x, uv.y].map(f32::to_bits),
+      preserving,
+    )
+  };
+
+  let mut sums: HashMap<_, Vec3> = HashMap::new();
+  let mut preserving = Vec::with_capacity(triangles.len());
+
+  for (face, triangle) in triangles.iter().enumerate() {
+    let p = triangle.map(|i| positions[i]);
+    let [t0, t1, t2] = uvs[face];
+    let (d1, d2) = (p[1] - p[0], p[2] - p[0]);
+    let (s1, s2) = (t1 - t0, t2 - t0);
+
+    // twice the signed area in uv space, negative for the mirrored faces
+    let area = s1.x * s2.y - s1.y * s2.x;
+    let tangent = if area.abs() > f32::EPSILON {
+      (d1 * s2.y - d2 * s1.y) * (1.0 / area)
+    } else {
+      Vec3::zero()
+    };
+    preserving.push(area >= 0.0);
+
+    for corner in 0..3 {
+      let n = unit(normals[face][corner]);
+      let project = |v: Vec3| unit(v - n * n.dot(&v));
+
+      let u = project(p[(corner + 1) % 3] - p[corner]);
+      let v = project(p[(corner + 2) % 3] - p[corner]);
+      let angle = u.dot(&v).clamp(-1.0, 1.0).acos();
+
+      let id = key(
+        triangle[corner],
+        &normals[face][corner],
+        &uvs[face][corner],
+        preserving[face],
+      );
+      let sum = sums.entry(id).or_insert(Vec3::zero());
+      *sum += project(tangent) * angle;
+    }
+  }
+
+  triangles
+    .iter()
+    .enumerate()
+    .map(|(face, triangle)| {
+      let sign = if preserving[face] { 1.0 } else { -1.0 };
+
+      [0, 1, 2].map(|corner| {
+        let normal = normals[face][corner];
+        let id = key(
+          triangle[corner],
+          &normal,
+          &uvs[face][corner],
+          preserving[face],
+        );
+        let n = unit(normal);
+        let sum = sums[&id];
+
+        let tangent = match unit(sum - n * n.dot(&sum)) {
+          t if t.length_square() > 0.0 => t,
+          _ => perpendicular(&n),
+        };
+        Vec4::from_vec3(&tangent, sign)
+      })
+    })
+    .collect()
+}
+
+/// any unit vector perpendicular to the unit `n`, the x axis when `n` is zero
+fn perpendicular(n: &Vec3) -> Vec3 {
+  let axis = if n.x.abs() < 0.9 {
+    Vec3::x_axis()
+  } else {
+    Vec3::y_axis()
+  };
+  match n.cross(axis) {
+    t if t.length_square() > 0.0 => t.normalize(),
+    _ => *Vec3::x_axis(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// the unit quad in the xy plane facing +z, as two triangles, with the
+  /// uv of every corner given by `uv`
+  fn quad(uv: impl Fn(&Vec3) -> Vec2) -> Vec<[Vec4; 3]> {
+    let positions = [
+      Vec3::new(0.0, 0.0, 0.0),
+      Vec3::new(1.0, 0.0, 0.0),
+      Vec3::new(1.0, 1.0, 0.0),
+      Vec3::new(0.0, 1.0, 0.0),
+    ];
+    let triangles = [[0, 1, 2], [0, 2, 3]];
+    let normals = [[*Vec3::z_axis(); 3]; 2];
+    let uvs = triangles.map(|t| t.map(|i| uv(&positions[i])));
+    generate_tangents(&positions, &triangles, &normals, &uvs)
+  }
+
+  fn assert_frame(tangent: &Vec4, t: Vec3, b: Vec3) {
+    let n = *Vec3::z_axis();
+    let tangent_3 = tangent.truncated_to_vec3();
+    let bitangent = n.cross(&tangent_3) * tangent.w;
+    assert!((tangent_3 - t).length() < 1e-5, "{tangent:?}");
+    assert!((bitangent - b).length() < 1e-5, "{bitangent:?}");
+  }
+
+  #[test]
+  fn uv_mapped_quad() {
+    // u along x and v along y, T = +u and B = +v
+    let tangents = quad(|p| Vec2::new(p.x, p.y));
+    for tangent in tangents.iter().flatten() {
+      assert_eq!(tangent.w, 1.0);
+      assert_frame(tangent, Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    }
+  }
+
+  #[test]
+  fn mirrored_quad() {
+    // u runs along -x, the bitangent still follows +v
+    let tangents = quad(|p| Vec2::new(1.0 - p.x, p.y));
+    for tangent in tangents.iter().flatten() {
+      assert_eq!(tangent.w, -1.0);
+      assert_frame(tangent, Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    }
+  }
+
+  #[test]
+  fn degenerate_uvs() {
+    // a tangent perpendicular to the normal rather than NaN
+    let tangents = quad(|_| Vec2::new(0.5, 0.5));
+    for tangent in tangents.iter().flatten() {
+      let t = tangent.truncated_to_vec3();
+      assert!((t.length() - 1.0).abs() < 1e-5);
+      assert!(t.dot(Vec3::z_axis()).abs() < 1e-5);
+    }
+  }
+}
@@ -44,9 +44,10 @@ pub fn object_3d(args: TokenStream, input: TokenStream) -> TokenStream {
       position: std::cell::RefCell<crate::math::Vec3>,
       rotation: std::cell::RefCell<crate::math::Rotation>,
       scale: std::cell::RefCell<crate::math::Vec3>,
+      up: std::cell::RefCell<crate::math::Vec3>,
       layers: std::cell::RefCell<crate::core::layer::Layers>,
-      cast_shadow: bool,
-      receive_shadow: bool,
+      cast_shadow: std::cell::RefCell<bool>,
+      receive_shadow: std::cell::RefCell<bool>,
       visible: std::cell::RefCell<bool>,
       user_data: std::collections::HashMap<String, Box<dyn std::any::Any>>,
       object_type: crate::core::object_3d::ObjectType,
@@ -158,12 +159,14 @@ pub fn object_3d(args: TokenStream, input: TokenStream) -> TokenStream {
         };
 
         let orthogonal_basis =
-          crate::math::Mat3::get_orthogonal_basis(eye, target, *crate::math::Vec3::y_axis());
+          crate::math::Mat3::get_orthogonal_basis(eye, target, *self.up.borrow());
 
         let mut rotate_mat = crate::math::Mat4::identity();
 
-        for i in 0..2 {
-          let col = crate::math::Vec4::from_vec3(&orthogonal_basis.get_col(i), 0.0);
+        // the basis holds the axes in its rows
+        let axes = orthogonal_basis.transpose();
+        for i in 0..3 {
+          let col = crate::math::Vec4::from_vec3(&axes.get_col(i), 0.0);
           rotate_mat.set_col(i, col);
         }
 
@@ -191,15 +194,18 @@ pub fn object_3d(args: TokenStream, input: TokenStream) -> TokenStream {
         *self.global_matrix.borrow()
       }
 
+      /// top down, the global matrix of the parent is expected to be up to date
       fn update_global_matrix(&self) {
         self.update_matrix();
 
-        if let Some(parent) = self.parent.borrow().as_ref() {
-          parent.update_global_matrix();
-          let parent_global = parent.global_matrix();
-          let mut global_matrix = self.global_matrix.borrow_mut();
+        {
           let local_matrix = self.matrix();
-          *global_matrix = parent_global * local_matrix;
+          let next_matrix = match self.parent.borrow().as_ref() {
+            Some(parent) => parent.global_matrix() * local_matrix,
+            None => local_matrix,
+          };
+          let mut global_matrix = self.global_matrix.borrow_mut();
+          *global_matrix = next_matrix;
         }
 
         for child in std::ops::Deref::deref(&self.children.borrow()) {
@@ -343,8 +349,28 @@ pub fn object_3d(args: TokenStream, input: TokenStream) -> TokenStream {
         rotation.into()
       }
 
+      fn up(&self) -> crate::math::Vec3 {
+        *self.up.borrow()
+      }
+
+      fn set_up(&self, up: crate::math::Vec3) {
+        *self.up.borrow_mut() = up;
+      }
+
       fn cast_shadow(&self) -> bool {
-        self.cast_shadow
+        *self.cast_shadow.borrow()
+      }
+
+      fn set_cast_shadow(&self, cast_shadow: bool) {
+        *self.cast_shadow.borrow_mut() = cast_shadow;
+      }
+
+      fn receive_shadow(&self) -> bool {
+        *self.receive_shadow.borrow()
+      }
+
+      fn set_receive_shadow(&self, receive_shadow: bool) {
+        *self.receive_shadow.borrow_mut() = receive_shadow;
       }
 
 
@@ -379,6 +405,7 @@ pub fn object_3d(args: TokenStream, input: TokenStream) -> TokenStream {
           .field("position", &self.position)
           .field("rotation", &self.rotation)
           .field("scale", &self.scale)
+          .field("up", &self.up)
           .field("layers", &self.layers)
           .field("cast_shadow", &self.cast_shadow)
           .field("receive_shadow", &self.receive_shadow)
@@ -427,14 +454,14 @@ pub fn light_shadow(args: TokenStream, input: TokenStream) -> TokenStream {
     pub struct #struct_name{
       #(#attributes)*
       camera: std::rc::Rc<dyn crate::cameras::camera::ICamera>,
-      intensity: f32,
-      bias: f32,
-      normal_bias: f32,
-      radius: f32,
+      intensity: std::cell::RefCell<f32>,
+      bias: std::cell::RefCell<f32>,
+      normal_bias: std::cell::RefCell<f32>,
+      radius: std::cell::RefCell<f32>,
       // shadow texture width & height
-      map_size: crate::math::Vec2,
+      map_size: std::cell::RefCell<crate::math::Vec2>,
       mat: crate::math::Mat4,
-      // vec4 -> offsetx, offsety, width, height
+      // vec4 -> offsetx, offsety, width, height, in map_size units
       viewports: Vec<crate::math::Vec4>,
       map: std::rc::Rc<crate::core::render_target::RenderTarget>,
 
@@ -451,8 +478,44 @@ pub fn light_shadow(args: TokenStream, input: TokenStream) -> TokenStream {
         self.camera.clone()
       }
 
+      fn intensity(&self) -> f32 {
+        *self.intensity.borrow()
+      }
+
+      fn set_intensity(&self, intensity: f32) {
+        *self.intensity.borrow_mut() = intensity;
+      }
+
+      fn bias(&self) -> f32 {
+        *self.bias.borrow()
+      }
+
+      fn set_bias(&self, bias: f32) {
+        *self.bias.borrow_mut() = bias;
+      }
+
+      fn normal_bias(&self) -> f32 {
+        *self.normal_bias.borrow()
+      }
+
+      fn set_normal_bias(&self, normal_bias: f32) {
+        *self.normal_bias.borrow_mut() = normal_bias;
+      }
+
+      fn radius(&self) -> f32 {
+        *self.radius.borrow()
+      }
+
+      fn set_radius(&self, radius: f32) {
+        *self.radius.borrow_mut() = radius;
+      }
+
       fn map_size(&self) -> crate::math::Vec2 {
-        self.map_size
+        *self.map_size.borrow()
+      }
+
+      fn set_map_size(&self, map_size: crate::math::Vec2) {
+        *self.map_size.borrow_mut() = map_size;
       }
 
       fn viewports(&self) -> &Vec<crate::math::Vec4> {
@@ -77,4 +77,22 @@ impl Sandbox {
     };
     Box::new(f)
   }
+
+  pub fn make_draw_rgba_image(&self) -> Box<dyn Fn(&[u8])> {
+    let width = self.width;
+    let height = self.height;
+
+    let f = move |pixels_buffer: &[u8]| {
+      fltk::draw::draw_image(
+        pixels_buffer,
+        0,
+        0,
+        width,
+        height,
+        fltk::enums::ColorDepth::Rgba8,
+      )
+      .unwrap();
+    };
+    Box::new(f)
+  }
 }
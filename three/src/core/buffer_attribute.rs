@@ -20,7 +20,7 @@ impl<T: Sized + Copy + ToF32> TypeBufferAttribute<T> {
 
   pub fn pick(&self, index: usize) -> TypeBufferAttribute<T> {
     let start = self.size * index;
-    let end = start + self.size + 1;
+    let end = start + self.size;
 
     let mut data = vec![];
     for i in start..end {
@@ -199,6 +199,14 @@ macro_rules! typed_array {
           )+
         }
       }
+
+      pub fn items(&self) -> usize {
+        match &self {
+          $(
+            Self::$enum(buffer) => buffer.items(),
+          )+
+        }
+      }
     }
 
   };
@@ -229,6 +237,12 @@ macro_rules! a {
       stringify!($type)
     ))
   };
+  ($store:ident, $type:ty, $key:tt) => {{
+    let res: Option<$type> = $store
+      .get($key)
+      .map_or(None, |v| crate::core::ExtractRef::<$type>::extract(v));
+    res
+  }};
 }
 
 pub(crate) use a;
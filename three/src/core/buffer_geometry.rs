@@ -1,4 +1,4 @@
-use math::Vec3;
+use math::{generate_normals, generate_tangents, NormalGeneration, Vec2, Vec3};
 
 use super::{
   buffer_attribute::{F32BufferAttribute, IBufferAttribute, TypeBufferEnum},
@@ -24,6 +24,89 @@ impl Default for BufferGeometry {
 
 pub type Attribute = HashMap<String, TypeBufferEnum>;
 
+impl BufferGeometry {
+  /// Sets the "normal" attribute from the "position" one, every 3 vertices
+  /// making a triangle. The vertices at the same position are welded, and an
+  /// optional "smoothing_group" attribute, one u32 per vertex, keeps the groups
+  /// apart, see `math::generate_normals`.
+  pub fn compute_vertex_normals(&mut self, options: &NormalGeneration) {
+    let Some((positions, triangles)) = self.welded_triangles() else {
+      return;
+    };
+    let groups: Vec<u32> = match self.attributes.get("smoothing_group") {
+      Some(TypeBufferEnum::U32(group)) => {
+        (0..triangles.len()).map(|t| group.get_x(t * 3)).collect()
+      }
+      _ => vec![],
+    };
+
+    let normals = generate_normals(&positions, &triangles, &groups, options);
+    let data = normals
+      .iter()
+      .flatten()
+      .flat_map(|n| [n.x, n.y, n.z])
+      .collect();
+    self.set_attribute("normal", F32BufferAttribute::new(data, 3, false).as_enum());
+  }
+
+  /// Sets the "tangent" attribute, 4 floats per vertex, from the "position",
+  /// "normal" and "uv" ones. `w` is the sign of the bitangent, the frames match
+  /// MikkTSpace, see `math::generate_tangents`.
+  pub fn compute_tangents(&mut self) {
+    let (Some(TypeBufferEnum::F32(normal)), Some(TypeBufferEnum::F32(uv))) =
+      (self.attributes.get("normal"), self.attributes.get("uv"))
+    else {
+      return;
+    };
+    let Some((positions, triangles)) = self.welded_triangles() else {
+      return;
+    };
+
+    let normals: Vec<[Vec3; 3]> = (0..triangles.len())
+      .map(|t| [0, 1, 2].map(|i| normal.get_vec3(t * 3 + i)))
+      .collect();
+    let uvs: Vec<[Vec2; 3]> = (0..triangles.len())
+      .map(|t| [0, 1, 2].map(|i| Vec2::new(uv.get_x(t * 3 + i), uv.get_y(t * 3 + i))))
+      .collect();
+
+    let tangents = generate_tangents(&positions, &triangles, &normals, &uvs);
+    let data = tangents
+      .iter()
+      .flatten()
+      .flat_map(|t| [t.x, t.y, t.z, t.w])
+      .collect();
+    self.set_attribute("tangent", F32BufferAttribute::new(data, 4, false).as_enum());
+  }
+
+  /// the distinct positions, and the triangles made of every 3 vertices
+  /// indexing into them
+  fn welded_triangles(&self) -> Option<(Vec<Vec3>, Vec<[usize; 3]>)> {
+    let Some(TypeBufferEnum::F32(position)) = self.attributes.get("position") else {
+      return None;
+    };
+
+    let mut welded: HashMap<[u32; 3], usize> = HashMap::new();
+    let mut positions = vec![];
+    let indices: Vec<usize> = (0..position.items())
+      .map(|i| {
+        let p = position.get_vec3(i);
+        *welded
+          .entry([p.x.to_bits(), p.y.to_bits(), p.z.to_bits()])
+          .or_insert_with(|| {
+            positions.push(p);
+            positions.len() - 1
+          })
+      })
+      .collect();
+
+    let triangles = indices
+      .chunks_exact(3)
+      .map(|t| [t[0], t[1], t[2]])
+      .collect();
+    Some((positions, triangles))
+  }
+}
+
 pub fn pick_attribute_per_vertex(attr: &Attribute, index: usize) -> Attribute {
   let mut attribute_per_vertex = Attribute::default();
   let vertex_index = (index % 3) as f32;
@@ -70,7 +153,7 @@ impl IBoundingSphere for BufferGeometry {
         let count = position.items();
         let mut max_radius = 0f32;
         for i in 0..count {
-          let attr_vec3 = position.get_vec3(i).distance_to(center);
+          let attr_vec3 = (position.get_vec3(i) - center).length();
           max_radius = max_radius.max(attr_vec3);
         }
 
@@ -79,8 +162,9 @@ impl IBoundingSphere for BufferGeometry {
     }
   }
 
+  /// the sphere of the last `update_bounding_sphere`, infinite before it
   fn bounding_sphere(&self) -> &Sphere {
-    todo!()
+    &self.bounding_sphere
   }
 }
 
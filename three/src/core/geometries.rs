@@ -2,16 +2,29 @@ use math::Vec3;
 
 use super::buffer_attribute::{IBufferAttribute, ToF32};
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Box3 {
   min: Vec3,
   max: Vec3,
 }
 
+/// the empty box, any point expands it
+impl Default for Box3 {
+  fn default() -> Self {
+    let mut res = Self {
+      min: Vec3::zero(),
+      max: Vec3::zero(),
+    };
+    res.reset();
+    res
+  }
+}
+
 impl Box3 {
   pub fn expand(&mut self, point: Vec3) {
-    self.min.min(point);
-    self.max.max(point);
+    let (min, max) = (self.min, self.max);
+    self.min = Vec3::new(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z));
+    self.max = Vec3::new(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z));
   }
 
   pub fn get_center(&self) -> Vec3 {
@@ -58,7 +58,15 @@ pub trait IObject3D: Any {
   fn global_position(&self) -> math::Vec3;
   fn global_rotation(&self) -> math::Rotation;
 
+  /// the direction kept up by `look_at`, y by default
+  fn up(&self) -> math::Vec3;
+  fn set_up(&self, up: math::Vec3);
+
   fn cast_shadow(&self) -> bool;
+  fn set_cast_shadow(&self, cast_shadow: bool);
+  /// whether the shadows of the lights fall on the object
+  fn receive_shadow(&self) -> bool;
+  fn set_receive_shadow(&self, receive_shadow: bool);
 
   fn layers(&self) -> std::cell::Ref<crate::core::layer::Layers>;
   fn test_layers(&self, layers: &crate::core::layer::Layers) -> bool;
@@ -78,12 +86,13 @@ macro_rules! with_default_fields {
       event_emitter: Default::default(),
       parent: Default::default(),
       children: Default::default(),
-      matrix: Default::default(),
-      global_matrix: Default::default(),
+      matrix: std::cell::RefCell::new(math::Mat4::identity()),
+      global_matrix: std::cell::RefCell::new(math::Mat4::identity()),
       position: Default::default(),
       rotation: Default::default(),
-      scale: Default::default(),
-      visible: Default::default(),
+      scale: std::cell::RefCell::new(math::Vec3::new(1.0, 1.0, 1.0)),
+      up: std::cell::RefCell::new(*math::Vec3::y_axis()),
+      visible: std::cell::RefCell::new(true),
       layers: Default::default(),
       cast_shadow: Default::default(),
       object_type: crate::core::object_3d::ObjectType::$type,
@@ -11,6 +11,13 @@ pub struct RenderTarget {
 }
 
 impl RenderTarget {
+  pub fn from_texture(texture: Texture) -> Self {
+    Self {
+      viewport: Default::default(),
+      texture: RefCell::new(texture),
+    }
+  }
+
   pub fn set_size(&self, w: f32, h: f32) {
     let mut viewport = self.viewport.borrow_mut();
     let mut texture = self.texture.borrow_mut();
@@ -20,6 +27,17 @@ impl RenderTarget {
     // texture.
   }
 
+  /// draws into the `(x, y, width, height)` region of the texture only
+  pub fn set_viewport(&self, viewport: Vec4) {
+    let mut v = self.viewport.borrow_mut();
+    v.set_offset(viewport.x, viewport.y);
+    v.set_size(viewport.z, viewport.w);
+  }
+
+  pub fn clear(&self, color: Vec4) {
+    self.texture.borrow_mut().clear(color);
+  }
+
   pub fn take_color(&self) -> Vec<u8> {
     let mut texture = self.texture.borrow_mut();
     texture.take_color().0
@@ -1,11 +1,12 @@
 use std::{
   collections::HashMap,
   ops::{Deref, DerefMut},
+  rc::Rc,
 };
 
 use math::{Mat3, Mat4, Vec2, Vec3, Vec4};
 
-use super::marco::define_gl_type_enum;
+use super::{marco::define_gl_type_enum, render_target::RenderTarget};
 
 trait SetGlType<T> {
   fn set_attribute(&mut self, key: &str, val: T);
@@ -40,6 +41,8 @@ define_uniform_trait!(
 #[derive(Debug, Default)]
 pub struct Uniform {
   attributes: HashMap<String, UniformTypeEnum>,
+  /// the textures the shaders read from, e.g. the shadow maps
+  samplers: HashMap<String, Rc<RenderTarget>>,
 }
 
 impl Deref for Uniform {
@@ -61,6 +64,14 @@ impl Uniform {
     self.attributes.insert(key.to_string(), typed_enum);
   }
 
+  pub fn insert_sampler(&mut self, key: &str, texture: Rc<RenderTarget>) {
+    self.samplers.insert(key.to_string(), texture);
+  }
+
+  pub fn sampler(&self, key: &str) -> Option<&Rc<RenderTarget>> {
+    self.samplers.get(key)
+  }
+
   pub fn merge(&mut self, another: &Self) {
     another.iter().for_each(|(k, v)| {
       if self.attributes.contains_key(k) {
@@ -69,6 +80,9 @@ impl Uniform {
 
       self.insert(k, *v);
     });
+    another.samplers.iter().for_each(|(k, v)| {
+      self.samplers.entry(k.clone()).or_insert_with(|| v.clone());
+    });
   }
 
   pub fn extends(mut self, mut from: Self) -> Self {
@@ -78,6 +92,9 @@ impl Uniform {
       }
       self.attributes.insert(k, v);
     }
+    for (k, v) in from.samplers.drain() {
+      self.samplers.entry(k).or_insert(v);
+    }
 
     self
   }
@@ -97,7 +114,9 @@ macro_rules! u {
     ))
   };
   ($store:ident, $type:ty, $key:tt) => {{
-    let res: Option<$type> = $store.get($key).map_or(None, |v| v.extract());
+    let res: Option<$type> = $store
+      .get($key)
+      .map_or(None, |v| crate::core::Extract::<$type>::extract(v));
     res
   }};
 }
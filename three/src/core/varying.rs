@@ -5,7 +5,7 @@ use std::{
 
 use math::{Barycentric, Mat4, Vec2, Vec3, Vec4};
 
-trait DeclareGlType<T> {
+pub(crate) trait DeclareGlType<T> {
   fn declare_attribute(&mut self, key: &str, val: T);
 }
 
@@ -91,6 +91,12 @@ impl Varying {
           let val = vec[0];
           self.result.insert(key.to_string(), val);
         }
+        // the ends of a line, weighted by the first two weights
+        2 => {
+          let lerped_val =
+            (vec[0] * (rhws[0] * bary.alpha()) + vec[1] * (rhws[1] * bary.beta())) * z;
+          self.result.insert(key.to_string(), lerped_val);
+        }
         3 => {
           let arr = [vec[0] * rhws[0], vec[1] * rhws[1], vec[2] * rhws[2]];
           let lerped_val = bary.apply_weight(&arr) * z;
@@ -107,7 +113,7 @@ impl Varying {
 
 macro_rules! v {
   ($store:ident,$type:ty,$key:tt,!) => {
-    crate::next::core::Extract::<$type>::extract(
+    crate::core::Extract::<$type>::extract(
       ($store
         .get($key)
         .expect(&format!("error from getting {} from varyings", $key))),
@@ -119,7 +125,9 @@ macro_rules! v {
     ))
   };
   ($store:ident,$type:ty,$key:tt) => {{
-    let res: Option<$type> = $store.get($key).map_or(None, |v| v.extract());
+    let res: Option<$type> = $store
+      .get($key)
+      .map_or(None, |v| crate::core::Extract::<$type>::extract(v));
     res
   }};
 }
@@ -127,10 +135,10 @@ macro_rules! v {
 macro_rules! add_v {
   ($store:ident,$key:tt,$expr:expr) => {{
     let val = $expr;
-    $store.declare_attribute($key, val);
+    crate::core::varying::DeclareGlType::declare_attribute($store, $key, val);
   }};
   ($store:ident,$key:tt,$val:tt) => {
-    $store.declare_attribute($key, $val);
+    crate::core::varying::DeclareGlType::declare_attribute($store, $key, $val);
   };
 }
 
@@ -7,7 +7,7 @@ use math::Mat4;
 ///  Here d is the resolution of the z-buffer.
 /// I like to have it equal to 255 because of simplicity of
 /// dumping black-and-white images of the z-buffer for debugging.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Viewport {
   x: f32,
   y: f32,
@@ -17,6 +17,12 @@ pub struct Viewport {
   viewport_matrix: Mat4,
 }
 
+impl Default for Viewport {
+  fn default() -> Self {
+    Self::new(0.0, 0.0, 0.0, 0.0)
+  }
+}
+
 impl Viewport {
   pub fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
     let mut viewport = Self {
@@ -43,6 +49,16 @@ impl Viewport {
     (self.w, self.h)
   }
 
+  pub fn set_offset(&mut self, x: f32, y: f32) {
+    self.x = x;
+    self.y = y;
+    self.recompute_matrix();
+  }
+
+  pub fn get_offset(&self) -> (f32, f32) {
+    (self.x, self.y)
+  }
+
 
   #[rustfmt::skip]
   pub fn recompute_matrix(&mut self) {
@@ -4,7 +4,7 @@ pub mod core;
 pub mod lights;
 pub mod loaders;
 pub mod material;
-pub mod math;
+pub use math;
 pub mod objects;
 pub mod renderer;
 pub mod textures;
@@ -25,15 +25,16 @@ pub struct DirectionalLight {
   pub intensity: f32,
   pub target: Object3D,
   // pub shadow
-  pub shadow: DirectionalLightShadow,
+  pub shadow: Rc<DirectionalLightShadow>,
 }
 
 impl DirectionalLight {
+  /// a white light shining from above the origin towards it
   pub fn new() -> std::rc::Rc<Self> {
-    let color = Vec4::default();
+    let color = Vec4::new(1.0, 1.0, 1.0, 1.0);
     let intensity = 1.0f32;
     let target = Object3D::new_ownership();
-    let shadow = DirectionalLightShadow::new();
+    let shadow = Rc::new(DirectionalLightShadow::new());
     let this = with_default_fields!(Light;color,intensity,target,shadow);
     this
   }
@@ -68,7 +69,7 @@ impl ToUniform for DirectionalLight {
 
 impl ILight for DirectionalLight {
   fn shadow(&self) -> Option<Rc<dyn super::light::ILightShadow>> {
-    todo!()
+    Some(self.shadow.clone())
   }
 
   fn light_type(&self) -> super::light::LightType {
@@ -83,7 +84,7 @@ impl ILight for DirectionalLight {
     self.color
   }
 
-  fn target(&self) -> &Object3D {
-    &self.target
+  fn target(&self) -> Option<&Object3D> {
+    Some(&self.target)
   }
 }
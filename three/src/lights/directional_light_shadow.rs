@@ -13,8 +13,9 @@ pub struct DirectionalLightShadow {}
 impl ILightShadow for DirectionalLightShadow {}
 
 impl DirectionalLightShadow {
+  /// the shadow camera covers 10 x 10 units around the light direction
   pub fn new() -> Self {
-    let camera = OrthographicCamera::default();
+    let camera = OrthographicCamera::new(-5.0, 5.0, 5.0, -5.0, 0.5, 500.0);
     init_shadow_map!(camera;)
   }
 }
@@ -23,11 +24,11 @@ impl ToUniform for DirectionalLightShadow {
   fn to_uniform(&self) -> crate::core::uniform::Uniform {
     let mut res = Uniform::default();
 
-    res.insert("shadowIntensity", self.intensity);
-    res.insert("shadowBias", self.bias);
-    res.insert("shadowNormalBias", self.normal_bias);
-    res.insert("shadowRadius", self.radius);
-    res.insert("shadowMapSize", self.map_size);
+    res.insert("shadowIntensity", self.intensity());
+    res.insert("shadowBias", self.bias());
+    res.insert("shadowNormalBias", self.normal_bias());
+    res.insert("shadowRadius", self.radius());
+    res.insert("shadowMapSize", self.map_size());
 
     res
   }
@@ -0,0 +1,83 @@
+use std::rc::Rc;
+
+use renderer_macro_derive::object_3d;
+
+use crate::{
+  cameras::camera::ICamera,
+  core::{
+    object_3d::{with_default_fields, IObject3D},
+    uniform::Uniform,
+  },
+  material::material::ToUniform,
+  objects::base::Object3D,
+};
+
+use math::{Vec3, Vec4};
+
+use super::light::{compute_direction, ILight, ILightShadow, LightType, ToUniformWithView};
+
+#[object_3d(IObject3D)]
+pub struct HemisphereLight {
+  pub color: Vec4,
+  pub ground_color: Vec4,
+  pub intensity: f32,
+}
+
+impl HemisphereLight {
+  /// An ambient light fading from `sky_color` above to `ground_color` below,
+  /// the sky is in the direction of the position, up by default.
+  pub fn new(sky_color: Vec4, ground_color: Vec4, intensity: f32) -> std::rc::Rc<Self> {
+    let color = sky_color;
+    let this = with_default_fields!(Light;color,ground_color,intensity);
+    this.update_position(Vec3::new(0.0, 1.0, 0.0));
+    this
+  }
+}
+
+impl ToUniformWithView for HemisphereLight {
+  fn to_uniform(&self, camera: Rc<dyn ICamera>) -> Uniform {
+    let mut res = Uniform::default();
+
+    let position = self.global_matrix().get_col(3).truncated_to_vec3();
+    let direction = compute_direction(position, Vec3::zero(), camera.view_matrix());
+
+    res.insert("direction", direction.normalize());
+
+    res
+  }
+}
+
+impl ToUniform for HemisphereLight {
+  fn to_uniform(&self) -> Uniform {
+    let mut uniform = Uniform::default();
+
+    uniform.insert("sky_color", self.color.truncated_to_vec3() * self.intensity);
+    uniform.insert(
+      "ground_color",
+      self.ground_color.truncated_to_vec3() * self.intensity,
+    );
+    uniform
+  }
+}
+
+impl ILight for HemisphereLight {
+  fn shadow(&self) -> Option<Rc<dyn ILightShadow>> {
+    None
+  }
+
+  fn light_type(&self) -> LightType {
+    LightType::HemisphereLight
+  }
+
+  fn intensity(&self) -> f32 {
+    self.intensity
+  }
+
+  fn color(&self) -> Vec4 {
+    self.color
+  }
+
+  fn target(&self) -> Option<&Object3D> {
+    None
+  }
+}
@@ -31,35 +31,74 @@ pub trait ILight: IObject3D + ToUniformWithView + ToUniform {
 
   fn shadow(&self) -> Option<Rc<dyn ILightShadow>>;
 
-  fn target(&self) -> &Object3D;
+  /// what the light points at, `None` for the lights shining all around
+  fn target(&self) -> Option<&Object3D>;
 }
 
+/// maps the NDC cube onto the [0, 1] cube of the shadow map
 #[rustfmt::skip]
-static NDC_FACTOR: Mat4 = Mat4::from_row([			
-  0.5, 0.0, 0.0, 0.5,
-	0.0, 0.5, 0.0, 0.5,
-	0.0, 0.0, 0.5, 0.5,
-	0.0, 0.0, 0.0, 1.0
-]);
+fn ndc_factor() -> Mat4 {
+  Mat4::from_row([
+    0.5, 0.0, 0.0, 0.5,
+    0.0, 0.5, 0.0, 0.5,
+    0.0, 0.0, 0.5, 0.5,
+    0.0, 0.0, 0.0, 1.0,
+  ])
+}
+
+/// maps the [0, 1] square of a viewport onto its place in a `map_size` map,
+/// `viewport` being in pixels from the top left corner of the map
+#[rustfmt::skip]
+fn viewport_factor(viewport: Vec4, map_size: Vec2) -> Mat4 {
+  let Vec4 { x, y, z: w, w: h } = viewport;
+  Mat4::from_row([
+    w / map_size.x, 0.0,            0.0, x / map_size.x,
+    0.0,            h / map_size.y, 0.0, 1.0 - (y + h) / map_size.y,
+    0.0,            0.0,            1.0, 0.0,
+    0.0,            0.0,            0.0, 1.0,
+  ])
+}
 
 pub trait ILightShadow: ILightShadowBase + ToUniform {
-  fn update_matrices(&self, light: Rc<dyn ILight>, viewport: Vec4) {
+  /// the map holds `frame_extents` times `map_size` texels, to fit the
+  /// viewports
+  fn frame_extents(&self) -> Vec2 {
+    Vec2::new(1.0, 1.0)
+  }
+
+  /// the viewport `index` in texels of the map, the viewports being given in
+  /// `map_size` units
+  fn viewport(&self, index: usize) -> Vec4 {
+    let Vec2 { x: w, y: h } = self.map_size();
+    let mut viewport = self.viewports()[index];
+    viewport.x *= w;
+    viewport.y *= h;
+    viewport.z *= w;
+    viewport.w *= h;
+    viewport
+  }
+
+  /// Points the shadow camera from the light at its target, the matrix maps
+  /// the world onto the [0, 1] cube of the viewport `viewport_index` of the
+  /// map.
+  fn update_matrices(&self, light: Rc<dyn ILight>, viewport_index: usize) {
     let global_light_position = light.global_matrix().get_col(3).truncated_to_vec3();
     self
       .camera()
       .update_from_global_position(global_light_position);
-    let target_position = light
-      .target()
-      .global_matrix()
-      .get_col(3)
-      .truncated_to_vec3();
+    let target_position = light.target().map_or(Vec3::zero(), |target| {
+      target.global_matrix().get_col(3).truncated_to_vec3()
+    });
 
     self.camera().look_at(target_position);
     self.camera().update_global_matrix();
 
     let vp_matrix = self.camera().projection_matrix() * self.camera().view_matrix();
 
-    self.set_matrix(NDC_FACTOR * vp_matrix);
+    let mut map_size = self.map_size();
+    map_size *= self.frame_extents();
+    let viewport = self.viewport(viewport_index);
+    self.set_matrix(viewport_factor(viewport, map_size) * ndc_factor() * vp_matrix);
   }
 }
 pub trait ILightShadowBase {
@@ -69,7 +108,24 @@ pub trait ILightShadowBase {
 
   fn camera(&self) -> Rc<dyn ICamera>;
 
+  /// how dark the shadow is, from 0 for no shadow to 1
+  fn intensity(&self) -> f32;
+  fn set_intensity(&self, intensity: f32);
+
+  /// added to the depth of the fragments before they are compared to the map
+  fn bias(&self) -> f32;
+  fn set_bias(&self, bias: f32);
+
+  /// moves the fragments along their world normal before the lookup
+  fn normal_bias(&self) -> f32;
+  fn set_normal_bias(&self, normal_bias: f32);
+
+  /// the width of the filter in texels
+  fn radius(&self) -> f32;
+  fn set_radius(&self, radius: f32);
+
   fn map_size(&self) -> Vec2;
+  fn set_map_size(&self, map_size: Vec2);
 
   fn viewports(&self) -> &Vec<Vec4>;
 
@@ -85,19 +141,26 @@ pub fn compute_direction(position: Vec3, target: Vec3, view_matrix: Mat4) -> Vec
   direction
 }
 
+/// the fields of a light shadow, with a single viewport covering the map
+/// unless `viewports` are given
 macro_rules! init_shadow_map {
-  ($camera:ident;$($val:ident),*) => {{
+  ($camera:ident;$($val:ident),*) => {
+    init_shadow_map!($camera, vec![math::Vec4::new(0.0, 0.0, 1.0, 1.0)];$($val),*)
+  };
+  ($camera:ident, $viewports:expr;$($val:ident),*) => {{
     Self{
       $($val,)*
       camera:$camera,
-      intensity:1.0,
-      bias: 0.0,
-      normal_bias: 0.0,
-      radius: 1.0,
-      map_size: math::Vec2::new(512.0,512.0),
+      intensity: std::cell::RefCell::new(1.0),
+      bias: std::cell::RefCell::new(0.0),
+      normal_bias: std::cell::RefCell::new(0.0),
+      radius: std::cell::RefCell::new(1.0),
+      map_size: std::cell::RefCell::new(math::Vec2::new(512.0,512.0)),
       mat:  Default::default(),
-      viewports: vec![math::Vec4::new(0.0,0.0,1.0,1.0)],
-      map:  Default::default(),
+      viewports: $viewports,
+      map: std::rc::Rc::new(crate::core::render_target::RenderTarget::from_texture(
+        crate::textures::texture::Texture::with_color_type(1, 1, image::ColorType::Rgba8),
+      )),
       matrix: Default::default(),
     }
   }};
@@ -1,3 +1,7 @@
 pub mod directional_light;
 pub mod directional_light_shadow;
+pub mod hemisphere_light;
 pub mod light;
+pub mod point_light;
+pub mod point_light_shadow;
+pub mod spot_light;
@@ -0,0 +1,86 @@
+use std::rc::Rc;
+
+use renderer_macro_derive::object_3d;
+
+use crate::{
+  cameras::camera::ICamera,
+  core::{
+    object_3d::{with_default_fields, IObject3D},
+    uniform::Uniform,
+  },
+  material::material::ToUniform,
+  objects::base::Object3D,
+};
+
+use math::Vec4;
+
+use super::{
+  light::{ILight, ILightShadow, LightType, ToUniformWithView},
+  point_light_shadow::PointLightShadow,
+};
+
+#[object_3d(IObject3D)]
+pub struct PointLight {
+  pub color: Vec4,
+  pub intensity: f32,
+  /// the range of the light, 0 for no limit
+  pub distance: f32,
+  /// how fast the light dims with the distance, 2 being physically correct
+  pub decay: f32,
+  pub shadow: Rc<PointLightShadow>,
+}
+
+impl PointLight {
+  /// a light shining all around its position
+  pub fn new(color: Vec4, intensity: f32, distance: f32, decay: f32) -> std::rc::Rc<Self> {
+    let shadow = Rc::new(PointLightShadow::new());
+    with_default_fields!(Light;color,intensity,distance,decay,shadow)
+  }
+}
+
+impl ToUniformWithView for PointLight {
+  fn to_uniform(&self, camera: Rc<dyn ICamera>) -> Uniform {
+    let mut res = Uniform::default();
+
+    let position = camera.view_matrix() * self.global_matrix().get_col(3);
+
+    res.insert("position", position.truncated_to_vec3());
+
+    res
+  }
+}
+
+impl ToUniform for PointLight {
+  fn to_uniform(&self) -> Uniform {
+    let mut uniform = Uniform::default();
+    let rgb = self.color.truncated_to_vec3() * self.intensity;
+    let color = Vec4::from_vec3(&rgb, self.color.w);
+
+    uniform.insert("color", color);
+    uniform.insert("distance", self.distance);
+    uniform.insert("decay", self.decay);
+    uniform
+  }
+}
+
+impl ILight for PointLight {
+  fn shadow(&self) -> Option<Rc<dyn ILightShadow>> {
+    Some(self.shadow.clone())
+  }
+
+  fn light_type(&self) -> LightType {
+    LightType::PointLight
+  }
+
+  fn intensity(&self) -> f32 {
+    self.intensity
+  }
+
+  fn color(&self) -> Vec4 {
+    self.color
+  }
+
+  fn target(&self) -> Option<&Object3D> {
+    None
+  }
+}
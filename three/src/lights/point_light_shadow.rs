@@ -0,0 +1,102 @@
+use std::rc::Rc;
+
+use renderer_macro_derive::light_shadow;
+
+use crate::{
+  cameras::perspective_camera::PerspectiveCamera, core::uniform::Uniform,
+  material::material::ToUniform,
+};
+use math::{apply_translate, Vec2, Vec3, Vec4};
+
+use super::light::{init_shadow_map, ILight, ILightShadow, ILightShadowBase};
+
+/// the directions the faces of the cube look at, +x, -x, +z, -z, +y and -y
+pub const CUBE_DIRECTIONS: [Vec3; 6] = [
+  Vec3::new(1.0, 0.0, 0.0),
+  Vec3::new(-1.0, 0.0, 0.0),
+  Vec3::new(0.0, 0.0, 1.0),
+  Vec3::new(0.0, 0.0, -1.0),
+  Vec3::new(0.0, 1.0, 0.0),
+  Vec3::new(0.0, -1.0, 0.0),
+];
+
+/// the up of the cameras of the faces
+pub const CUBE_UPS: [Vec3; 6] = [
+  Vec3::new(0.0, 1.0, 0.0),
+  Vec3::new(0.0, 1.0, 0.0),
+  Vec3::new(0.0, 1.0, 0.0),
+  Vec3::new(0.0, 1.0, 0.0),
+  Vec3::new(0.0, 0.0, 1.0),
+  Vec3::new(0.0, 0.0, -1.0),
+];
+
+/// where the faces lie in the map, in faces from its top left corner
+pub const CUBE_VIEWPORTS: [Vec4; 6] = [
+  Vec4::new(2.0, 1.0, 1.0, 1.0),
+  Vec4::new(0.0, 1.0, 1.0, 1.0),
+  Vec4::new(3.0, 1.0, 1.0, 1.0),
+  Vec4::new(1.0, 1.0, 1.0, 1.0),
+  Vec4::new(3.0, 0.0, 1.0, 1.0),
+  Vec4::new(1.0, 0.0, 1.0, 1.0),
+];
+
+/// the faces of the cube are laid out 4 x 2 in the map
+pub const CUBE_FRAME_EXTENTS: Vec2 = Vec2::new(4.0, 2.0);
+
+/// The shadow all around a point light, its map holds the six faces of a cube,
+/// `map_size` being the size of a face. The faces store the distance to the
+/// light, from the near to the far plane of the camera, and the fragments find
+/// theirs by their direction from the light.
+#[light_shadow(ILightShadowBase)]
+pub struct PointLightShadow {
+  perspective_camera: Rc<PerspectiveCamera>,
+}
+
+impl PointLightShadow {
+  pub fn new() -> Self {
+    let perspective_camera = PerspectiveCamera::new(90.0, 1.0, 0.5, 500.0);
+    let camera = perspective_camera.clone();
+    init_shadow_map!(camera, CUBE_VIEWPORTS.to_vec(); perspective_camera)
+  }
+}
+
+impl Default for PointLightShadow {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ILightShadow for PointLightShadow {
+  fn frame_extents(&self) -> Vec2 {
+    CUBE_FRAME_EXTENTS
+  }
+
+  /// Turns the camera to the face `viewport_index`, the matrix only moves the
+  /// world to the light, the fragments being looked up by their direction.
+  fn update_matrices(&self, light: Rc<dyn ILight>, viewport_index: usize) {
+    let position = light.global_position();
+    let camera = self.camera();
+    camera.update_from_global_position(position);
+    camera.set_up(CUBE_UPS[viewport_index]);
+    camera.look_at(position + CUBE_DIRECTIONS[viewport_index]);
+    camera.update_global_matrix();
+
+    self.set_matrix(apply_translate(&(position * -1.0)));
+  }
+}
+
+impl ToUniform for PointLightShadow {
+  fn to_uniform(&self) -> crate::core::uniform::Uniform {
+    let mut res = Uniform::default();
+
+    res.insert("shadowIntensity", self.intensity());
+    res.insert("shadowBias", self.bias());
+    res.insert("shadowNormalBias", self.normal_bias());
+    res.insert("shadowRadius", self.radius());
+    res.insert("shadowMapSize", self.map_size());
+    res.insert("shadowCameraNear", self.perspective_camera.near);
+    res.insert("shadowCameraFar", self.perspective_camera.far);
+
+    res
+  }
+}
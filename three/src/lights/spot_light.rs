@@ -0,0 +1,103 @@
+use std::rc::Rc;
+
+use renderer_macro_derive::object_3d;
+
+use crate::{
+  cameras::camera::ICamera,
+  core::{
+    object_3d::{with_default_fields, IObject3D},
+    uniform::Uniform,
+  },
+  material::material::ToUniform,
+  objects::base::Object3D,
+};
+
+use math::Vec4;
+
+use super::light::{compute_direction, ILight, ILightShadow, LightType, ToUniformWithView};
+
+#[object_3d(IObject3D)]
+pub struct SpotLight {
+  pub color: Vec4,
+  pub intensity: f32,
+  /// the range of the light, 0 for no limit
+  pub distance: f32,
+  /// the half angle of the cone in radians, up to PI / 2
+  pub angle: f32,
+  /// the share of the cone fading out to its edge, from 0 to 1
+  pub penumbra: f32,
+  /// how fast the light dims with the distance, 2 being physically correct
+  pub decay: f32,
+  pub target: Object3D,
+}
+
+impl SpotLight {
+  /// a cone of light from its position towards its target, the origin by
+  /// default
+  pub fn new(
+    color: Vec4,
+    intensity: f32,
+    distance: f32,
+    angle: f32,
+    penumbra: f32,
+    decay: f32,
+  ) -> std::rc::Rc<Self> {
+    let target = Object3D::new_ownership();
+    with_default_fields!(Light;color,intensity,distance,angle,penumbra,decay,target)
+  }
+}
+
+impl ToUniformWithView for SpotLight {
+  fn to_uniform(&self, camera: Rc<dyn ICamera>) -> Uniform {
+    let mut res = Uniform::default();
+
+    let position = self.global_matrix().get_col(3);
+    let target = self.target.global_matrix().get_col(3).truncated_to_vec3();
+    let view_matrix = camera.view_matrix();
+
+    let direction = compute_direction(position.truncated_to_vec3(), target, view_matrix);
+
+    res.insert("position", (view_matrix * position).truncated_to_vec3());
+    res.insert("direction", direction.normalize());
+
+    res
+  }
+}
+
+impl ToUniform for SpotLight {
+  fn to_uniform(&self) -> Uniform {
+    let mut uniform = Uniform::default();
+    let rgb = self.color.truncated_to_vec3() * self.intensity;
+    let color = Vec4::from_vec3(&rgb, self.color.w);
+
+    uniform.insert("color", color);
+    uniform.insert("distance", self.distance);
+    uniform.insert("decay", self.decay);
+    // the cosines of the edge of the cone and of the inner, unfaded cone
+    uniform.insert("cone_cos", self.angle.cos());
+    uniform.insert("penumbra_cos", (self.angle * (1.0 - self.penumbra)).cos());
+    uniform
+  }
+}
+
+impl ILight for SpotLight {
+  fn shadow(&self) -> Option<Rc<dyn ILightShadow>> {
+    None
+  }
+
+  fn light_type(&self) -> LightType {
+    LightType::SpotLight
+  }
+
+  fn intensity(&self) -> f32 {
+    self.intensity
+  }
+
+  fn color(&self) -> Vec4 {
+    self.color
+  }
+
+  fn target(&self) -> Option<&Object3D> {
+    Some(&self.target)
+  }
+}
@@ -5,8 +5,16 @@ use std::{
 
 use lazy_static::lazy_static;
 
-use crate::utils::SingleOrList;
-use math::{Vec2, Vec3};
+use crate::{
+  core::{
+    buffer_attribute::{F32BufferAttribute, IBufferAttribute, TypeBufferEnum, U32BufferAttribute},
+    buffer_geometry::{BufferGeometry, IGeometry},
+  },
+  utils::SingleOrList,
+};
+use math::{
+  parse_obj_vertices, triangulate_face, NormalGeneration, ObjCounts, ObjVertex, Vec2, Vec3,
+};
 
 use super::{
   defines::{parse_num, parse_token, ParserError},
@@ -31,15 +39,27 @@ impl VertexIndex {
   }
 }
 
+impl From<ObjVertex> for VertexIndex {
+  fn from(v: ObjVertex) -> Self {
+    Self::new(v.position, v.normal, v.uv)
+  }
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Face {
   pub vertices: [VertexIndex; 3],
+  /// the `s` group the face belongs to, `0` when smoothing is off
+  pub smoothing_group: u32,
 }
 
 #[derive(Debug, Default)]
 pub struct Model {
   pub name: String,
   pub faces: Vec<Face>,
+  /// the polylines of the `l` elements
+  pub lines: Vec<Vec<VertexIndex>>,
+  /// the vertices of the `p` elements
+  pub points: Vec<VertexIndex>,
   pub material: Option<String>,
 }
 #[derive(Debug, Default)]
@@ -51,6 +71,7 @@ pub struct ObjData {
   pub normals: Vec<Vec3>,
   pub uvs: Vec<Vec2>,
   pub mtl_libs: Vec<String>,
+  smoothing_group: u32,
 }
 
 impl ILoaderData for ObjData {
@@ -80,6 +101,35 @@ impl ObjData {
     Ok(())
   }
 
+  /// Adds a face with any number of vertices, see `triangulate_face`.
+  pub fn add_polygon(&mut self, vertices: &[VertexIndex]) -> Result<(), ParserError> {
+    let indices: Vec<usize> = vertices.iter().map(|v| v.position_index as usize).collect();
+    for [a, b, c] in triangulate_face(&self.vertices, &indices) {
+      self.add_face(Face {
+        vertices: [vertices[a], vertices[b], vertices[c]],
+        smoothing_group: self.smoothing_group,
+      })?;
+    }
+    Ok(())
+  }
+
+  pub fn add_polyline(&mut self, vertices: &[VertexIndex]) -> Result<(), ParserError> {
+    let model = self.models.last_mut().ok_or(ParserError::ModelNotInit)?;
+    model.lines.push(vertices.to_vec());
+    Ok(())
+  }
+
+  pub fn add_points(&mut self, vertices: &[VertexIndex]) -> Result<(), ParserError> {
+    let model = self.models.last_mut().ok_or(ParserError::ModelNotInit)?;
+    model.points.extend_from_slice(vertices);
+    Ok(())
+  }
+
+  /// `0` turns the smoothing off
+  pub fn set_smoothing_group(&mut self, group: u32) {
+    self.smoothing_group = group;
+  }
+
   pub fn add_vertex(&mut self, vertex: Vec3) {
     self.vertices.push(vertex)
   }
@@ -117,6 +167,72 @@ impl ObjData {
       f(s)
     }
   }
+
+  /// The faces of `model` as a geometry, 3 vertices per triangle. The `vt` and
+  /// `vn` data become the "uv" and "normal" attributes, the corners without
+  /// `vn` data get generated normals which respect the `s` groups. A model
+  /// without any smoothing group is smoothed as a whole. The models with uvs
+  /// get a "tangent" attribute too.
+  pub fn to_geometry(&self, model: &Model, options: &NormalGeneration) -> BufferGeometry {
+    let corners: Vec<&VertexIndex> = model.faces.iter().flat_map(|f| &f.vertices).collect();
+    let mut geometry = BufferGeometry::default();
+
+    let positions = corners
+      .iter()
+      .map(|v| self.vertices[v.position_index as usize])
+      .flat_map(|p| [p.x, p.y, p.z])
+      .collect();
+    geometry.set_attribute(
+      "position",
+      F32BufferAttribute::new(positions, 3, false).as_enum(),
+    );
+
+    // the uvs of every corner, or none
+    let uvs: Option<Vec<Vec2>> = corners
+      .iter()
+      .map(|v| v.uv_index.map(|i| self.uvs[i as usize]))
+      .collect();
+    if let Some(uvs) = uvs {
+      let data = uvs.iter().flat_map(|uv| [uv.x, uv.y]).collect();
+      geometry.set_attribute("uv", F32BufferAttribute::new(data, 2, false).as_enum());
+    }
+
+    let mut normals: Vec<Option<Vec3>> = corners
+      .iter()
+      .map(|v| v.normal_index.map(|i| self.normals[i as usize]))
+      .collect();
+    if normals.iter().any(Option::is_none) {
+      if model.faces.iter().any(|f| f.smoothing_group != 0) {
+        let groups = model
+          .faces
+          .iter()
+          .flat_map(|f| [f.smoothing_group; 3])
+          .collect();
+        geometry.set_attribute(
+          "smoothing_group",
+          U32BufferAttribute::new(groups, 1, false).as_enum(),
+        );
+      }
+      geometry.compute_vertex_normals(options);
+
+      // the `vn` data wins over the generated normals
+      if let Some(TypeBufferEnum::F32(generated)) = geometry.get_attribute().get("normal") {
+        for (i, normal) in normals.iter_mut().enumerate() {
+          normal.get_or_insert_with(|| generated.get_vec3(i));
+        }
+      }
+    }
+    let data = normals
+      .iter()
+      .flatten()
+      .flat_map(|n| [n.x, n.y, n.z])
+      .collect();
+    geometry.set_attribute("normal", F32BufferAttribute::new(data, 3, false).as_enum());
+    // for the tangent space normal maps, skipped without uvs
+    geometry.compute_tangents();
+
+    geometry
+  }
 }
 
 pub struct ObjParserImpl;
@@ -139,47 +255,32 @@ impl Parse<ObjData> for ObjParserImpl {
         "mtllib" => data.add_mtllib(working_dir, parse_token!(tokens.next(); String)?)?,
         "usemtl" => data.bind_material(parse_token!(tokens.next(); String)?)?,
         "f" => {
-          let mut vertex_vec = vec![];
-          let mut done = false;
-
-          while !done {
-            match tokens.next() {
-              Some(str) => {
-                let splited: Vec<&str> = str.split("/").collect();
-                let indices = splited.as_slice();
-                if indices.len() > 3 || indices.len() < 1 {
-                  return Err(ParserError::InvalidSyntax(format!("face vertex indices")));
-                }
-
-                let (mut texture_index, mut normal_index) = (None, None);
-
-                match *indices {
-                  [_, second, third] => {
-                    if !second.is_empty() {
-                      texture_index = Some(parse_num!(second, u32) - 1);
-                    }
-                    normal_index = Some(parse_num!(third, u32) - 1)
-                  }
-                  [_, second] => {
-                    texture_index = Some(parse_num!(second, u32) - 1);
-                  }
-                  _ => return Err(ParserError::InvalidSyntax("face vertex format".to_string())),
-                }
-
-                let vertex_index = parse_num!(indices[0], u32) - 1;
-                vertex_vec.push(VertexIndex::new(vertex_index, normal_index, texture_index));
-              }
-              None => {
-                done = true;
-              }
-            }
-          }
-          if vertex_vec.len() != 3 {
+          let vertices = parse_vertex_indices(tokens, data)?;
+          if vertices.len() < 3 {
             return Err(ParserError::InvalidSyntax("Face Vertices".to_string()));
           }
-
-          let vertices: [VertexIndex; 3] = [vertex_vec[0], vertex_vec[1], vertex_vec[2]];
-          data.add_face(Face { vertices })?;
+          data.add_polygon(&vertices)?;
+        }
+        "l" => {
+          let vertices = parse_vertex_indices(tokens, data)?;
+          if vertices.len() < 2 {
+            return Err(ParserError::InvalidSyntax("Line Vertices".to_string()));
+          }
+          data.add_polyline(&vertices)?;
+        }
+        "p" => {
+          let vertices = parse_vertex_indices(tokens, data)?;
+          if vertices.is_empty() {
+            return Err(ParserError::InvalidSyntax("Point Vertices".to_string()));
+          }
+          data.add_points(&vertices)?;
+        }
+        "s" => {
+          let group = match parse_token!(tokens.next(); String)?.as_str() {
+            "off" => 0,
+            group => parse_num!(group, u32),
+          };
+          data.set_smoothing_group(group);
         }
         _ => {}
       }
@@ -196,6 +297,20 @@ impl Parse<ObjData> for ObjParserImpl {
   }
 }
 
+/// the vertices of an `f`, `l` or `p` element
+fn parse_vertex_indices(
+  tokens: &mut std::str::SplitWhitespace,
+  data: &ObjData,
+) -> Result<Vec<VertexIndex>, ParserError> {
+  let counts = ObjCounts {
+    positions: data.vertices.len(),
+    uvs: data.uvs.len(),
+    normals: data.normals.len(),
+  };
+  let vertices = parse_obj_vertices(tokens, counts).map_err(ParserError::InvalidSyntax)?;
+  Ok(vertices.into_iter().map(VertexIndex::from).collect())
+}
+
 type ObjLoader = Loader<ObjData, ObjParserImpl>;
 
 lazy_static! {
@@ -1,8 +1,12 @@
-use crate::core::uniform::{Uniform, UniformTypeEnum};
+use crate::core::{
+  uniform::{u, Uniform},
+  varying::{add_v, v},
+};
+use math::{Vec2, Vec4};
 
 use super::{
   material::{BasicMaterial, ToUniform},
-  shader::DefineShader,
+  shader::{DefineShader, Shader},
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -24,35 +28,127 @@ impl Into<f32> for DepthPacking {
   }
 }
 
+impl From<i32> for DepthPacking {
+  fn from(value: i32) -> Self {
+    match value {
+      3201 => DepthPacking::RGBADepthPacking,
+      3202 => DepthPacking::RGBDepthPacking,
+      3203 => DepthPacking::RGDepthPacking,
+      _ => DepthPacking::BasicDepthPacking,
+    }
+  }
+}
+
 impl Default for DepthPacking {
   fn default() -> Self {
     Self::BasicDepthPacking
   }
 }
 
+impl DepthPacking {
+  /// the channels holding the depth, from the most significant one
+  fn channels(self) -> usize {
+    match self {
+      DepthPacking::BasicDepthPacking => 1,
+      DepthPacking::RGDepthPacking => 2,
+      DepthPacking::RGBDepthPacking => 3,
+      DepthPacking::RGBADepthPacking => 4,
+    }
+  }
+}
+
+/// Spreads a depth in [0, 1] over 8 bits per channel. The basic packing is a
+/// grey level, the channels left over by the other ones are 0, alpha is 1.
+pub fn pack_depth(depth: f32, packing: DepthPacking) -> Vec4 {
+  let depth = depth.clamp(0.0, 1.0) as f64;
+  if let DepthPacking::BasicDepthPacking = packing {
+    let depth = depth as f32;
+    return Vec4::new(depth, depth, depth, 1.0);
+  }
+
+  let channels = packing.channels();
+  let max = (1u64 << (8 * channels)) - 1;
+  let bits = (depth * max as f64).round() as u64;
+  let mut res = [0.0, 0.0, 0.0, 1.0];
+  for (i, channel) in res.iter_mut().take(channels).enumerate() {
+    let shift = 8 * (channels - 1 - i);
+    *channel = ((bits >> shift) & 0xff) as f32 / 255.0;
+  }
+  Vec4::new(res[0], res[1], res[2], res[3])
+}
+
+/// the depth written by `pack_depth`
+pub fn unpack_depth(color: Vec4, packing: DepthPacking) -> f32 {
+  let channels = packing.channels();
+  if channels == 1 {
+    return color.x;
+  }
+
+  let max = (1u64 << (8 * channels)) - 1;
+  let bits = [color.x, color.y, color.z, color.w]
+    .iter()
+    .take(channels)
+    .fold(0u64, |bits, c| (bits << 8) | (c * 255.0).round() as u64);
+  (bits as f64 / max as f64) as f32
+}
+
+/// The mean depth and the mean squared depth, over 16 bits each in the red &
+/// green and the blue & alpha channels. The square root of the second one is
+/// stored, as precise as the first one for the small depths.
+pub fn pack_moments(moments: Vec2) -> Vec4 {
+  let mean = pack_depth(moments.x, DepthPacking::RGDepthPacking);
+  let mean_2 = pack_depth(moments.y.max(0.0).sqrt(), DepthPacking::RGDepthPacking);
+  Vec4::new(mean.x, mean.y, mean_2.x, mean_2.y)
+}
+
+/// the moments written by `pack_moments`
+pub fn unpack_moments(color: Vec4) -> Vec2 {
+  let unpack =
+    |a: f32, b: f32| unpack_depth(Vec4::new(a, b, 0.0, 1.0), DepthPacking::RGDepthPacking);
+  Vec2::new(unpack(color.x, color.y), unpack(color.z, color.w).powi(2))
+}
+
 #[derive(Debug, Default)]
 pub struct MeshDepthAttribute {
-  depth_packing: DepthPacking,
+  pub depth_packing: DepthPacking,
+}
+
+impl MeshDepthAttribute {
+  pub fn new(depth_packing: DepthPacking) -> Self {
+    Self { depth_packing }
+  }
 }
 
 impl ToUniform for MeshDepthAttribute {
   fn to_uniform(&self) -> Uniform {
     let mut res = Uniform::default();
-    let a: f32 = self.depth_packing.into();
-    res.insert("wireframe", UniformTypeEnum::Float(a));
+    res.insert("depth_packing", self.depth_packing as i32);
     res
   }
 }
 
+/// Writes the depth of the fragments, 0 at the near plane and 1 at the far
+/// one, packed by the `depth_packing` uniform.
 pub struct DepthShader {}
 
 impl DefineShader for DepthShader {
   fn vertex() -> super::shader::VertexShader {
-    todo!()
+    let default_vertex = Shader::default_vertex();
+    Box::new(move |attribute, uniform, varying, gl| {
+      default_vertex(attribute, uniform, varying, gl);
+      // z & w are interpolated apart, their ratio is not linear on screen
+      add_v!(varying, "clip_position", gl.gl_position);
+    })
   }
 
   fn fragment() -> super::shader::FragmentShader {
-    todo!()
+    Box::new(|uniform, varying, gl| {
+      let clip_position = v!(varying, Vec4, "clip_position", !);
+      let depth = clip_position.z / clip_position.w * 0.5 + 0.5;
+      let packing = u!(uniform, i32, "depth_packing").map_or(DepthPacking::default(), Into::into);
+      gl.gl_frag_color = pack_depth(depth, packing);
+      true
+    })
   }
 }
 pub type MeshDepthMaterial = BasicMaterial<MeshDepthAttribute, DepthShader>;
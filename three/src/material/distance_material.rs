@@ -0,0 +1,51 @@
+use crate::core::{
+  buffer_attribute::a,
+  uniform::u,
+  varying::{add_v, v},
+};
+use math::{Mat4, Vec3, Vec4};
+
+use super::{
+  depth_material::{pack_depth, DepthPacking, MeshDepthAttribute},
+  material::BasicMaterial,
+  shader::{DefineShader, Shader},
+};
+
+/// Writes the distance of the fragments to the `reference_position` uniform,
+/// 0 at `shadowCameraNear` and 1 at `shadowCameraFar`, packed by the
+/// `depth_packing` uniform. The maps of the point lights hold it in place of
+/// the depth, the same in every direction.
+pub struct DistanceShader {}
+
+impl DefineShader for DistanceShader {
+  fn vertex() -> super::shader::VertexShader {
+    let default_vertex = Shader::default_vertex();
+    Box::new(move |attribute, uniform, varying, gl| {
+      default_vertex(attribute, uniform, varying, gl);
+      let position = a!(attribute, Vec3, "position", !);
+      let model_matrix = u!(uniform, Mat4, "model_matrix", !);
+      let world_position = model_matrix * Vec4::from_vec3(&position, 1.0);
+      add_v!(
+        varying,
+        "world_position",
+        world_position.truncated_to_vec3()
+      );
+    })
+  }
+
+  fn fragment() -> super::shader::FragmentShader {
+    Box::new(|uniform, varying, gl| {
+      let world_position = v!(varying, Vec3, "world_position", !);
+      let reference_position = u!(uniform, Vec3, "reference_position", !);
+      let near = u!(uniform, f32, "shadowCameraNear", !);
+      let far = u!(uniform, f32, "shadowCameraFar", !);
+
+      let distance = (world_position - reference_position).length();
+      let distance = (distance - near) / (far - near);
+      let packing = u!(uniform, i32, "depth_packing").map_or(DepthPacking::default(), Into::into);
+      gl.gl_frag_color = pack_depth(distance, packing);
+      true
+    })
+  }
+}
+pub type MeshDistanceMaterial = BasicMaterial<MeshDepthAttribute, DistanceShader>;
@@ -36,6 +36,14 @@ pub enum Side {
   DoubleSide,
 }
 
+/// the footprint of a point on screen, `gl_point_size` pixels wide
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PointShape {
+  #[default]
+  Square,
+  Disc,
+}
+
 impl Default for Side {
   fn default() -> Self {
     Self::FrontSide
@@ -77,6 +85,10 @@ pub struct BasicMaterial<T: ToUniform + Default, U: DefineShader> {
   pub wireframe: bool,
   pub wireframe_linewidth: u8,
 
+  /// in pixels, the default vertex shader passes it on as `gl_point_size`
+  pub point_size: f32,
+  pub point_shape: PointShape,
+
   pub attributes: RefCell<Rc<T>>,
 
   pub abstract_shader: PhantomData<U>,
@@ -90,6 +102,7 @@ pub trait IMaterial: RunShader {
   fn visible(&self) -> bool;
   fn wireframe(&self) -> bool;
   fn wireframe_linewidth(&self) -> u8;
+  fn point_shape(&self) -> PointShape;
   fn to_uniform(&self) -> Uniform;
   fn depth_test(&self) -> bool;
   fn depth_write(&self) -> bool;
@@ -107,7 +120,9 @@ impl<T: ToUniform + Default, U: DefineShader> IMaterial for BasicMaterial<T, U>
   }
 
   fn to_uniform(&self) -> Uniform {
-    self.attributes.borrow().to_uniform()
+    let mut uniform = self.attributes.borrow().to_uniform();
+    uniform.insert("point_size", self.point_size);
+    uniform
   }
 
   fn wireframe(&self) -> bool {
@@ -118,6 +133,10 @@ impl<T: ToUniform + Default, U: DefineShader> IMaterial for BasicMaterial<T, U>
     self.wireframe_linewidth
   }
 
+  fn point_shape(&self) -> PointShape {
+    self.point_shape
+  }
+
   fn depth_test(&self) -> bool {
     self.depth_test
   }
@@ -151,14 +170,17 @@ impl<T: ToUniform + Default, U: DefineShader> Default for BasicMaterial<T, U> {
       opacity: Default::default(),
       transparent: Default::default(),
       transmission: Default::default(),
-      visible: Default::default(),
-      depth_test: Default::default(),
+      visible: true,
+      depth_test: true,
       depth_func: Default::default(),
-      depth_write: Default::default(),
+      depth_write: true,
       attributes: Default::default(),
       abstract_shader: Default::default(),
       wireframe: Default::default(),
-      wireframe_linewidth: Default::default(),
+      wireframe_linewidth: 1,
+      point_size: 1.0,
+      point_shape: Default::default(),
+      map: Default::default(),
     }
   }
 }
@@ -1,6 +1,8 @@
 pub mod depth_material;
+pub mod distance_material;
 pub mod material;
 pub mod shader;
 pub mod standard_material;
 pub mod shader_material;
+pub mod shadow;
 pub mod presets;
\ No newline at end of file
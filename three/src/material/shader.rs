@@ -51,6 +51,7 @@ impl Shader {
       let projection_matrix = u!(unifrom, Mat4, "projection_matrix", !);
       let position = Vec4::from_vec3(&a!(attribute, Vec3, "position", !), 1.0);
       gl.gl_position = projection_matrix * view_matrix * model_matrix * position;
+      gl.gl_point_size = u!(unifrom, f32, "point_size").unwrap_or(1.0);
     });
     vertex
   }
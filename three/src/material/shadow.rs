@@ -0,0 +1,263 @@
+//! The shadow maps as seen from the receiving materials. The vertex shaders
+//! place their vertices in the maps, the fragment shaders compare their depth
+//! with the one stored there, filtered by the `shadow_map_type` uniform. The
+//! maps of the point lights are cubes of distances, looked up by direction.
+
+use crate::{
+  core::{
+    render_target::RenderTarget,
+    uniform::{u, Uniform},
+    varying::{add_v, v, Varying},
+  },
+  lights::point_light_shadow::{CUBE_DIRECTIONS, CUBE_FRAME_EXTENTS, CUBE_UPS, CUBE_VIEWPORTS},
+  material::depth_material::{unpack_depth, unpack_moments},
+  renderer::shadow_map::{ShadowMapType, SHADOW_DEPTH_PACKING},
+};
+use math::{Mat4, Vec2, Vec3, Vec4};
+
+/// Adds the `directional_shadow_coord[i]` and the `point_shadow_coord[i]`
+/// varyings, the vertex in the map of every directional shadow, and relative
+/// to every point light casting a shadow. It is pushed along its world normal
+/// by the `shadowNormalBias` of the shadow first.
+pub fn shadow_vertex(
+  uniform: &Uniform,
+  varying: &mut Varying,
+  world_position: Vec3,
+  world_normal: Vec3,
+) {
+  for light in ["directional", "point"] {
+    let key = format!("{light}_light_shadow_count");
+    let key = key.as_str();
+    let count = u!(uniform, i32, key).unwrap_or(0);
+    for i in 0..count {
+      let key = format!("{light}_light_shadows[{i}].shadowNormalBias");
+      let key = key.as_str();
+      let normal_bias = u!(uniform, f32, key).unwrap_or(0.0);
+      let key = format!("{light}_shadow_matrix[{i}]");
+      let key = key.as_str();
+      let shadow_matrix = u!(uniform, Mat4, key, !);
+
+      let position = world_position + world_normal * normal_bias;
+      let key = format!("{light}_shadow_coord[{i}]");
+      let key = key.as_str();
+      add_v!(
+        varying,
+        key,
+        shadow_matrix * Vec4::from_vec3(&position, 1.0)
+      );
+    }
+  }
+}
+
+/// the filter, the bias, the radius and the intensity of a shadow
+struct ShadowParams {
+  shadow_type: ShadowMapType,
+  bias: f32,
+  radius: f32,
+  intensity: f32,
+}
+
+impl ShadowParams {
+  /// the uniforms of the shadow `index` of the `light` lights
+  fn new(uniform: &Uniform, light: &str, index: i32) -> Self {
+    let param = |name: &str, default: f32| {
+      let key = format!("{light}_light_shadows[{index}].{name}");
+      let key = key.as_str();
+      u!(uniform, f32, key).unwrap_or(default)
+    };
+
+    Self {
+      shadow_type: u!(uniform, i32, "shadow_map_type").map_or(Default::default(), Into::into),
+      bias: param("shadowBias", 0.0),
+      radius: param("shadowRadius", 1.0),
+      intensity: param("shadowIntensity", 1.0),
+    }
+  }
+
+  /// from `1 - intensity` in the shadow to 1
+  fn light(&self, shadow: f32) -> f32 {
+    1.0 - self.intensity * (1.0 - shadow)
+  }
+}
+
+/// The light let through by the directional shadow `index`, from
+/// `1 - shadowIntensity` in the shadow to 1. The fragments out of the map are
+/// lit.
+pub fn directional_shadow(uniform: &Uniform, varying: &Varying, index: i32) -> f32 {
+  let key = format!("directional_shadow_coord[{index}]");
+  let key = key.as_str();
+  let Some(coord) = v!(varying, Vec4, key) else {
+    return 1.0;
+  };
+  let Some(map) = uniform.sampler(&format!("directional_shadow_map[{index}]")) else {
+    return 1.0;
+  };
+
+  let coord = coord / coord.w;
+  let in_frustum = (0.0..=1.0).contains(&coord.x) && (0.0..=1.0).contains(&coord.y);
+  if !in_frustum || coord.z > 1.0 {
+    return 1.0;
+  }
+
+  let params = ShadowParams::new(uniform, "directional", index);
+  let (width, height) = map.texture().size();
+  // v goes up the map
+  let (x, y) = (coord.x * width as f32, (1.0 - coord.y) * height as f32);
+  let bounds = [0, 0, width as i32, height as i32];
+
+  let depth = coord.z + params.bias;
+  params.light(filter_shadow(map, &params, x, y, depth, bounds))
+}
+
+/// The face of the cube looking at `direction`, and where the direction
+/// crosses it from (0, 0) at its top left corner to (1, 1), as the faces are
+/// rendered.
+fn cube_face(direction: Vec3) -> (usize, f32, f32) {
+  let Vec3 { x, y, z } = direction;
+  let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+  let face = if ax >= ay && ax >= az {
+    if x > 0.0 {
+      0
+    } else {
+      1
+    }
+  } else if az >= ay {
+    if z > 0.0 {
+      2
+    } else {
+      3
+    }
+  } else if y > 0.0 {
+    4
+  } else {
+    5
+  };
+
+  // the axes of the camera of the face, looking down its -z
+  let forward = CUBE_DIRECTIONS[face];
+  let back = forward * -1.0;
+  let right = CUBE_UPS[face].cross(&back).normalize();
+  let up = back.cross(&right);
+
+  let distance = direction * forward;
+  let (ndc_x, ndc_y) = (direction * right / distance, direction * up / distance);
+  (face, ndc_x * 0.5 + 0.5, 0.5 - ndc_y * 0.5)
+}
+
+/// The light let through by the point shadow `index`, from
+/// `1 - shadowIntensity` in the shadow to 1. The fragments beyond the far
+/// plane of the shadow camera are lit.
+pub fn point_shadow(uniform: &Uniform, varying: &Varying, index: i32) -> f32 {
+  let key = format!("point_shadow_coord[{index}]");
+  let key = key.as_str();
+  let Some(coord) = v!(varying, Vec4, key) else {
+    return 1.0;
+  };
+  let Some(map) = uniform.sampler(&format!("point_shadow_map[{index}]")) else {
+    return 1.0;
+  };
+
+  let key = format!("point_light_shadows[{index}].shadowCameraNear");
+  let key = key.as_str();
+  let near = u!(uniform, f32, key, !);
+  let key = format!("point_light_shadows[{index}].shadowCameraFar");
+  let key = key.as_str();
+  let far = u!(uniform, f32, key, !);
+
+  // the coordinates are relative to the light
+  let direction = coord.truncated_to_vec3();
+  let distance = (direction.length() - near) / (far - near);
+  if distance > 1.0 {
+    return 1.0;
+  }
+
+  let params = ShadowParams::new(uniform, "point", index);
+  let (width, height) = map.texture().size();
+  let face_width = width as f32 / CUBE_FRAME_EXTENTS.x;
+  let face_height = height as f32 / CUBE_FRAME_EXTENTS.y;
+
+  let (face, s, t) = cube_face(direction);
+  let viewport = CUBE_VIEWPORTS[face];
+  let (left, top) = (viewport.x * face_width, viewport.y * face_height);
+  let (x, y) = (left + s * face_width, top + t * face_height);
+  // the filters stay on the face
+  let bounds = [
+    left as i32,
+    top as i32,
+    (left + face_width) as i32,
+    (top + face_height) as i32,
+  ];
+
+  let depth = distance + params.bias;
+  params.light(filter_shadow(map, &params, x, y, depth, bounds))
+}
+
+/// The share of the texels around (x, y) nearer to the light than `depth`,
+/// the position being in texels from the top left corner of the map. The
+/// texels are read within the `bounds`, left, top, right and bottom. The VSM
+/// maps hold the moments of the depths rather than the depths, see
+/// `pack_moments`.
+fn filter_shadow(
+  map: &RenderTarget,
+  params: &ShadowParams,
+  x: f32,
+  y: f32,
+  depth: f32,
+  bounds: [i32; 4],
+) -> f32 {
+  let texture = map.texture();
+  let [left, top, right, bottom] = bounds;
+  let texel_depth = |x: i32, y: i32| {
+    let (x, y) = (x.clamp(left, right - 1), y.clamp(top, bottom - 1));
+    unpack_depth(texture.texel(x, y), SHADOW_DEPTH_PACKING)
+  };
+  let compare = |x: i32, y: i32| if depth <= texel_depth(x, y) { 1.0 } else { 0.0 };
+
+  let radius = params.radius;
+  let offsets = || (-1..=1).flat_map(|j| (-1..=1).map(move |i| (i as f32, j as f32)));
+
+  match params.shadow_type {
+    ShadowMapType::BasicShadowMap => compare(x.floor() as i32, y.floor() as i32),
+    ShadowMapType::PCFShadowMap => {
+      let sum: f32 = offsets()
+        .map(|(i, j)| {
+          let (x, y) = (x + i * radius, y + j * radius);
+          compare(x.floor() as i32, y.floor() as i32)
+        })
+        .sum();
+      sum / 9.0
+    }
+    ShadowMapType::PCFSoftShadowMap => {
+      // the comparisons of the four nearest texels, weighted bilinearly
+      let bilinear = |x: f32, y: f32| {
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i32, y0 as i32);
+        let top = compare(x0, y0) * (1.0 - fx) + compare(x0 + 1, y0) * fx;
+        let bottom = compare(x0, y0 + 1) * (1.0 - fx) + compare(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+      };
+      let sum: f32 = offsets()
+        .map(|(i, j)| bilinear(x + i * radius, y + j * radius))
+        .sum();
+      sum / 9.0
+    }
+    ShadowMapType::VSMShadowMap => {
+      // the shadow pass blurred the moments of the depths over the radius
+      let (x, y) = (x.floor() as i32, y.floor() as i32);
+      let (x, y) = (x.clamp(left, right - 1), y.clamp(top, bottom - 1));
+      let Vec2 { x: mean, y: mean_2 } = unpack_moments(texture.texel(x, y));
+      if depth <= mean {
+        return 1.0;
+      }
+
+      // the moments hold 16 bits each
+      let variance = (mean_2 - mean * mean).max(1e-6);
+      let d = depth - mean;
+      let p_max = variance / (variance + d * d);
+      // cuts the tail of the bound, which lights the penumbras too much
+      ((p_max - 0.3) / 0.65).clamp(0.0, 1.0)
+    }
+  }
+}
@@ -1,12 +1,19 @@
 use crate::{
-  core::{buffer_geometry::Attribute, uniform::Uniform, varying::Varying},
+  core::{
+    buffer_attribute::a,
+    buffer_geometry::Attribute,
+    uniform::{u, Uniform, UniformTypeEnum},
+    varying::{add_v, v, Varying},
+    Extract,
+  },
   loaders::mtl_loader::MtlData,
 };
-use math::Vec3;
+use math::{Mat3, Mat4, Vec3, Vec4};
 
 use super::{
   material::{define_material_attribute, BasicMaterial},
-  shader::{DefineShader, GlPerFragment, GlPerVertex},
+  shader::{DefineShader, GlPerFragment, GlPerVertex, Shader},
+  shadow::{directional_shadow, point_shadow, shadow_vertex},
 };
 
 define_material_attribute!(
@@ -23,20 +30,165 @@ define_material_attribute!(
   illum->illum:u32
 );
 
+/// the normals and the positions are passed on in view space, where the
+/// lights are given, the receivers of shadows place the vertex in the shadow
+/// maps too
 fn standard_vertex_shader(
   attribute: &Attribute,
   uniform: &Uniform,
   varying: &mut Varying,
   gl_vertex: &mut GlPerVertex,
 ) {
+  Shader::default_vertex()(attribute, uniform, varying, gl_vertex);
+
+  if let Some(normal) = a!(attribute, Vec3, "normal") {
+    let normal_matrix = u!(uniform, Mat3, "normal_matrix", !);
+    let normal = normal_matrix * normal;
+    add_v!(varying, "normal", normal);
+
+    let position = a!(attribute, Vec3, "position", !);
+    let model_view_matrix = u!(uniform, Mat4, "model_view_matrix", !);
+    let view_position = model_view_matrix * Vec4::from_vec3(&position, 1.0);
+    add_v!(varying, "view_position", view_position.truncated_to_vec3());
+
+    if u!(uniform, bool, "receive_shadow").unwrap_or(false) {
+      let model_matrix = u!(uniform, Mat4, "model_matrix", !);
+      let view_matrix = u!(uniform, Mat4, "view_matrix", !);
+      let world_position = (model_matrix * Vec4::from_vec3(&position, 1.0)).truncated_to_vec3();
+      // back from the view space, the view matrix being a rigid motion
+      let world_normal = view_matrix.truncated_to_mat3().transpose() * normal;
+      shadow_vertex(uniform, varying, world_position, world_normal.normalize());
+    }
+  }
+}
+
+/// the light left at `distance` from a light of range `cutoff`, falling off
+/// by the inverse of the distance to the power `decay` and smoothly down to 0
+/// at the cutoff
+fn distance_attenuation(distance: f32, cutoff: f32, decay: f32) -> f32 {
+  let mut attenuation = 1.0 / distance.powf(decay).max(0.01);
+  if cutoff > 0.0 {
+    let falloff = (1.0 - (distance / cutoff).powi(4)).clamp(0.0, 1.0);
+    attenuation *= falloff * falloff;
+  }
+  attenuation
+}
+
+/// a step at `high` when the edges meet
+fn smoothstep(low: f32, high: f32, x: f32) -> f32 {
+  if high <= low {
+    return if x >= high { 1.0 } else { 0.0 };
+  }
+  let t = ((x - low) / (high - low)).clamp(0.0, 1.0);
+  t * t * (3.0 - 2.0 * t)
+}
+
+/// the uniform `key` of a light, which `GLLights::upload` always fills in
+fn light_uniform<T>(uniform: &Uniform, key: String) -> T
+where
+  UniformTypeEnum: Extract<T>,
+{
+  let key = key.as_str();
+  u!(uniform, T, key, !)
 }
 
+/// The light reaching a fragment, the lights casting a shadow are dimmed by it
+/// on the receivers.
+fn irradiance(uniform: &Uniform, varying: &Varying, normal: Vec3) -> Vec3 {
+  let count = |name: &str| {
+    let key = format!("{name}_count");
+    let key = key.as_str();
+    u!(uniform, i32, key).unwrap_or(0)
+  };
+
+  let mut irradiance = u!(uniform, Vec3, "ambient_light_color").unwrap_or(Vec3::zero());
+
+  let receive_shadow = u!(uniform, bool, "receive_shadow").unwrap_or(false);
+  for i in 0..count("directional_light") {
+    let direction: Vec3 = light_uniform(uniform, format!("directional_lights[{i}].direction"));
+    let light_color: Vec4 = light_uniform(uniform, format!("directional_lights[{i}].color"));
+    let shadow = match light_uniform::<i32>(uniform, format!("directional_lights[{i}].shadow")) {
+      index if receive_shadow && index >= 0 => directional_shadow(uniform, varying, index),
+      _ => 1.0,
+    };
+    let n_dot_l = (normal * direction.normalize()).max(0.0);
+    irradiance += light_color.truncated_to_vec3() * (n_dot_l * shadow);
+  }
+
+  // the ambient light of the sky and the ground, weighted by the facing
+  for i in 0..count("hemisphere_light") {
+    let direction: Vec3 = light_uniform(uniform, format!("hemisphere_lights[{i}].direction"));
+    let sky_color: Vec3 = light_uniform(uniform, format!("hemisphere_lights[{i}].sky_color"));
+    let ground_color: Vec3 = light_uniform(uniform, format!("hemisphere_lights[{i}].ground_color"));
+
+    let weight = 0.5 * (normal * direction) + 0.5;
+    irradiance += ground_color * (1.0 - weight) + sky_color * weight;
+  }
+
+  let Some(position) = v!(varying, Vec3, "view_position") else {
+    return irradiance;
+  };
+
+  for i in 0..count("point_light") {
+    let light_position: Vec3 = light_uniform(uniform, format!("point_lights[{i}].position"));
+    let light_color: Vec4 = light_uniform(uniform, format!("point_lights[{i}].color"));
+    let cutoff: f32 = light_uniform(uniform, format!("point_lights[{i}].distance"));
+    let decay: f32 = light_uniform(uniform, format!("point_lights[{i}].decay"));
+
+    let shadow = match light_uniform::<i32>(uniform, format!("point_lights[{i}].shadow")) {
+      index if receive_shadow && index >= 0 => point_shadow(uniform, varying, index),
+      _ => 1.0,
+    };
+
+    let to_light = light_position - position;
+    let distance = to_light.length();
+    let n_dot_l = (normal * to_light.normalize()).max(0.0);
+    let attenuation = distance_attenuation(distance, cutoff, decay);
+    irradiance += light_color.truncated_to_vec3() * (n_dot_l * attenuation * shadow);
+  }
+
+  for i in 0..count("spot_light") {
+    let light_position: Vec3 = light_uniform(uniform, format!("spot_lights[{i}].position"));
+    let direction: Vec3 = light_uniform(uniform, format!("spot_lights[{i}].direction"));
+    let light_color: Vec4 = light_uniform(uniform, format!("spot_lights[{i}].color"));
+    let cutoff: f32 = light_uniform(uniform, format!("spot_lights[{i}].distance"));
+    let decay: f32 = light_uniform(uniform, format!("spot_lights[{i}].decay"));
+    let cone_cos: f32 = light_uniform(uniform, format!("spot_lights[{i}].cone_cos"));
+    let penumbra_cos: f32 = light_uniform(uniform, format!("spot_lights[{i}].penumbra_cos"));
+
+    let to_light = light_position - position;
+    let distance = to_light.length();
+    let to_light = to_light.normalize();
+    let n_dot_l = (normal * to_light).max(0.0);
+    let cone = smoothstep(cone_cos, penumbra_cos, to_light * direction);
+    let attenuation = distance_attenuation(distance, cutoff, decay) * cone;
+    irradiance += light_color.truncated_to_vec3() * (n_dot_l * attenuation);
+  }
+
+  irradiance
+}
+
+/// Lambert shading of the diffuse color by the lights, the geometries without
+/// normals are left unlit.
 fn standard_fragment_shader(
   uniform: &Uniform,
   varying: &Varying,
   gl_fragment: &mut GlPerFragment,
 ) -> bool {
-  false
+  let diffuse = u!(uniform, Vec3, "diffuse").unwrap_or(Vec3::new(1.0, 1.0, 1.0));
+  let emissive = u!(uniform, Vec3, "emissive_coeficient").unwrap_or(Vec3::zero());
+
+  let color = match v!(varying, Vec3, "normal") {
+    Some(normal) => {
+      let mut color = diffuse;
+      color *= irradiance(uniform, varying, normal.normalize());
+      color + emissive
+    }
+    None => diffuse + emissive,
+  };
+
+  gl_fragment.gl_frag_color = Vec4::from_vec3(&color, 1.0);
+  true
 }
 
 pub struct StandardShader {}
@@ -12,6 +12,8 @@ use crate::{
 
 use super::base::Renderable;
 
+/// a strip through the vertices of its geometry, `wireframe_linewidth` pixels
+/// wide
 #[object_3d(IObject3D)]
 pub struct Line {
   geometry: Rc<BufferGeometry>,
@@ -22,7 +24,14 @@ impl Line {
   pub fn new() -> std::rc::Rc<Self> {
     let geometry = Rc::new(Default::default());
     let material = Rc::new(Default::default());
-    with_default_fields!(Mesh; geometry, material)
+    with_default_fields!(Line; geometry, material)
+  }
+
+  pub fn from_geometry(
+    geometry: Rc<BufferGeometry>,
+    material: Rc<StandardMeshMaterial>,
+  ) -> std::rc::Rc<Self> {
+    with_default_fields!(Line; geometry, material)
   }
 }
 
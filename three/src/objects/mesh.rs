@@ -22,6 +22,13 @@ impl Mesh {
     let material = Rc::new(Default::default());
     with_default_fields!(Mesh; geometry, material)
   }
+
+  pub fn from_geometry(
+    geometry: Rc<BufferGeometry>,
+    material: Rc<StandardMeshMaterial>,
+  ) -> std::rc::Rc<Self> {
+    with_default_fields!(Mesh; geometry, material)
+  }
 }
 
 impl Renderable for Mesh {
@@ -32,6 +32,7 @@ pub struct GLLights {
   pub directional: LightQueue,
   pub point: LightQueue,
   pub spot: LightQueue,
+  pub hemi: LightQueue,
 }
 
 impl GLLights {
@@ -40,9 +41,23 @@ impl GLLights {
     self.directional.clear();
     self.point.clear();
     self.spot.clear();
+    self.hemi.clear();
   }
 
-  pub fn setup(&mut self, lights: &Vec<Rc<dyn ILight>>) {
+  /// the queue of the lights of `light_type` drawn by the materials
+  fn queue_mut(&mut self, light_type: LightType) -> Option<&mut LightQueue> {
+    match light_type {
+      LightType::DirectionalLight => Some(&mut self.directional),
+      LightType::PointLight => Some(&mut self.point),
+      LightType::SpotLight => Some(&mut self.spot),
+      LightType::HemisphereLight => Some(&mut self.hemi),
+      _ => None,
+    }
+  }
+
+  /// `shadows` is whether the shadow maps were rendered for this frame
+  pub fn setup(&mut self, lights: &Vec<Rc<dyn ILight>>, shadows: bool) {
+    self.reset();
     for light in lights.iter() {
       match light.light_type() {
         LightType::AmbientLight => {
@@ -51,50 +66,103 @@ impl GLLights {
           self.ambient.y += color.y;
           self.ambient.z += color.z;
         }
-        LightType::DirectionalLight => {
+        LightType::DirectionalLight
+        | LightType::SpotLight
+        | LightType::PointLight
+        | LightType::HemisphereLight => {
+          let queue = self.queue_mut(light.light_type()).unwrap();
           let l = light.clone();
-          let uniform = (l as Rc<dyn ToUniform>).to_uniform();
-          self.directional.uniform.push(uniform);
-          if light.cast_shadow() {
+          let mut uniform = (l as Rc<dyn ToUniform>).to_uniform();
+          // the index of the shadow of the light, -1 without one
+          let mut shadow_index = -1;
+          if shadows && light.cast_shadow() {
             if let Some(shadow) = light.shadow() {
+              shadow_index = queue.shadow_uniform.len() as i32;
               let shadow_uniform = shadow.to_uniform();
-              self.directional.shadow_uniform.push(shadow_uniform);
-              self.directional.shadow_map.push(shadow.map().clone());
-              self.directional.shadow_matrix.push(shadow.matrix());
+              queue.shadow_uniform.push(shadow_uniform);
+              queue.shadow_map.push(shadow.map().clone());
+              queue.shadow_matrix.push(shadow.matrix());
             }
           }
+          uniform.insert("shadow", shadow_index);
+          queue.uniform.push(uniform);
         }
-        LightType::SpotLight => todo!(),
-        LightType::PointLight => todo!(),
 
         LightType::LightProbe => todo!(),
-        LightType::HemisphereLight => todo!(),
         LightType::RectAreaLight => todo!(),
       }
     }
   }
 
+  /// Adds the uniforms depending on the camera, the positions and the
+  /// directions of the lights in view space.
   pub fn setup_view(&mut self, lights: &Vec<Rc<dyn ILight>>, camera: Rc<dyn ICamera>) {
-    let mut i = 0;
+    // the next light of every queue, in the order of `setup`
+    let mut indices = [0; 4];
     for light in lights.iter() {
-      match light.light_type() {
-        LightType::DirectionalLight => {
-          let dummy = Uniform::default();
-          let uniform = std::mem::replace(&mut self.directional.uniform[i], dummy);
-          let l = light.clone();
-          let view_uniform = (l as Rc<dyn ToUniformWithView>).to_uniform(camera.clone());
-          let view_uniform = view_uniform.extends(uniform);
-          let _ = std::mem::replace(&mut self.directional.uniform[i], view_uniform);
-        }
-        LightType::SpotLight => todo!(),
-        LightType::PointLight => todo!(),
+      let slot = match light.light_type() {
+        LightType::DirectionalLight => 0,
+        LightType::PointLight => 1,
+        LightType::SpotLight => 2,
+        LightType::HemisphereLight => 3,
 
         LightType::LightProbe => todo!(),
-        LightType::HemisphereLight => todo!(),
         LightType::RectAreaLight => todo!(),
-        LightType::AmbientLight => {}
+        LightType::AmbientLight => continue,
+      };
+      let i = indices[slot];
+      indices[slot] += 1;
+
+      let queue = self.queue_mut(light.light_type()).unwrap();
+      let dummy = Uniform::default();
+      let uniform = std::mem::replace(&mut queue.uniform[i], dummy);
+      let l = light.clone();
+      let view_uniform = (l as Rc<dyn ToUniformWithView>).to_uniform(camera.clone());
+      let view_uniform = view_uniform.extends(uniform);
+      let _ = std::mem::replace(&mut queue.uniform[i], view_uniform);
+    }
+  }
+
+  /// Copies the lights into the uniforms of a draw, the uniforms of the nth
+  /// directional light are named `directional_lights[n].<name>`, those of its
+  /// shadow `directional_light_shadows[m].<name>` where m is its `shadow`.
+  /// The point, spot and hemisphere lights follow as `point_lights`,
+  /// `spot_lights` and `hemisphere_lights`, with their shadows.
+  pub fn upload(&self, uniform: &mut Uniform) {
+    uniform.insert("ambient_light_color", self.ambient.truncated_to_vec3());
+    let queues = [
+      ("directional_light", &self.directional),
+      ("point_light", &self.point),
+      ("spot_light", &self.spot),
+      ("hemisphere_light", &self.hemi),
+    ];
+    for (name, queue) in queues {
+      uniform.insert(&format!("{name}_count"), queue.uniform.len() as i32);
+      for (i, light) in queue.uniform.iter().enumerate() {
+        for (key, value) in light.iter() {
+          uniform.insert(&format!("{name}s[{i}].{key}"), *value);
+        }
+      }
+
+      uniform.insert(
+        &format!("{name}_shadow_count"),
+        queue.shadow_uniform.len() as i32,
+      );
+      // `directional_light` -> `directional_shadow_map`
+      let prefix = name.trim_end_matches("_light");
+      for (i, shadow) in queue.shadow_uniform.iter().enumerate() {
+        for (key, value) in shadow.iter() {
+          uniform.insert(&format!("{name}_shadows[{i}].{key}"), *value);
+        }
+        uniform.insert(
+          &format!("{prefix}_shadow_matrix[{i}]"),
+          queue.shadow_matrix[i],
+        );
+        uniform.insert_sampler(
+          &format!("{prefix}_shadow_map[{i}]"),
+          queue.shadow_map[i].clone(),
+        );
       }
-      i += 1;
     }
   }
 }
@@ -10,14 +10,17 @@ use crate::core::buffer_geometry::IGeometry;
 use crate::core::render_target::RenderTarget;
 use crate::core::uniform::Uniform;
 use crate::lights::directional_light::DirectionalLight;
+use crate::lights::hemisphere_light::HemisphereLight;
 use crate::lights::light::ILight;
+use crate::lights::point_light::PointLight;
+use crate::lights::spot_light::SpotLight;
 use crate::material::material::IMaterial;
-use math::Mat4;
 use crate::objects::base::Renderable;
 use crate::objects::group::Group;
 use crate::objects::line::Line;
 use crate::objects::mesh::Mesh;
 use crate::objects::point::Point;
+use math::Mat4;
 
 use crate::utils::rc_convert;
 use crate::{
@@ -55,8 +58,15 @@ impl GlRenderer {
 
   pub fn set_render_target(&mut self) {}
 
+  /// the shadow pass, disabled by default
+  pub fn shadow_map_mut(&mut self) -> &mut ShadowMap {
+    &mut self.shadow_map
+  }
+
   pub fn set_size(&mut self, w: f32, h: f32) {
     self.result.set_size(w, h);
+    let mut depth = self.depth.borrow_mut();
+    *depth = DepthBuffer::new(w as u32, h as u32);
   }
 
   pub fn clear(&self) {
@@ -90,7 +100,9 @@ impl GlRenderer {
 
         ObjectType::Light => {
           let obj = object.clone();
-          let light: Rc<dyn ILight> = rc_convert!(obj;DirectionalLight;"Unexpected Light Type");
+          let light: Rc<dyn ILight> = rc_convert!(
+            obj;DirectionalLight,PointLight,SpotLight,HemisphereLight;"Unexpected Light Type"
+          );
           current_render_state.push_light(light.clone());
 
           if object.cast_shadow() {
@@ -135,6 +147,7 @@ impl GlRenderer {
   pub fn render(&mut self, scene: Rc<Scene>, camera: Rc<dyn ICamera>) -> ColorBuffer {
     scene.update_global_matrix();
     camera.update_global_matrix();
+    self.clear();
 
     let project_matrix = camera.projection_matrix();
     let view_matrix = camera.view_matrix();
@@ -142,13 +155,15 @@ impl GlRenderer {
 
     let mut global_uniform = Uniform::default();
     global_uniform.insert("view_matrix", view_matrix);
-    global_uniform.insert("project_matrix", project_matrix);
+    global_uniform.insert("projection_matrix", project_matrix);
     global_uniform.insert("view_projection_matrix", view_projection_matrix);
+    global_uniform.insert("shadow_map_type", self.shadow_map.shadow_type() as i32);
 
     let scene_id = scene.uuid();
     let current_render_state = self.render_states.get(scene_id);
-    // self.current_render_list =
+    current_render_state.init(camera.clone());
     let current_render_list = self.render_lists.get(scene_id);
+    current_render_list.init();
 
     self.project_object(
       current_render_list.clone(),
@@ -168,7 +183,7 @@ impl GlRenderer {
       camera.clone(),
     );
 
-    current_render_state.setup_lights();
+    current_render_state.setup_lights(self.shadow_map.enable());
     self.render_scene(
       current_render_list.clone(),
       current_render_state.clone(),
@@ -193,6 +208,7 @@ impl GlRenderer {
     global_uniform: &mut Uniform,
   ) {
     render_state.setup_lights_view(camera.clone());
+    render_state.upload_lights(global_uniform);
     let opaque = render_list.opaque.borrow();
     if opaque.len() > 0 {
       self.render_objects(&opaque, scene.clone(), camera.clone(), global_uniform);
@@ -253,23 +269,20 @@ impl GlRenderer {
     m_uniform.insert("model_view_matrix", model_view_matrix);
     m_uniform.insert("model_matrix", model_matrix);
     m_uniform.insert("normal_matrix", normal_matrix);
+    m_uniform.insert("receive_shadow", object.receive_shadow());
 
-    // let mut uniform = global_uniform.merge(m_uniform);
-    m_uniform.merge(&global_uniform);
+    m_uniform.merge(global_uniform);
 
     let target = self.get_current_target();
     let mut depth_buffer = self.depth.borrow_mut();
     render_pipeline(
       target,
       &mut depth_buffer,
-      camera.clone(),
       object.clone(),
       geometry.clone(),
       material.clone(),
+      &mut m_uniform,
       None,
     );
-    // uniform.insert("model_matrix", model_matrix);
-
-    // let
   }
 }
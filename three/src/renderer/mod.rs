@@ -2,5 +2,5 @@ pub mod gl_renderer;
 mod render_states;
 
 mod render_pipeline;
-mod shadow_map;
+pub mod shadow_map;
 mod gl_lights;
\ No newline at end of file
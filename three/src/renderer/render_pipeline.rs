@@ -1,9 +1,7 @@
 use std::rc::Rc;
 
 use crate::{
-  cameras::camera::ICamera,
   core::{
-    buffer_attribute::{ToF32, TypeBufferAttribute, TypeBufferEnum},
     buffer_geometry::{pick_attribute_per_vertex, Attribute, IGeometry},
     object_3d::{IObject3D, ObjectType},
     render_target::RenderTarget,
@@ -11,11 +9,15 @@ use crate::{
     varying::Varying,
   },
   material::{
-    material::IMaterial,
+    material::{IMaterial, PointShape},
     shader::{GlPerFragment, GlPerVertex},
   },
 };
-use math::{data_array::DepthBuffer, Barycentric, BoundaryBox, Vec2};
+use math::{
+  clip::{clip_segment, clip_triangle, is_inside, triangulate, ClipResult, ClipVertex},
+  data_array::DepthBuffer,
+  Barycentric, EdgeRasterizer, Mat4, Vec2, Vec3, Vec4,
+};
 
 enum RenderMode {
   Triangle,
@@ -23,100 +25,304 @@ enum RenderMode {
   Line,
 }
 
-fn iter_triangle_verterx(attr: &Attribute, group: usize) -> [Attribute; 3] {
-  let first = pick_attribute_per_vertex(attr, group);
-  let second = pick_attribute_per_vertex(attr, group + 1);
-  let third = pick_attribute_per_vertex(attr, group + 2);
-  return [first, second, third];
+/// the state shared by the fragments of a draw, which stay in the viewport
+/// from (left, top) to (right, bottom)
+struct FragmentWriter<'a> {
+  target: &'a RenderTarget,
+  depth_buffer: &'a mut DepthBuffer,
+  material: &'a dyn IMaterial,
+  uniform: &'a Uniform,
+  left: u32,
+  top: u32,
+  right: u32,
+  bottom: u32,
 }
 
-fn render_triangle<T: Sized + Copy + ToF32>(
-  target: &RenderTarget,
-  depth_buffer: &mut DepthBuffer,
-  position: &Box<TypeBufferAttribute<T>>,
+impl FragmentWriter<'_> {
+  /// Shades the fragment at (x, y) when it passes the depth test, the
+  /// varyings are interpolated with `barycentric`. A fragment shader returning
+  /// false discards the fragment.
+  fn write(
+    &mut self,
+    x: u32,
+    y: u32,
+    depth: f32,
+    varyings: &mut Varying,
+    barycentric: &Barycentric,
+    rhws: [f32; 3],
+  ) {
+    let is_closer = self.depth_buffer.get(x, y) >= depth;
+    if self.material.depth_test() && !is_closer {
+      return;
+    }
+
+    let mut gl_perfragment = GlPerFragment::default();
+    varyings.lerp(barycentric, rhws);
+    if !self
+      .material
+      .fragment(self.uniform, varyings, &mut gl_perfragment)
+    {
+      return;
+    }
+    self.target.write(x, y, gl_perfragment.gl_frag_color);
+
+    if self.material.depth_write() && is_closer {
+      self.depth_buffer.set(x, y, depth);
+    }
+  }
+
+  fn contains(&self, x: i32, y: i32) -> bool {
+    x >= self.left as i32
+      && y >= self.top as i32
+      && (x as u32) < self.right
+      && (y as u32) < self.bottom
+  }
+}
+
+/// Runs the vertex shader on the vertices at `indices`, their `gl_position`
+/// is left in clip space.
+fn shade_vertices<const N: usize>(
   attribute: &Attribute,
-  material: Rc<dyn IMaterial>,
-  uniform: &mut Uniform,
+  indices: [usize; N],
+  material: &dyn IMaterial,
+  uniform: &Uniform,
+  varyings: &mut Varying,
+) -> [GlPerVertex; N] {
+  let mut vs_results: [GlPerVertex; N] = std::array::from_fn(|_| GlPerVertex::default());
+  for (index, vs_result) in indices.into_iter().zip(vs_results.iter_mut()) {
+    let vertex_attribute = pick_attribute_per_vertex(attribute, index);
+    material.vertex(&vertex_attribute, uniform, varyings, vs_result);
+  }
+  vs_results
+}
+
+/// Maps the clipped vertices onto the screen, along with their `1 / w`.
+/// `None` for the degenerate ones, through the eye.
+fn project<const N: usize>(
+  vertices: &[ClipVertex; N],
+  viewport_matrix: Mat4,
+) -> Option<([Vec4; N], [f32; N])> {
+  if vertices.iter().any(|v| v.position.w <= f32::EPSILON) {
+    return None;
+  }
+  let rhws = vertices.map(|v| 1.0 / v.position.w);
+  let positions = vertices.map(|v| viewport_matrix * (v.position / v.position.w));
+  Some((positions, rhws))
+}
+
+/// The weights of the fragment relative to the source vertices, from its
+/// `barycentric` weights in the clipped primitive on the screen, corrected
+/// for the perspective with the `rhws` of the clipped vertices.
+fn source_weights<const N: usize>(
+  barycentric: [f32; N],
+  rhws: [f32; N],
+  vertices: &[ClipVertex; N],
+) -> Barycentric {
+  let perspective: [f32; N] = std::array::from_fn(|i| barycentric[i] * rhws[i]);
+  let sum: f32 = perspective.iter().sum();
+  let weights = vertices
+    .iter()
+    .zip(perspective)
+    .fold(Vec3::zero(), |weights, (v, p)| {
+      weights + v.weights * (p / sum)
+    });
+  Barycentric::from_weights(weights.x, weights.y, weights.z)
+}
+
+/// The triangles are clipped against the view volume before the perspective
+/// divide, the varyings of the fragments are interpolated from the source
+/// vertices.
+fn render_triangle(
+  writer: &mut FragmentWriter,
+  count: usize,
+  attribute: &Attribute,
+  viewport_matrix: Mat4,
 ) {
-  let viewport_matrix = target.update_and_get_viewport();
-  let viewport = target.viewport();
-  uniform.insert("viewport_matrix", viewport_matrix);
-  let data = &position.data;
-  let size = &position.size;
-  let num_of_vertex = data.len() / size;
-  for i in 0..num_of_vertex / 3_usize {
-    let index = i * 3_usize;
-    let vertex_attribute = iter_triangle_verterx(attribute, index);
+  for i in 0..count / 3 {
+    let index = i * 3;
     let mut varyings = Varying::default();
+    let vs_results = shade_vertices(
+      attribute,
+      [index, index + 1, index + 2],
+      writer.material,
+      writer.uniform,
+      &mut varyings,
+    );
+    let positions = vs_results.each_ref().map(|v| v.gl_position);
 
-    let mut vs_results: [GlPerVertex; 3] = Default::default();
-    for j in 0..3 {
-      material.vertex(
-        &vertex_attribute[j],
-        &uniform,
-        &mut varyings,
-        &mut vs_results[j],
-      );
-    }
+    let triangles = match clip_triangle(&positions) {
+      ClipResult::Outside => continue,
+      ClipResult::Inside => {
+        let weights = [
+          Vec3::new(1.0, 0.0, 0.0),
+          Vec3::new(0.0, 1.0, 0.0),
+          Vec3::new(0.0, 0.0, 1.0),
+        ];
+        vec![std::array::from_fn(|i| ClipVertex {
+          position: positions[i],
+          weights: weights[i],
+        })]
+      }
+      ClipResult::Clipped(polygon) => triangulate(&polygon),
+    };
 
-    let mut vertices_2d: [Vec2; 3] = Default::default();
-    let mut rhws: [f32; 3] = Default::default();
-    for j in 0..3 {
-      vs_results[j].gl_position /= vs_results[j].gl_position.w;
+    for triangle in &triangles {
+      let Some((screen, rhws)) = project(triangle, viewport_matrix) else {
+        continue;
+      };
+      let vertices_2d = screen.map(|p| p.truncate_to_vec2());
+      let vertices_z = screen.map(|p| p.z);
 
-      vs_results[j].gl_position = viewport_matrix * vs_results[j].gl_position;
+      let raster = match EdgeRasterizer::new(&vertices_2d, writer.right, writer.bottom) {
+        Some(raster) => raster,
+        None => continue,
+      };
 
-      vertices_2d[j] = vs_results[j].gl_position.truncate_to_vec2();
-      rhws[j] = 1.0 / vs_results[j].gl_position.w;
+      raster.rasterize(raster.y_range(), |x, y, barycentric| {
+        if x < writer.left || y < writer.top {
+          return;
+        }
+        let depth = barycentric.apply_weight(&vertices_z);
+        let weights = [barycentric.alpha(), barycentric.beta(), barycentric.gamma()];
+        let source = source_weights(weights, rhws, triangle);
+        writer.write(x, y, depth, &mut varyings, &source, [1.0; 3]);
+      });
     }
+  }
+}
 
-    let (width, height) = viewport.get_size();
+/// The segments of a line object join its consecutive vertices, the other
+/// objects are drawn as the edges of their triangles.
+fn line_segments(object_type: ObjectType, count: usize) -> Vec<[usize; 2]> {
+  match object_type {
+    ObjectType::Line => (1..count).map(|i| [i - 1, i]).collect(),
+    _ => (0..count / 3 * 3)
+      .map(|i| [i, i / 3 * 3 + (i + 1) % 3])
+      .collect(),
+  }
+}
 
-    let BoundaryBox {
-      x_max,
-      x_min,
-      y_max,
-      y_min,
-    } = BoundaryBox::new(&vertices_2d, width, height);
+/// Walks the segments a pixel at a time along their major axis, and covers
+/// `line_width` pixels across it at every step.
+fn render_line(
+  writer: &mut FragmentWriter,
+  segments: &[[usize; 2]],
+  attribute: &Attribute,
+  viewport_matrix: Mat4,
+  line_width: u8,
+) {
+  let line_width = line_width.max(1) as i32;
+  for segment in segments {
+    let mut varyings = Varying::default();
+    let vs_results = shade_vertices(
+      attribute,
+      *segment,
+      writer.material,
+      writer.uniform,
+      &mut varyings,
+    );
+    let Some(ends) = clip_segment(&vs_results.each_ref().map(|v| v.gl_position)) else {
+      continue;
+    };
+    let Some(([p0, p1], rhws)) = project(&ends, viewport_matrix) else {
+      continue;
+    };
 
-    for x in (x_min as u32)..(x_max as u32 + 1) {
-      for y in (y_min as u32)..(y_max as u32 + 1) {
-        let barycentric = Barycentric::new(&Vec2::new(x as f32, y as f32), &vertices_2d);
+    let delta = p1 - p0;
+    let steep = delta.y.abs() > delta.x.abs();
+    let steps = delta.x.abs().max(delta.y.abs()).ceil().max(1.0) as usize;
+    for step in 0..=steps {
+      let t = step as f32 / steps as f32;
+      let Vec4 { x, y, z: depth, .. } = p0 + delta * t;
+      let barycentric = source_weights([1.0 - t, t], rhws, &ends);
 
-        if !barycentric.is_inside() {
-          continue;
-        }
-        let mut vertices_z: [f32; 3] = Default::default();
-        for j in 0..3 {
-          vertices_z[j] = vs_results[j].gl_position.z;
+      // the pixels across the line, centered on it
+      let (x, y) = (x.floor() as i32, y.floor() as i32);
+      for offset in 0..line_width {
+        let across = offset - (line_width - 1) / 2;
+        let (x, y) = if steep {
+          (x + across, y)
+        } else {
+          (x, y + across)
+        };
+        if writer.contains(x, y) {
+          let rhws = [1.0, 1.0, 0.0];
+          writer.write(x as u32, y as u32, depth, &mut varyings, &barycentric, rhws);
         }
+      }
+    }
+  }
+}
 
-        let depth = barycentric.apply_weight(&vertices_z);
-        let is_closer = depth_buffer.get(x, y) >= depth;
-
-        if !material.depth_test() || is_closer {
-          let mut gl_perfragment = GlPerFragment::default();
-          //TODO lerp the varyings
-          varyings.lerp(&barycentric, rhws);
-          material.fragment(&uniform, &varyings, &mut gl_perfragment);
-          target.write(x, y, gl_perfragment.gl_frag_color);
-        }
+/// Covers a `gl_point_size` wide square or disc around every vertex, the
+/// fragments take the depth and the varyings of the vertex.
+fn render_point(
+  writer: &mut FragmentWriter,
+  count: usize,
+  attribute: &Attribute,
+  viewport_matrix: Mat4,
+  shape: PointShape,
+) {
+  for index in 0..count {
+    let mut varyings = Varying::default();
+    let [vs_result] = shade_vertices(
+      attribute,
+      [index],
+      writer.material,
+      writer.uniform,
+      &mut varyings,
+    );
+    // a point is kept as a whole when its center is in the view volume
+    let vertex = ClipVertex {
+      position: vs_result.gl_position,
+      weights: Vec3::new(1.0, 0.0, 0.0),
+    };
+    if !is_inside(&vertex.position) {
+      continue;
+    }
+    let Some(([position], [rhw])) = project(&[vertex], viewport_matrix) else {
+      continue;
+    };
+
+    let center = position.truncate_to_vec2();
+    let radius = vs_result.gl_point_size.max(1.0) / 2.0;
+    let barycentric = Barycentric::from_weights(1.0, 0.0, 0.0);
 
-        if material.depth_write() && is_closer {
-          depth_buffer.set(x, y, depth);
+    let (left, top) = (
+      (center.x - radius).round() as i32,
+      (center.y - radius).round() as i32,
+    );
+    let (right, bottom) = (
+      (center.x + radius).round() as i32,
+      (center.y + radius).round() as i32,
+    );
+    for y in top..bottom {
+      for x in left..right {
+        let pixel_center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+        let inside = match shape {
+          PointShape::Square => true,
+          PointShape::Disc => (pixel_center - center).length() <= radius,
+        };
+        if inside && writer.contains(x, y) {
+          let rhws = [rhw, 0.0, 0.0];
+          let depth = position.z;
+          writer.write(x as u32, y as u32, depth, &mut varyings, &barycentric, rhws);
         }
       }
     }
   }
 }
 
+/// Draws the geometry of `object` into `target` with the shaders of
+/// `material`. `uniform` holds the uniforms of the material, the object and
+/// the frame.
 pub fn render_pipeline(
   target: &RenderTarget,
   depth_buffer: &mut DepthBuffer,
-  camera: Rc<dyn ICamera>,
   object: Rc<dyn IObject3D>,
   geometry: Rc<dyn IGeometry>,
   material: Rc<dyn IMaterial>,
+  uniform: &mut Uniform,
   vertex_pointer: Option<String>,
 ) {
   let mut mode = RenderMode::Triangle;
@@ -132,36 +338,43 @@ pub fn render_pipeline(
   }
 
   let pointer = &vertex_pointer.unwrap_or("position".to_string());
+  let attribute = geometry.get_attribute();
+  let count = match attribute.get(pointer) {
+    Some(position) => position.items(),
+    None => return,
+  };
+
+  let viewport_matrix = target.update_and_get_viewport();
+  let (width, height) = target.viewport().get_size();
+  let (x, y) = target.viewport().get_offset();
+  uniform.insert("viewport_matrix", viewport_matrix);
+
+  let mut writer = FragmentWriter {
+    target,
+    depth_buffer,
+    material: &*material,
+    uniform,
+    left: x as u32,
+    top: y as u32,
+    right: (x + width) as u32,
+    bottom: (y + height) as u32,
+  };
 
   match mode {
-    RenderMode::Triangle => {
-      let attribute = geometry.get_attribute();
-      let position: Option<&TypeBufferEnum> = attribute.get(pointer);
-      let mut uniform = material.to_uniform();
-      if let Some(p) = position {
-        let material = material.clone();
-        match p {
-          TypeBufferEnum::F64(position) => render_triangle(
-            target,
-            depth_buffer,
-            position,
-            attribute,
-            material,
-            &mut uniform,
-          ),
-          TypeBufferEnum::F32(position) => render_triangle(
-            target,
-            depth_buffer,
-            position,
-            attribute,
-            material,
-            &mut uniform,
-          ),
-          _ => {}
-        }
-      }
-    }
-    RenderMode::Point => todo!(),
-    RenderMode::Line => todo!(),
+    RenderMode::Triangle => render_triangle(&mut writer, count, attribute, viewport_matrix),
+    RenderMode::Point => render_point(
+      &mut writer,
+      count,
+      attribute,
+      viewport_matrix,
+      material.point_shape(),
+    ),
+    RenderMode::Line => render_line(
+      &mut writer,
+      &line_segments(object.get_type(), count),
+      attribute,
+      viewport_matrix,
+      material.wireframe_linewidth(),
+    ),
   }
 }
@@ -40,15 +40,19 @@ impl RenderState {
     *shadows = vec![];
   }
 
-  pub fn setup_lights(&self) {
+  pub fn setup_lights(&self, shadows: bool) {
     let lights = &self.lights.borrow();
-    self.gl_lights.borrow_mut().setup(lights);
+    self.gl_lights.borrow_mut().setup(lights, shadows);
   }
 
   pub fn setup_lights_view(&self, camera: Rc<dyn ICamera>) {
     let lights = &self.lights.borrow();
     self.gl_lights.borrow_mut().setup_view(lights, camera);
   }
+
+  pub fn upload_lights(&self, uniform: &mut Uniform) {
+    self.gl_lights.borrow().upload(uniform);
+  }
 }
 pub struct RenderItem {
   id: String,
@@ -70,6 +74,12 @@ pub struct RenderList {
 }
 
 impl RenderList {
+  pub fn init(&self) {
+    *self.opaque.borrow_mut() = vec![];
+    *self.transparent.borrow_mut() = vec![];
+    *self.transmissive.borrow_mut() = vec![];
+  }
+
   pub fn push(
     &self,
     object: Rc<dyn IObject3D>,
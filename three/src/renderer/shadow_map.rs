@@ -1,43 +1,100 @@
+use std::cell::RefCell;
 use std::rc::Rc;
 
 use crate::core::render_target::RenderTarget;
-use crate::material::depth_material::MeshDepthMaterial;
+use crate::material::depth_material::{
+  pack_depth, pack_moments, unpack_depth, DepthPacking, MeshDepthAttribute, MeshDepthMaterial,
+};
+use crate::material::distance_material::MeshDistanceMaterial;
 use crate::material::material::IMaterial;
 use crate::utils::rc_convert;
 use crate::{
   cameras::camera::ICamera,
   core::object_3d::{IObject3D, ObjectType},
-  lights::light::ILight,
+  lights::light::{ILight, LightType},
   objects::{base::Renderable, line::Line, mesh::Mesh, point::Point, scene::Scene},
 };
 use math::data_array::DepthBuffer;
-use math::Vec2;
-use math::Vec4;
+use math::{Vec2, Vec4};
 
 use super::render_pipeline::render_pipeline;
 
-#[derive(Debug)]
-enum ShadowMapType {
+/// How the receiving materials filter the shadow maps. `radius` is the
+/// width of the filters in texels.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ShadowMapType {
+  /// a single comparison, hard and aliased
   BasicShadowMap,
+  /// the average of 3 x 3 comparisons
+  #[default]
   PCFShadowMap,
+  /// the average of 3 x 3 bilinearly filtered comparisons
   PCFSoftShadowMap,
+  /// the Chebyshev bound from the moments of the depths, the map holding the
+  /// depths and the squared depths blurred over `radius` texels
   VSMShadowMap,
 }
 
-impl Default for ShadowMapType {
-  fn default() -> Self {
-    Self::PCFShadowMap
+impl From<i32> for ShadowMapType {
+  fn from(value: i32) -> Self {
+    match value {
+      0 => ShadowMapType::BasicShadowMap,
+      2 => ShadowMapType::PCFSoftShadowMap,
+      3 => ShadowMapType::VSMShadowMap,
+      _ => ShadowMapType::PCFShadowMap,
+    }
   }
 }
 
-#[derive(Default)]
+/// the packing of the depths in the shadow maps
+pub const SHADOW_DEPTH_PACKING: DepthPacking = DepthPacking::RGBADepthPacking;
+
 pub struct ShadowMap {
   enable: bool,
   shadow_type: ShadowMapType,
-  material: MeshDepthMaterial,
+  material: Rc<MeshDepthMaterial>,
+  distance_material: Rc<MeshDistanceMaterial>,
+}
+
+impl Default for ShadowMap {
+  fn default() -> Self {
+    let material = MeshDepthMaterial {
+      attributes: RefCell::new(Rc::new(MeshDepthAttribute::new(SHADOW_DEPTH_PACKING))),
+      ..Default::default()
+    };
+    let distance_material = MeshDistanceMaterial {
+      attributes: RefCell::new(Rc::new(MeshDepthAttribute::new(SHADOW_DEPTH_PACKING))),
+      ..Default::default()
+    };
+
+    Self {
+      enable: false,
+      shadow_type: Default::default(),
+      material: Rc::new(material),
+      distance_material: Rc::new(distance_material),
+    }
+  }
 }
 
 impl ShadowMap {
+  pub fn enable(&self) -> bool {
+    self.enable
+  }
+
+  pub fn set_enable(&mut self, enable: bool) {
+    self.enable = enable;
+  }
+
+  pub fn shadow_type(&self) -> ShadowMapType {
+    self.shadow_type
+  }
+
+  pub fn set_shadow_type(&mut self, shadow_type: ShadowMapType) {
+    self.shadow_type = shadow_type;
+  }
+
+  /// Renders the depth of the shadow casters into the map of every light,
+  /// once per viewport of its shadow.
   pub fn render(&self, lights: &Vec<Rc<dyn ILight>>, scene: Rc<Scene>, camera: Rc<dyn ICamera>) {
     if !self.enable {
       return;
@@ -45,95 +102,156 @@ impl ShadowMap {
 
     for light in lights {
       if let Some(shadow) = light.shadow() {
+        let mut map_size = shadow.map_size();
+        map_size *= shadow.frame_extents();
         let Vec2 {
           x: map_width,
           y: map_height,
-        } = shadow.map_size();
+        } = map_size;
 
         let map = shadow.map();
 
         map.update_texture_name(light.name() + ".shadow_map");
-        shadow.camera().projection_matrix();
-
-        let vps = shadow.viewports();
-        for vp in vps {
-          let mut viewport = Vec4::zero();
-          //offset_x
-          viewport.x = vp.x * map_width;
-          //offset_y
-          viewport.y = vp.y * map_height;
-          //texture_width
-          viewport.z = vp.z * map_width;
-          //texture_height
-          viewport.w = vp.w * map_height;
-          shadow.update_matrices(light.clone(), viewport);
+        map.set_size(map_width, map_height);
+        map.clear(pack_depth(1.0, SHADOW_DEPTH_PACKING));
+
+        let mut depth_buffer = DepthBuffer::new(map_width as u32, map_height as u32);
+        depth_buffer.clear(f32::MAX);
+
+        for i in 0..shadow.viewports().len() {
+          shadow.update_matrices(light.clone(), i);
+          map.set_viewport(shadow.viewport(i));
           self.render_object(
-            shadow.map().clone(),
+            &map,
+            &mut depth_buffer,
             scene.clone(),
             camera.clone(),
             shadow.camera().clone(),
             light.clone(),
           );
         }
+
+        if self.shadow_type == ShadowMapType::VSMShadowMap {
+          for i in 0..shadow.viewports().len() {
+            blur_moments(&map, shadow.viewport(i), shadow.radius());
+          }
+        }
       }
     }
   }
 
   fn render_object(
     &self,
-    target: Rc<RenderTarget>,
+    target: &RenderTarget,
+    depth_buffer: &mut DepthBuffer,
     object: Rc<dyn IObject3D>,
     camera: Rc<dyn ICamera>,
     shadow_camera: Rc<dyn ICamera>,
     light: Rc<dyn ILight>,
   ) {
-    if !object.layers().test(&camera.layers()) {
+    if !object.visible() || !object.layers().test(&camera.layers()) {
       return;
     }
 
     match object.get_type() {
-      ObjectType::Mesh | ObjectType::Line | ObjectType::Point => {
+      ObjectType::Mesh | ObjectType::Line | ObjectType::Point if object.cast_shadow() => {
         let obj = object.clone();
         let renderable: Rc<dyn Renderable> =
           rc_convert!(obj;Mesh,Line,Point;"Unexpected Renderable Type");
-        if !renderable.material().visible() {
-          return;
-        }
-
         let material = renderable.material();
-        let depth_material = to_depth_material(object.clone(), material, light.clone());
-        let geometry = renderable.geometry();
-        let mut depth_buffer = DepthBuffer::default();
-        render_pipeline(
-          &target,
-          &mut depth_buffer,
-          camera,
-          object,
-          geometry,
-          depth_material,
-          None,
-        );
-      }
-      _ => {
-        let children = object.children();
-
-        for child in children.iter() {
-          self.render_object(
-            target.clone(),
-            child.clone(),
-            camera.clone(),
-            shadow_camera.clone(),
-            light.clone(),
+        if material.visible() {
+          let depth_material = self.to_depth_material(object.clone(), material, light.clone());
+          let geometry = renderable.geometry();
+          let mut uniform = depth_material.to_uniform();
+          uniform.insert("model_matrix", object.global_matrix());
+          uniform.insert("view_matrix", shadow_camera.view_matrix());
+          uniform.insert("projection_matrix", shadow_camera.projection_matrix());
+          // the light and the range of its shadow, for the distance material
+          if let Some(shadow) = light.shadow() {
+            uniform.merge(&shadow.to_uniform());
+          }
+          uniform.insert("reference_position", light.global_position());
+          render_pipeline(
+            target,
+            depth_buffer,
+            object.clone(),
+            geometry,
+            depth_material,
+            &mut uniform,
+            None,
           );
         }
       }
+      _ => {}
+    }
+
+    let children = object.children();
+
+    for child in children.iter() {
+      self.render_object(
+        target,
+        depth_buffer,
+        child.clone(),
+        camera.clone(),
+        shadow_camera.clone(),
+        light.clone(),
+      );
+    }
+  }
+
+  /// the material drawing `object` into the map of `light`, the point lights
+  /// store the distance to the light
+  fn to_depth_material(
+    &self,
+    _object: Rc<dyn IObject3D>,
+    _material: Rc<dyn IMaterial>,
+    light: Rc<dyn ILight>,
+  ) -> Rc<dyn IMaterial> {
+    match light.light_type() {
+      LightType::PointLight => self.distance_material.clone(),
+      _ => self.material.clone(),
     }
   }
 }
 
-fn to_depth_material(
-  object: Rc<dyn IObject3D>,
-  material: Rc<dyn IMaterial>,
-  light: Rc<dyn ILight>,
-) -> Rc<dyn IMaterial> {
+/// Turns the depths of the `viewport` of the map into their moments, the
+/// depth and the squared depth, averaged over a box of `radius` texels around
+/// every texel. The box is cut at the edges of the viewport.
+fn blur_moments(map: &RenderTarget, viewport: Vec4, radius: f32) {
+  let (left, top) = (viewport.x as usize, viewport.y as usize);
+  let (width, height) = (viewport.z as usize, viewport.w as usize);
+  let r = radius.round().max(1.0) as usize;
+
+  let mut moments: Vec<Vec2> = {
+    let texture = map.texture();
+    (0..width * height)
+      .map(|i| {
+        let (x, y) = (left + i % width, top + i / width);
+        let depth = unpack_depth(texture.texel(x as i32, y as i32), SHADOW_DEPTH_PACKING);
+        Vec2::new(depth, depth * depth)
+      })
+      .collect()
+  };
+
+  // a box filter is separable, the rows then the columns
+  let mut blurred = vec![Vec2::new(0.0, 0.0); moments.len()];
+  for (step, length, lines) in [(1, width, height), (width, height, width)] {
+    for line in 0..lines {
+      let first = if step == 1 { line * width } else { line };
+      for i in 0..length {
+        let window = i.saturating_sub(r)..(i + r + 1).min(length);
+        let count = window.len() as f32;
+        let sum = window.fold(Vec2::new(0.0, 0.0), |sum, j| {
+          sum + moments[first + j * step]
+        });
+        blurred[first + i * step] = sum / count;
+      }
+    }
+    std::mem::swap(&mut moments, &mut blurred);
+  }
+
+  for (i, moment) in moments.into_iter().enumerate() {
+    let (x, y) = (left + i % width, top + i / width);
+    map.write(x as u32, y as u32, pack_moments(moment));
+  }
 }
@@ -57,6 +57,16 @@ impl Texture {
     instance
   }
 
+  /// a texture keeping `color_type` through `set_size`, e.g. `Rgba8` for the
+  /// depth packed into 4 channels
+  pub fn with_color_type(w: u32, h: u32, color_type: image::ColorType) -> Self {
+    Self {
+      image: DynamicImage::new(w, h, color_type),
+      bit_depth: color_type,
+      ..Default::default()
+    }
+  }
+
   pub fn load(path: &str, id: u32) -> Result<Self, ImageError> {
     let image = open(path)?;
     let mut instance = Self::default();
@@ -80,11 +90,40 @@ impl Texture {
     Vec4::new(x as f32, y as f32, z as f32, w as f32) / 255.0
   }
 
+  /// `color` is in [0, 1], as `get_pixel` returns it
   pub fn write(&mut self, x: u32, y: u32, color: Vec4) {
-    let pixel = Rgba([color.x as u8, color.y as u8, color.z as u8, color.w as u8]);
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let pixel = Rgba([
+      channel(color.x),
+      channel(color.y),
+      channel(color.z),
+      channel(color.w),
+    ]);
     self.image.put_pixel(x, y, pixel);
   }
 
+  /// the texel at (x, y) from the top left corner, clamped to the edges
+  pub fn texel(&self, x: i32, y: i32) -> Vec4 {
+    let image = &self.image;
+    let x = x.clamp(0, image.width() as i32 - 1) as u32;
+    let y = y.clamp(0, image.height() as i32 - 1) as u32;
+
+    let [x, y, z, w] = image.get_pixel(x, y).0;
+    Vec4::new(x as f32, y as f32, z as f32, w as f32) / 255.0
+  }
+
+  pub fn clear(&mut self, color: Vec4) {
+    for y in 0..self.image.height() {
+      for x in 0..self.image.width() {
+        self.write(x, y, color);
+      }
+    }
+  }
+
+  pub fn size(&self) -> (u32, u32) {
+    (self.image.width(), self.image.height())
+  }
+
   pub fn set_size(&mut self, w: u32, h: u32) {
     self.image = DynamicImage::new(w, h, self.bit_depth);
   }
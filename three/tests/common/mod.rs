@@ -0,0 +1,100 @@
+//! The meshes and the helpers shared by the rendering tests, each test uses a
+//! part of them only.
+#![allow(dead_code)]
+
+use std::rc::Rc;
+
+use three::{
+  cameras::{camera::ICamera, perspective_camera::PerspectiveCamera},
+  core::{
+    buffer_attribute::F32BufferAttribute,
+    buffer_geometry::{BufferGeometry, IGeometry},
+  },
+  math::{NormalGeneration, Vec3, Vec4},
+  objects::mesh::Mesh,
+};
+
+/// the width and the height of the frames
+pub const SIZE: u32 = 200;
+
+/// a mesh of the triangles at `positions`, with their vertex normals
+pub fn mesh(positions: Vec<f32>) -> Rc<Mesh> {
+  let mut geometry = BufferGeometry::default();
+  geometry.set_attribute(
+    "position",
+    F32BufferAttribute::new(positions, 3, false).as_enum(),
+  );
+  geometry.compute_vertex_normals(&NormalGeneration::default());
+
+  Mesh::from_geometry(Rc::new(geometry), Rc::new(Default::default()))
+}
+
+/// two triangles, counter clockwise from the side they face
+pub fn quad(corners: [[f32; 3]; 4]) -> Rc<Mesh> {
+  mesh(
+    [0, 1, 2, 0, 2, 3]
+      .iter()
+      .flat_map(|&i| corners[i])
+      .collect(),
+  )
+}
+
+/// the ground from -4 to 4, facing up
+pub fn ground() -> Rc<Mesh> {
+  quad([
+    [-4.0, 0.0, 4.0],
+    [4.0, 0.0, 4.0],
+    [4.0, 0.0, -4.0],
+    [-4.0, 0.0, -4.0],
+  ])
+}
+
+/// the 12 triangles of the cube from `-half_size` to `half_size`, counter
+/// clockwise from outside
+pub fn cube(half_size: f32) -> Rc<Mesh> {
+  let axes = [
+    (*Vec3::x_axis(), *Vec3::y_axis()),
+    (*Vec3::y_axis(), *Vec3::z_axis()),
+    (*Vec3::z_axis(), *Vec3::x_axis()),
+  ];
+
+  let mut positions = vec![];
+  for (axis, tangent) in axes {
+    for normal in [axis, axis * -1.0] {
+      let bitangent = normal.cross(&tangent);
+      let corner = |u: f32, v: f32| (normal + tangent * u + bitangent * v) * half_size;
+      let quad = [
+        corner(-1.0, -1.0),
+        corner(1.0, -1.0),
+        corner(1.0, 1.0),
+        corner(-1.0, 1.0),
+      ];
+      for i in [0, 1, 2, 0, 2, 3] {
+        positions.extend([quad[i].x, quad[i].y, quad[i].z]);
+      }
+    }
+  }
+  mesh(positions)
+}
+
+/// the pixel where `point` lands, from the top left corner
+pub fn pixel_at(camera: &PerspectiveCamera, point: Vec3) -> (u32, u32) {
+  let clip = camera.projection_matrix() * camera.view_matrix() * Vec4::from_vec3(&point, 1.0);
+  let (x, y) = (clip.x / clip.w, clip.y / clip.w);
+  let x = ((x + 1.0) / 2.0 * SIZE as f32) as u32;
+  let y = ((1.0 - y) / 2.0 * SIZE as f32) as u32;
+  (x, y)
+}
+
+/// the red channel of the pixel where `point` lands
+pub fn red_at(data: &[u8], camera: &PerspectiveCamera, point: Vec3) -> u8 {
+  let (x, y) = pixel_at(camera, point);
+  data[((x + y * SIZE) * 3) as usize]
+}
+
+/// writes the rgb frame to `<name>.png` in the temporary directory of the
+/// tests, to look at it when they fail
+pub fn save(name: &str, data: &[u8]) {
+  let path = format!("{}/{name}.png", env!("CARGO_TARGET_TMPDIR"));
+  image::save_buffer(&path, data, SIZE, SIZE, image::ColorType::Rgb8).unwrap();
+}
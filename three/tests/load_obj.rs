@@ -0,0 +1,189 @@
+use three::{
+  core::{
+    buffer_attribute::{IBufferAttribute, TypeBufferEnum},
+    buffer_geometry::{BufferGeometry, IGeometry},
+  },
+  loaders::obj_loader::{obj_loader, ObjData, VertexIndex},
+  math::{NormalGeneration, Vec3, Vec4},
+  utils::SingleOrList,
+};
+
+/// Writes `obj` to a file of its own and hands the parsed data to `f`.
+fn load<R>(name: &str, obj: &str, f: impl FnOnce(&ObjData) -> R) -> R {
+  let path = std::env::temp_dir().join(format!("three-{}.obj", name));
+  std::fs::write(&path, obj).unwrap();
+
+  let mut loader = obj_loader.lock().unwrap();
+  match loader.load(path.to_str().unwrap()) {
+    Ok(SingleOrList::Data(data)) => f(data),
+    _ => panic!("{} is not loaded", name),
+  }
+}
+
+/// the position indices of the vertices
+fn positions(vertices: &[VertexIndex]) -> Vec<u32> {
+  vertices.iter().map(|v| v.position_index).collect()
+}
+
+fn normals(geometry: &BufferGeometry) -> Vec<Vec3> {
+  match geometry.get_attribute().get("normal") {
+    Some(TypeBufferEnum::F32(normal)) => (0..normal.items()).map(|i| normal.get_vec3(i)).collect(),
+    _ => vec![],
+  }
+}
+
+#[track_caller]
+fn assert_normal(actual: Vec3, expected: Vec3) {
+  assert!(
+    (actual - expected.normalize()).length() < 1e-5,
+    "{:?} != {:?}",
+    actual,
+    expected
+  );
+}
+
+#[test]
+fn lines_and_points() {
+  let obj = "o shape\nv 0 0 0\nv 1 0 0\nv 1 1 0\nl 1 2 3\np -1\nl -3 -1\n";
+  load("lines-and-points", obj, |data| {
+    let model = &data.models[0];
+
+    assert!(model.faces.is_empty());
+    assert_eq!(model.lines.len(), 2);
+    assert_eq!(positions(&model.lines[0]), [0, 1, 2]);
+    assert_eq!(positions(&model.lines[1]), [0, 2]);
+    assert_eq!(positions(&model.points), [2]);
+  });
+}
+
+/// two triangles folded by 45 degrees along the y axis, `groups` are the `s`
+/// statements before each of them
+fn fold(groups: [&str; 2]) -> String {
+  format!(
+    "o fold\nv -1 0 0\nv 0 0 0\nv 0 1 0\nv 1 0 -1\n{}\nf 1 2 3\n{}\nf 2 4 3\n",
+    groups[0], groups[1]
+  )
+}
+
+const FLAT: Vec3 = Vec3 {
+  x: 0.0,
+  y: 0.0,
+  z: 1.0,
+};
+const TILTED: Vec3 = Vec3 {
+  x: 1.0,
+  y: 0.0,
+  z: 1.0,
+};
+
+/// the normals of the fold, generated with the default crease angle
+fn fold_normals(name: &str, groups: [&str; 2]) -> Vec<Vec3> {
+  load(name, &fold(groups), |data| {
+    normals(&data.to_geometry(&data.models[0], &NormalGeneration::default()))
+  })
+}
+
+#[test]
+fn same_smoothing_group_is_smoothed() {
+  let normals = fold_normals("same-group", ["s 1", "s 1"]);
+
+  assert_eq!(normals.len(), 6);
+  assert_normal(normals[0], FLAT);
+  assert_normal(normals[4], TILTED);
+  // the corners on the fold share a normal, between the ones of the faces
+  for (a, b) in [(1, 3), (2, 5)] {
+    assert_normal(normals[a], normals[b]);
+    assert!(normals[a].dot(&FLAT) < 0.99 && normals[a].dot(&TILTED.normalize()) < 0.99);
+  }
+
+  // without any group, the whole model is smoothed
+  assert_eq!(fold_normals("no-group", ["", ""]), normals);
+}
+
+#[test]
+fn smoothing_groups_keep_the_faces_apart() {
+  for (name, groups) in [("other-group", ["s 1", "s 2"]), ("off", ["s 1", "s off"])] {
+    let normals = fold_normals(name, groups);
+    for i in 0..3 {
+      assert_normal(normals[i], FLAT);
+      assert_normal(normals[i + 3], TILTED);
+    }
+  }
+}
+
+#[test]
+fn vn_data_is_kept() {
+  let obj = "o fold\nv -1 0 0\nv 0 0 0\nv 0 1 0\nv 1 0 -1\nvn 0 1 0\n\
+             f 1//1 2//1 3//1\nf 2 4 3\n";
+  let normals = load("vn", obj, |data| {
+    normals(&data.to_geometry(&data.models[0], &NormalGeneration::default()))
+  });
+
+  for normal in &normals[..3] {
+    assert_normal(*normal, Vec3::new(0.0, 1.0, 0.0));
+  }
+  // the other face is generated as if there were no vn data
+  let generated = fold_normals("vn-generated", ["", ""]);
+  for (normal, expected) in normals[3..].iter().zip(&generated[3..]) {
+    assert_normal(*normal, *expected);
+  }
+}
+
+fn tangents(geometry: &BufferGeometry) -> Vec<Vec4> {
+  match geometry.get_attribute().get("tangent") {
+    Some(TypeBufferEnum::F32(tangent)) => (0..tangent.items())
+      .map(|i| {
+        Vec4::new(
+          tangent.get_x(i),
+          tangent.get_y(i),
+          tangent.get_z(i),
+          tangent.get_w(i),
+        )
+      })
+      .collect(),
+    _ => vec![],
+  }
+}
+
+#[test]
+fn tangents_follow_the_uvs() {
+  // the same square facing +z, with its uvs as is, mirrored along u, and
+  // rotated by 90 degrees
+  let obj = "v -1 -1 0\nv 1 -1 0\nv 1 1 0\nv -1 1 0\n\
+             vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvn 0 0 1\n\
+             o plain\nf 1/1/1 2/2/1 3/3/1 4/4/1\n\
+             o mirrored\nf 1/2/1 2/1/1 3/4/1 4/3/1\n\
+             o rotated\nf 1/4/1 2/1/1 3/2/1 4/3/1\n";
+  // the directions of increasing u and v on the square, and the sign of the
+  // bitangent
+  let frames = [
+    (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 1.0),
+    (Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), -1.0),
+    (Vec3::new(0.0, 1.0, 0.0), Vec3::new(-1.0, 0.0, 0.0), 1.0),
+  ];
+
+  load("tangents", obj, |data| {
+    assert_eq!(data.models.len(), frames.len());
+    for (model, (u, v, sign)) in data.models.iter().zip(frames) {
+      let geometry = data.to_geometry(model, &NormalGeneration::default());
+      let normals = normals(&geometry);
+      let tangents = tangents(&geometry);
+      assert_eq!(tangents.len(), 6, "{}", model.name);
+
+      for (n, t) in normals.iter().zip(tangents) {
+        let tangent = t.truncated_to_vec3();
+        assert_normal(tangent, u);
+        assert_eq!(t.w, sign, "{}", model.name);
+        assert_normal(n.cross(&tangent) * t.w, v);
+      }
+    }
+  });
+}
+
+#[test]
+fn no_tangents_without_uvs() {
+  load("no-uvs", &fold(["", ""]), |data| {
+    let geometry = data.to_geometry(&data.models[0], &NormalGeneration::default());
+    assert!(tangents(&geometry).is_empty());
+  });
+}
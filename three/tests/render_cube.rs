@@ -0,0 +1,56 @@
+mod common;
+
+use common::{cube, save, SIZE};
+use three::{
+  cameras::perspective_camera::PerspectiveCamera, core::object_3d::IObject3D,
+  lights::directional_light::DirectionalLight, math::Vec3, objects::scene::Scene,
+  renderer::gl_renderer::GlRenderer,
+};
+
+fn grey(data: &[u8], x: u32, y: u32) -> u8 {
+  let p = ((x + y * SIZE) * 3) as usize;
+  assert_eq!(data[p], data[p + 1]);
+  assert_eq!(data[p], data[p + 2]);
+  data[p]
+}
+
+#[test]
+fn render_cube() {
+  let scene = Scene::new();
+  scene.add(cube(1.0));
+
+  let light = DirectionalLight::new();
+  let light_position = Vec3::new(1.0, 3.0, 2.0);
+  light.update_position(light_position);
+  scene.add(light);
+
+  let camera = PerspectiveCamera::new(45.0, 1.0, 0.1, 100.0);
+  camera.update_position(Vec3::new(3.5, 3.0, 4.5));
+  camera.look_at(Vec3::zero());
+
+  let mut renderer = GlRenderer::new();
+  renderer.set_size(SIZE as f32, SIZE as f32);
+  let buffer = renderer.render(scene, camera);
+  let data = buffer.data();
+
+  save("render_cube", data);
+
+  // the cube is in the middle of the frame, over a black background
+  assert!(grey(data, SIZE / 2, SIZE / 2) > 0);
+  for (x, y) in [(0, 0), (SIZE - 1, 0), (0, SIZE - 1), (SIZE - 1, SIZE - 1)] {
+    assert_eq!(grey(data, x, y), 0);
+  }
+
+  // the +x, +y and +z faces face the camera, each of them is lit by n · l
+  let l = light_position.normalize();
+  for n_dot_l in [l.x, l.y, l.z] {
+    let expected = (n_dot_l * 255.0).round() as i32;
+    let covered = (0..SIZE * SIZE)
+      .filter(|i| (grey(data, i % SIZE, i / SIZE) as i32 - expected).abs() <= 1)
+      .count();
+    assert!(
+      covered > (SIZE * SIZE / 50) as usize,
+      "{covered} pixels lit at {expected}"
+    );
+  }
+}
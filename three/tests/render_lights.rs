@@ -0,0 +1,84 @@
+mod common;
+
+use std::{f32::consts::PI, rc::Rc};
+
+use common::{ground, red_at, save, SIZE};
+use three::{
+  cameras::perspective_camera::PerspectiveCamera,
+  core::object_3d::IObject3D,
+  lights::{hemisphere_light::HemisphereLight, point_light::PointLight, spot_light::SpotLight},
+  math::{Vec3, Vec4},
+  objects::scene::Scene,
+  renderer::gl_renderer::GlRenderer,
+};
+
+/// Renders the ground lit by `light` alone, and returns the red channel of the
+/// pixels where the ground `points` land.
+fn render(name: &str, light: Rc<dyn IObject3D>, points: &[Vec3]) -> Vec<u8> {
+  let scene = Scene::new();
+  scene.add(ground());
+  scene.add(light);
+
+  let camera = PerspectiveCamera::new(45.0, 1.0, 0.1, 100.0);
+  camera.update_position(Vec3::new(0.0, 8.0, 4.0));
+  camera.look_at(Vec3::zero());
+
+  let mut renderer = GlRenderer::new();
+  renderer.set_size(SIZE as f32, SIZE as f32);
+  let buffer = renderer.render(scene, camera.clone());
+  let data = buffer.data();
+
+  save(&format!("render_lights_{name}"), data);
+  points
+    .iter()
+    .map(|point| red_at(data, &camera, *point))
+    .collect()
+}
+
+/// the pixel centers are up to half a pixel, some 0.03 units, away from the
+/// points
+fn assert_near(values: &[u8], expected: &[f32]) {
+  for (value, expected) in values.iter().zip(expected) {
+    let expected = (expected * 255.0).round() as i32;
+    assert!(
+      (*value as i32 - expected).abs() <= 5,
+      "{values:?} lit instead of {expected}"
+    );
+  }
+}
+
+#[test]
+fn render_lights() {
+  let white = Vec4::new(1.0, 1.0, 1.0, 1.0);
+  let points = [
+    Vec3::zero(),
+    Vec3::new(1.0, 0.0, 0.0),
+    Vec3::new(3.0, 0.0, 0.0),
+  ];
+
+  // n · l over the square of the distance, from a unit above the origin
+  let point = PointLight::new(white, 1.0, 0.0, 2.0);
+  point.update_position(Vec3::new(0.0, 1.0, 0.0));
+  let lit = render("point", point, &points);
+  assert_near(&lit, &[1.0, 0.5f32.powf(1.5), 0.1f32.powf(1.5)]);
+
+  // a range of 2 units cuts the light before the last point
+  let point = PointLight::new(white, 1.0, 2.0, 2.0);
+  point.update_position(Vec3::new(0.0, 1.0, 0.0));
+  let lit = render("point_distance", point, &points);
+  assert_near(&lit[..1], &[(1.0 - 0.5f32.powi(4)).powi(2)]);
+  assert_eq!(lit[2], 0);
+
+  // a 30 degrees cone from 4 units above the origin, without decay
+  let spot = SpotLight::new(white, 1.0, 0.0, PI / 6.0, 0.0, 0.0);
+  spot.update_position(Vec3::new(0.0, 4.0, 0.0));
+  let lit = render("spot", spot, &points);
+  assert_near(&lit[..2], &[1.0, 4.0 / 17f32.sqrt()]);
+  assert_eq!(lit[2], 0);
+
+  // the ground faces the sky
+  let grey = |v: f32| Vec4::new(v, v, v, 1.0);
+  let hemisphere = HemisphereLight::new(grey(0.6), grey(0.2), 1.0);
+  let lit = render("hemisphere", hemisphere, &points);
+  assert_near(&lit, &[0.6, 0.6, 0.6]);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::{cube, ground, quad, red_at, save, SIZE};
+use three::{
+  cameras::perspective_camera::PerspectiveCamera,
+  core::object_3d::IObject3D,
+  lights::{light::ILight, point_light::PointLight},
+  math::{Vec2, Vec3, Vec4},
+  objects::scene::Scene,
+  renderer::{gl_renderer::GlRenderer, shadow_map::ShadowMapType},
+};
+
+/// the light reaching `point` on a surface facing `normal`, without decay
+fn n_dot_l(light: Vec3, point: Vec3, normal: Vec3) -> i32 {
+  let lit = (light - point).normalize() * normal;
+  (lit * 255.0).round() as i32
+}
+
+#[test]
+fn render_point_shadow() {
+  let scene = Scene::new();
+
+  // a ground and a wall on the right, under and beside the bulb
+  let ground = ground();
+  ground.set_receive_shadow(true);
+  scene.add(ground);
+  let wall = quad([
+    [4.0, 0.0, -4.0],
+    [4.0, 0.0, 4.0],
+    [4.0, 6.0, 4.0],
+    [4.0, 6.0, -4.0],
+  ]);
+  wall.set_receive_shadow(true);
+  scene.add(wall);
+
+  // a cube below the bulb, one beside it
+  for position in [Vec3::new(0.0, 1.5, 0.0), Vec3::new(2.0, 3.0, 0.0)] {
+    let cube = cube(0.5);
+    cube.update_position(position);
+    cube.set_cast_shadow(true);
+    scene.add(cube);
+  }
+
+  let white = Vec4::new(1.0, 1.0, 1.0, 1.0);
+  let light = PointLight::new(white, 1.0, 0.0, 0.0);
+  let light_position = Vec3::new(0.0, 3.0, 0.0);
+  light.update_position(light_position);
+  light.set_cast_shadow(true);
+  let shadow = light.shadow().unwrap();
+  shadow.set_map_size(Vec2::new(128.0, 128.0));
+  shadow.set_bias(-0.002);
+  scene.add(light);
+
+  let camera = PerspectiveCamera::new(60.0, 1.0, 0.1, 100.0);
+  camera.update_position(Vec3::new(-1.0, 9.0, 9.0));
+  camera.look_at(Vec3::new(1.0, 1.5, 0.0));
+
+  let mut renderer = GlRenderer::new();
+  renderer.set_size(SIZE as f32, SIZE as f32);
+  renderer.shadow_map_mut().set_enable(true);
+  renderer
+    .shadow_map_mut()
+    .set_shadow_type(ShadowMapType::PCFShadowMap);
+  let buffer = renderer.render(scene, camera.clone());
+  let data = buffer.data();
+
+  save("render_point_shadow", data);
+
+  // the cube below the bulb shades the ground, on the -y face of the cube map
+  let up = *Vec3::y_axis();
+  assert_eq!(red_at(data, &camera, Vec3::new(1.0, 0.0, 0.0)), 0);
+  for point in [Vec3::new(2.5, 0.0, 0.0), Vec3::new(-2.0, 0.0, 2.5)] {
+    let expected = n_dot_l(light_position, point, up);
+    assert!((red_at(data, &camera, point) as i32 - expected).abs() <= 3);
+  }
+
+  // the cube beside it shades the wall, on the +x face
+  let left = Vec3::new(-1.0, 0.0, 0.0);
+  assert_eq!(red_at(data, &camera, Vec3::new(4.0, 3.0, 0.0)), 0);
+  let point = Vec3::new(4.0, 3.0, 2.5);
+  let expected = n_dot_l(light_position, point, left);
+  assert!((red_at(data, &camera, point) as i32 - expected).abs() <= 3);
+}
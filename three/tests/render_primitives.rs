@@ -0,0 +1,137 @@
+mod common;
+
+use std::rc::Rc;
+
+use common::{save, SIZE};
+use three::{
+  cameras::perspective_camera::PerspectiveCamera,
+  core::{
+    buffer_attribute::F32BufferAttribute,
+    buffer_geometry::{BufferGeometry, IGeometry},
+    object_3d::IObject3D,
+  },
+  material::{material::PointShape, standard_material::StandardMeshMaterial},
+  math::Vec3,
+  objects::{line::Line, mesh::Mesh, point::Point, scene::Scene},
+  renderer::gl_renderer::GlRenderer,
+};
+
+fn geometry(positions: Vec<f32>) -> Rc<BufferGeometry> {
+  let mut geometry = BufferGeometry::default();
+  geometry.set_attribute(
+    "position",
+    F32BufferAttribute::new(positions, 3, false).as_enum(),
+  );
+  Rc::new(geometry)
+}
+
+fn is_lit(data: &[u8], x: u32, y: u32) -> bool {
+  data[((x + y * SIZE) * 3) as usize] > 0
+}
+
+#[test]
+fn render_primitives() {
+  let scene = Scene::new();
+
+  // a line across the middle row, three pixels thick
+  let material = StandardMeshMaterial {
+    wireframe_linewidth: 3,
+    ..Default::default()
+  };
+  let line = Line::from_geometry(
+    geometry(vec![-1.0, 0.0, 0.0, 1.0, 0.0, 0.0]),
+    Rc::new(material),
+  );
+  scene.add(line);
+
+  // a disc 9 pixels wide above it
+  let material = StandardMeshMaterial {
+    point_size: 9.0,
+    point_shape: PointShape::Disc,
+    ..Default::default()
+  };
+  let point = Point::from_geometry(geometry(vec![0.0, 1.0, 0.0]), Rc::new(material));
+  scene.add(point);
+
+  // the edges of a triangle below it
+  let material = StandardMeshMaterial {
+    wireframe: true,
+    ..Default::default()
+  };
+  let triangle = Mesh::from_geometry(
+    geometry(vec![-1.0, -1.5, 0.0, 1.0, -1.5, 0.0, 0.0, -0.5, 0.0]),
+    Rc::new(material),
+  );
+  scene.add(triangle);
+
+  let camera = PerspectiveCamera::new(45.0, 1.0, 0.1, 100.0);
+  camera.update_position(Vec3::new(0.0, 0.0, 5.0));
+  camera.look_at(Vec3::zero());
+
+  let mut renderer = GlRenderer::new();
+  renderer.set_size(SIZE as f32, SIZE as f32);
+  let buffer = renderer.render(scene, camera);
+  let data = buffer.data();
+
+  save("render_primitives", data);
+
+  // the line spans x in [52, 148] on the rows 99 to 101
+  for y in 99..=101 {
+    assert!(is_lit(data, 100, y));
+    assert!(is_lit(data, 60, y));
+  }
+  assert!(!is_lit(data, 100, 97));
+  assert!(!is_lit(data, 100, 103));
+  assert!(!is_lit(data, 40, 100));
+
+  // the disc is centered on (100, 52)
+  for (x, y) in [(100, 52), (97, 52), (103, 52), (100, 49), (100, 55)] {
+    assert!(is_lit(data, x, y));
+  }
+  for (x, y) in [(96, 48), (104, 48), (96, 56), (104, 56)] {
+    assert!(!is_lit(data, x, y));
+  }
+
+  // the triangle is hollow, from the row 124 to its base on the row 172
+  assert!(is_lit(data, 100, 172));
+  assert!(!is_lit(data, 100, 150));
+}
+
+#[test]
+fn render_clipped() {
+  let scene = Scene::new();
+
+  // a ground reaching from far ahead to behind the camera
+  let ground = Mesh::from_geometry(
+    geometry(vec![-5.0, -1.0, -20.0, 5.0, -1.0, -20.0, 0.0, -1.0, 20.0]),
+    Rc::new(StandardMeshMaterial::default()),
+  );
+  scene.add(ground);
+
+  // a line above it, from behind the camera to the horizon
+  let line = Line::from_geometry(
+    geometry(vec![0.0, 1.0, 20.0, 0.0, 1.0, -20.0]),
+    Rc::new(StandardMeshMaterial::default()),
+  );
+  scene.add(line);
+
+  let camera = PerspectiveCamera::new(45.0, 1.0, 0.1, 100.0);
+  camera.update_position(Vec3::new(0.0, 0.0, 5.0));
+  camera.look_at(Vec3::zero());
+
+  let mut renderer = GlRenderer::new();
+  renderer.set_size(SIZE as f32, SIZE as f32);
+  let buffer = renderer.render(scene, camera);
+  let data = buffer.data();
+
+  save("render_clipped", data);
+
+  // both are cut by the near plane instead of being dropped
+  for x in [5, 100, 195] {
+    assert!(is_lit(data, x, SIZE - 1));
+  }
+  assert!(!is_lit(data, 60, 100));
+  assert!(is_lit(data, 100, 0));
+  assert!(is_lit(data, 100, 60));
+  assert!(!is_lit(data, 95, 60));
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::{cube, ground, red_at, save, SIZE};
+use three::{
+  cameras::perspective_camera::PerspectiveCamera,
+  core::object_3d::IObject3D,
+  lights::{directional_light::DirectionalLight, light::ILight},
+  math::Vec3,
+  objects::scene::Scene,
+  renderer::{gl_renderer::GlRenderer, shadow_map::ShadowMapType},
+};
+
+#[test]
+fn render_shadow() {
+  let types = [
+    ShadowMapType::BasicShadowMap,
+    ShadowMapType::PCFShadowMap,
+    ShadowMapType::PCFSoftShadowMap,
+    ShadowMapType::VSMShadowMap,
+  ];
+
+  for shadow_type in types {
+    let scene = Scene::new();
+
+    let ground = ground();
+    ground.set_receive_shadow(true);
+    scene.add(ground);
+
+    let cube = cube(0.5);
+    cube.update_position(Vec3::new(0.0, 1.5, 0.0));
+    cube.set_cast_shadow(true);
+    scene.add(cube);
+
+    let light = DirectionalLight::new();
+    let light_position = Vec3::new(2.0, 6.0, 1.0);
+    light.update_position(light_position);
+    light.set_cast_shadow(true);
+    let shadow = light.shadow().unwrap();
+    shadow.set_bias(-0.002);
+    shadow.set_radius(2.0);
+    scene.add(light);
+
+    let camera = PerspectiveCamera::new(45.0, 1.0, 0.1, 100.0);
+    camera.update_position(Vec3::new(0.0, 7.0, 7.0));
+    camera.look_at(Vec3::zero());
+
+    let mut renderer = GlRenderer::new();
+    renderer.set_size(SIZE as f32, SIZE as f32);
+    renderer.shadow_map_mut().set_enable(true);
+    renderer.shadow_map_mut().set_shadow_type(shadow_type);
+    let buffer = renderer.render(scene, camera.clone());
+    let data = buffer.data();
+
+    save(&format!("render_shadow_{shadow_type:?}"), data);
+
+    // the center of the cube seen from the light lands in the middle of its
+    // shadow, the open ground is lit by n · l
+    let shadowed = Vec3::new(0.0, 1.5, 0.0) - light_position * (1.5 / light_position.y);
+    let lit = (light_position.normalize().y * 255.0).round() as i32;
+    assert!((red_at(data, &camera, Vec3::new(2.5, 0.0, 2.5)) as i32 - lit).abs() <= 1);
+    assert!((red_at(data, &camera, Vec3::new(-2.5, 0.0, 2.5)) as i32 - lit).abs() <= 1);
+    assert_eq!(red_at(data, &camera, shadowed), 0, "{shadow_type:?}");
+
+    // from the middle of the shadow out across its edge under the cube, on
+    // +x, the basic shadows step from dark to lit and the filtered ones have
+    // a penumbra
+    let mut profile: Vec<i32> = (0..=200)
+      .map(|step| shadowed + Vec3::new(step as f32 * 0.01, 0.0, 0.0))
+      .map(|point| red_at(data, &camera, point) as i32)
+      .collect();
+    profile.dedup();
+    assert_eq!(profile[0], 0, "{shadow_type:?}");
+    assert!(
+      (profile[profile.len() - 1] - lit).abs() <= 1,
+      "{shadow_type:?}"
+    );
+
+    let penumbra = profile.iter().filter(|&&red| red > 0 && red < lit - 1);
+    if shadow_type == ShadowMapType::BasicShadowMap {
+      assert_eq!(penumbra.count(), 0, "{profile:?}");
+    } else {
+      assert!(penumbra.count() > 0, "{shadow_type:?} {profile:?}");
+    }
+  }
+}
@@ -0,0 +1,332 @@
+//! Headless front end of the tinyrenderer, renders an OBJ model into image files.
+//!
+//! nino-render <obj path or directory> [options]
+
+use std::{f32::consts::PI, path::Path, process};
+
+use math::{color::ToneMapping, Mat4, Vec3};
+use tinyrenderer::{
+  model::from_obj_path,
+  obj_loader::{
+    material::{Material, TexturePointer},
+    shader::Shader,
+  },
+  renderer::{
+    controls::OrbitController,
+    debug::DebugView,
+    light::Light,
+    renderer::Renderer,
+    shader::{
+      gouraud::make_gouraud_shader, pbr::make_pbr_shader, phong::make_phong_shader,
+      shadow::make_shadow_shader, wireframe::make_wireframe_shader,
+    },
+  },
+};
+
+const USAGE: &str = "\
+usage: nino-render <obj path or directory> [options]
+
+options:
+  -o, --output <path>     output image, the format follows the extension
+                          (png, tga, ppm), defaults to out.png
+  -s, --size <w>x<h>      resolution, defaults to 800x800
+  --eye <x,y,z>           camera position, defaults to 1,2,5
+  --target <x,y,z>        point looked at, defaults to 0,0,0
+  --fov <degrees>         frustum angle, defaults to 14
+  --ortho <half width>    orthographic projection showing <half width> world
+                          units on each side of the view direction
+  --shader <name>         gouraud | phong | pbr | wireframe | shadow, defaults
+                          to gouraud
+  --light <x,y,z>         direction towards the light, defaults to 1,1,1
+  --texture <map>=<path>  binds an image to the shader, e.g. norm=nm.tga,
+                          the maps are diffuse | norm | specular_color |
+                          bump | ... as in the MTL files, can be repeated
+  --tone-mapping <name>   none | exposure | reinhard | aces, defaults to none
+  --exposure <value>      scales the colors before the tone mapping, defaults
+                          to 1
+  --debug <view>          depth | normals | uv | overdraw | triangles | models,
+                          replaces the colors of the shader
+  --turntable <frames>    orbits the camera around the target, writing
+                          <output>_000.<ext>, <output>_001.<ext>, ...
+  -h, --help              prints this message";
+
+struct Options {
+  obj: String,
+  output: String,
+  width: u32,
+  height: u32,
+  eye: Vec3,
+  target: Vec3,
+  fov: f32,
+  ortho: Option<f32>,
+  shader: String,
+  light: Vec3,
+  textures: Vec<(String, String)>,
+  tone_mapping: ToneMapping,
+  exposure: f32,
+  debug_view: DebugView,
+  turntable: Option<u32>,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Self {
+      obj: String::new(),
+      output: "out.png".to_string(),
+      width: 800,
+      height: 800,
+      eye: Vec3::new(1.0, 2.0, 5.0),
+      target: Vec3::zero(),
+      fov: 14.0,
+      ortho: None,
+      shader: "gouraud".to_string(),
+      light: Vec3::new(1.0, 1.0, 1.0),
+      textures: vec![],
+      tone_mapping: ToneMapping::None,
+      exposure: 1.0,
+      debug_view: DebugView::None,
+      turntable: None,
+    }
+  }
+}
+
+fn parse_vec3(value: &str) -> Result<Vec3, String> {
+  let parts = value
+    .split(',')
+    .map(|p| p.trim().parse::<f32>())
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|_| format!("'{}' is not a x,y,z vector", value))?;
+
+  match parts[..] {
+    [x, y, z] => Ok(Vec3::new(x, y, z)),
+    _ => Err(format!("'{}' is not a x,y,z vector", value)),
+  }
+}
+
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+  let invalid = || format!("'{}' is not a <w>x<h> size", value);
+  let (w, h) = value.split_once('x').ok_or_else(invalid)?;
+  let w = w.parse::<u32>().map_err(|_| invalid())?;
+  let h = h.parse::<u32>().map_err(|_| invalid())?;
+  if w == 0 || h == 0 {
+    return Err(invalid());
+  }
+  Ok((w, h))
+}
+
+/// `norm=nm.tga` into the map and the path
+fn parse_texture(value: &str) -> Result<(String, String), String> {
+  let invalid = || format!("'{}' is not a <map>=<path> texture", value);
+  let (map, path) = value.split_once('=').ok_or_else(invalid)?;
+  if !TexturePointer::keys().contains(&map) {
+    return Err(format!("unknown texture map '{}'", map));
+  }
+  if path.is_empty() {
+    return Err(invalid());
+  }
+  Ok((map.to_string(), path.to_string()))
+}
+
+fn parse_tone_mapping(value: &str) -> Result<ToneMapping, String> {
+  match value {
+    "none" => Ok(ToneMapping::None),
+    "exposure" => Ok(ToneMapping::Exposure),
+    "reinhard" => Ok(ToneMapping::Reinhard),
+    "aces" => Ok(ToneMapping::AcesFilmic),
+    _ => Err(format!("unknown tone mapping '{}'", value)),
+  }
+}
+
+fn parse_debug_view(value: &str) -> Result<DebugView, String> {
+  match value {
+    "none" => Ok(DebugView::None),
+    "depth" => Ok(DebugView::Depth),
+    "normals" => Ok(DebugView::Normals),
+    "uv" => Ok(DebugView::UvChecker),
+    "overdraw" => Ok(DebugView::Overdraw),
+    "triangles" => Ok(DebugView::TriangleId),
+    "models" => Ok(DebugView::ModelId),
+    _ => Err(format!("unknown debug view '{}'", value)),
+  }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+  let mut options = Options::default();
+  let mut obj = None;
+
+  while let Some(arg) = args.next() {
+    let mut value = || {
+      args
+        .next()
+        .ok_or_else(|| format!("missing value for '{}'", arg))
+    };
+
+    match arg.as_str() {
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        process::exit(0);
+      }
+      "-o" | "--output" => options.output = value()?,
+      "-s" | "--size" => (options.width, options.height) = parse_size(&value()?)?,
+      "--eye" => options.eye = parse_vec3(&value()?)?,
+      "--target" => options.target = parse_vec3(&value()?)?,
+      "--light" => options.light = parse_vec3(&value()?)?,
+      "--texture" => options.textures.push(parse_texture(&value()?)?),
+      "--fov" => {
+        let fov = value()?;
+        options.fov = fov
+          .parse()
+          .map_err(|_| format!("'{}' is not a number", fov))?;
+      }
+      "--ortho" => {
+        let half_width = value()?;
+        let half_width = half_width
+          .parse::<f32>()
+          .ok()
+          .filter(|w| *w > 0.0)
+          .ok_or_else(|| format!("'{}' is not a positive width", half_width))?;
+        options.ortho = Some(half_width);
+      }
+      "--shader" => options.shader = value()?,
+      "--tone-mapping" => options.tone_mapping = parse_tone_mapping(&value()?)?,
+      "--debug" => options.debug_view = parse_debug_view(&value()?)?,
+      "--exposure" => {
+        let exposure = value()?;
+        options.exposure = exposure
+          .parse()
+          .map_err(|_| format!("'{}' is not a number", exposure))?;
+      }
+      "--turntable" => {
+        let frames = value()?;
+        let frames = frames
+          .parse::<u32>()
+          .ok()
+          .filter(|n| *n > 0)
+          .ok_or_else(|| format!("'{}' is not a positive frame count", frames))?;
+        options.turntable = Some(frames);
+      }
+      _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+      _ if obj.is_none() => obj = Some(arg),
+      _ => return Err(format!("unexpected argument '{}'", arg)),
+    }
+  }
+
+  options.obj = obj.ok_or("missing the obj path")?;
+  Ok(options)
+}
+
+fn make_shader(name: &str) -> Result<Shader, String> {
+  match name {
+    "gouraud" => Ok(make_gouraud_shader()),
+    "phong" => Ok(make_phong_shader()),
+    "pbr" => Ok(make_pbr_shader()),
+    "wireframe" => Ok(make_wireframe_shader(0.01)),
+    "shadow" => Ok(make_shadow_shader()),
+    _ => Err(format!("unknown shader '{}'", name)),
+  }
+}
+
+/// `out.png` becomes `out_007.png` for the 8th frame
+fn frame_path(output: &str, frame: u32) -> String {
+  let path = Path::new(output);
+  let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("out");
+  let name = match path.extension().and_then(|e| e.to_str()) {
+    Some(ext) => format!("{}_{:03}.{}", stem, frame, ext),
+    None => format!("{}_{:03}", stem, frame),
+  };
+  path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+fn save(path: &str, data: &[u8], width: u32, height: u32) -> Result<(), String> {
+  let image = image::RgbaImage::from_raw(width, height, data.to_vec())
+    .ok_or("the color buffer does not match the resolution")?;
+
+  let is_ppm = Path::new(path)
+    .extension()
+    .is_some_and(|ext| ext.eq_ignore_ascii_case("ppm"));
+  // ppm has no alpha channel
+  let result = if is_ppm {
+    image::DynamicImage::ImageRgba8(image).to_rgb8().save(path)
+  } else {
+    image.save(path)
+  };
+
+  result.map_err(|e| format!("failed to write '{}': {}", path, e))
+}
+
+fn run(options: Options) -> Result<(), String> {
+  let scene = from_obj_path(&options.obj, &options.obj)
+    .map_err(|e| format!("failed to load '{}': {:?}", options.obj, e))?;
+
+  let mut material = Material {
+    shader: make_shader(&options.shader)?,
+    ..Default::default()
+  };
+
+  let (width, height) = (options.width, options.height);
+  let mut renderer = Renderer::new(width, height);
+  for (map, path) in &options.textures {
+    if !Path::new(path).is_file() {
+      return Err(format!("failed to load '{}': no such file", path));
+    }
+    // the colors are stored in sRGB, the normals & co. are linear data
+    match map.as_str() {
+      "ambient" | "diffuse" | "specular_color" | "emissive" => {
+        renderer.load_color_texture(path, path)
+      }
+      _ => renderer.load_texture(path, path),
+    }
+    material.texture_map.set_by_key(map, path.clone());
+  }
+  renderer.set_tone_mapping(options.tone_mapping);
+  renderer.set_exposure(options.exposure);
+  renderer.set_debug_view(options.debug_view);
+  // the lambertian term of the pbr shader is divided by pi, the intensity
+  // makes up for it to match the brightness of the other shaders
+  let intensity = if options.shader == "pbr" { PI } else { 1.0 };
+  renderer.lights = vec![Light::directional(
+    options.light,
+    Vec3::new(1.0, 1.0, 1.0),
+    intensity,
+  )];
+  match options.ortho {
+    Some(half_width) => renderer.camera.update_orthographic(1.0, 1000.0, half_width),
+    None => renderer
+      .camera
+      .update_frustum(1.0, 1000.0, options.fov.to_radians()),
+  }
+
+  // the eye is kept at the same height & distance, and rotated around the
+  // vertical axis through the target
+  let mut orbit = OrbitController::new(options.eye, options.target);
+  let azimuth = orbit.azimuth;
+  let frames = options.turntable.unwrap_or(1);
+
+  for frame in 0..frames {
+    orbit.azimuth = azimuth + 2.0 * PI * frame as f32 / frames as f32;
+    orbit.update(&mut renderer.camera);
+    renderer.render(&scene, Mat4::identity(), &material);
+    let color = renderer.take_color();
+
+    let path = match options.turntable {
+      Some(_) => frame_path(&options.output, frame),
+      None => options.output.clone(),
+    };
+    save(&path, color.data(), width, height)?;
+    println!("{}", path);
+  }
+
+  Ok(())
+}
+
+fn main() {
+  let options = parse_args(std::env::args().skip(1)).unwrap_or_else(|message| {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    process::exit(2);
+  });
+
+  if let Err(message) = run(options) {
+    eprintln!("error: {}", message);
+    process::exit(1);
+  }
+}
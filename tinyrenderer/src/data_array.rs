@@ -1,5 +1,8 @@
 // struct PureElemImage<T> {}
-use math;
+use math::{
+  self,
+  color::{linear_to_srgb_color, ToneMapping},
+};
 
 pub struct DataArray<T> {
   data: Vec<T>,
@@ -23,6 +26,109 @@ impl<T> DataArray<T> {
   pub fn data(&self) -> &Vec<T> {
     &self.data
   }
+
+  /// Splits the array into bands of `rows` rows, the last one may be shorter.
+  /// Each band mutably borrows its own part of the data, so the bands can be
+  /// written from different threads.
+  pub fn split_rows_mut(&mut self, rows: u32) -> Vec<DataRows<'_, T>> {
+    let w = self.w;
+    let rows = rows.max(1);
+    let channels = self.data.len() / (self.w * self.h).max(1) as usize;
+    let chunk = (w * rows) as usize * channels;
+    if chunk == 0 {
+      return vec![];
+    }
+
+    self
+      .data
+      .chunks_mut(chunk)
+      .enumerate()
+      .map(|(i, data)| {
+        let y_min = i as u32 * rows;
+        let y_max = y_min + (data.len() / (w as usize * channels)) as u32;
+        DataRows {
+          data,
+          w,
+          y_min,
+          y_max,
+        }
+      })
+      .collect()
+  }
+}
+
+/// A band of rows `[y_min, y_max)` borrowed from a `DataArray`, addressed with
+/// the coordinates of the whole array.
+pub struct DataRows<'a, T> {
+  data: &'a mut [T],
+  w: u32,
+  y_min: u32,
+  y_max: u32,
+}
+
+impl<'a, T> DataRows<'a, T> {
+  pub fn y_min(&self) -> u32 {
+    self.y_min
+  }
+
+  /// exclusive
+  pub fn y_max(&self) -> u32 {
+    self.y_max
+  }
+
+  fn offset(&self, x: u32, y: u32) -> usize {
+    (x + (y - self.y_min) * self.w) as usize
+  }
+}
+
+impl<'a> DataRows<'a, u8> {
+  pub fn set(&mut self, x: u32, y: u32, color: &math::Vec4) {
+    let p = self.offset(x, y) * 4;
+    write_color(&mut self.data[p..p + 4], color);
+  }
+
+  pub fn get(&self, x: u32, y: u32) -> math::Vec4 {
+    let p = self.offset(x, y) * 4;
+    read_color(&self.data[p..p + 4])
+  }
+}
+
+impl<'a> DataRows<'a, math::Vec4> {
+  pub fn set(&mut self, x: u32, y: u32, color: &math::Vec4) {
+    let p = self.offset(x, y);
+    self.data[p] = *color;
+  }
+
+  pub fn get(&self, x: u32, y: u32) -> math::Vec4 {
+    self.data[self.offset(x, y)]
+  }
+}
+
+impl<'a> DataRows<'a, f32> {
+  pub fn set(&mut self, x: u32, y: u32, value: f32) {
+    let p = self.offset(x, y);
+    self.data[p] = value;
+  }
+
+  pub fn get(&self, x: u32, y: u32) -> f32 {
+    self.data[self.offset(x, y)]
+  }
+}
+
+fn write_color(pixel: &mut [u8], color: &math::Vec4) {
+  pixel[0] = (color.x * 255.0) as u8;
+  pixel[1] = (color.y * 255.0) as u8;
+  pixel[2] = (color.z * 255.0) as u8;
+  pixel[3] = (color.w * 255.0) as u8;
+}
+
+fn read_color(pixel: &[u8]) -> math::Vec4 {
+  math::Vec4::new(
+    pixel[0] as f32 / 255.0,
+    pixel[1] as f32 / 255.0,
+    pixel[2] as f32 / 255.0,
+    pixel[3] as f32 / 255.0,
+  )
 }
 
 impl<T> Default for DataArray<T> {
@@ -45,13 +151,30 @@ impl DataArray<u8> {
   }
 
   pub fn set(&mut self, x: u32, y: u32, color: &math::Vec4) {
-    let p = (x + y * self.w) as usize * 3;
+    let p = (x + y * self.w) as usize * 4;
     if p >= self.data.len() {
       return;
     }
-    self.data[(x + y * self.w) as usize * 3] = (color.x * 255.0) as u8;
-    self.data[(x + y * self.w) as usize * 3 + 1] = (color.y * 255.0) as u8;
-    self.data[(x + y * self.w) as usize * 3 + 2] = (color.z * 255.0) as u8;
+    write_color(&mut self.data[p..p + 4], color);
+  }
+
+  pub fn get(&self, x: u32, y: u32) -> math::Vec4 {
+    let p = (x + y * self.w) as usize * 4;
+    read_color(&self.data[p..p + 4])
+  }
+}
+
+impl DataArray<math::Vec4> {
+  pub fn clear(&mut self, color: &math::Vec4) {
+    self.data.fill(*color)
+  }
+
+  pub fn set(&mut self, x: u32, y: u32, color: &math::Vec4) {
+    self.data[(x + y * self.w) as usize] = *color;
+  }
+
+  pub fn get(&self, x: u32, y: u32) -> math::Vec4 {
+    self.data[(x + y * self.w) as usize]
   }
 }
 
@@ -64,12 +187,15 @@ impl DataArray<f32> {
     self.data[(x + y * self.w) as usize] = value;
   }
 
-  pub fn get(&mut self, x: u32, y: u32) -> f32 {
+  pub fn get(&self, x: u32, y: u32) -> f32 {
     self.data[(x + y * self.w) as usize]
   }
 }
 
+/// RGBA, 4 bytes per pixel
 pub type ColorBuffer = DataArray<u8>;
+/// linear RGBA, the colors may go past 1 before the tone mapping
+pub type HdrColorBuffer = DataArray<math::Vec4>;
 pub type DepthBuffer = DataArray<f32>;
 
 impl DepthBuffer {
@@ -84,9 +210,37 @@ impl DepthBuffer {
 impl ColorBuffer {
   pub fn new(w: u32, h: u32) -> Self {
     Self {
-      data: vec![255; (w * h * 3) as usize],
+      data: vec![255; (w * h * 4) as usize],
       w,
       h,
     }
   }
 }
+
+impl HdrColorBuffer {
+  /// cleared to white, like `ColorBuffer::new`
+  pub fn new(w: u32, h: u32) -> Self {
+    Self {
+      data: vec![math::Vec4::new(1.0, 1.0, 1.0, 1.0); (w * h) as usize],
+      w,
+      h,
+    }
+  }
+
+  /// Tone maps the colors and encodes them to sRGB, the 8 bit buffer is what
+  /// gets displayed.
+  pub fn to_color_buffer(&self, tone_mapping: ToneMapping, exposure: f32) -> ColorBuffer {
+    let mut data = Vec::with_capacity(self.data.len() * 4);
+    for color in &self.data {
+      let mapped = tone_mapping.apply(&color.truncated_to_vec3(), exposure);
+      let encoded = linear_to_srgb_color(&math::Vec4::from_vec3(&mapped, color.w));
+      data.extend([encoded.x, encoded.y, encoded.z, encoded.w].map(|c| (c * 255.0).round() as u8));
+    }
+
+    ColorBuffer {
+      data,
+      w: self.w,
+      h: self.h,
+    }
+  }
+}
@@ -4,4 +4,6 @@ pub mod model;
 pub mod obj_loader;
 pub mod renderer;
 pub mod shade_triangle;
+#[cfg(test)]
+mod test_utils;
 mod utils;
@@ -7,7 +7,12 @@ use crate::{
   },
   utils::swap_and_move,
 };
-use math::{Vec2, Vec3, Vec4};
+use std::collections::HashMap;
+
+use math::{
+  generate_normals, generate_tangents, BoundingBox, BoundingSphere, NormalGeneration, Vec2, Vec3,
+  Vec4,
+};
 
 // type TextureRefer<'a> = TextureMap<&'a Texture>;
 // impl<'a> Default for TextureRefer<'a> {
@@ -57,6 +62,8 @@ pub struct Vertex {
   pub position: Vec4,
   pub normal: Option<Vec3>,
   pub texture: Option<Vec2>,
+  /// MikkTSpace tangent, `w` being the sign of the bitangent
+  pub tangent: Option<Vec4>,
   // pub material: Option<VertexMaterial>,
   pub rhw: f32,
 }
@@ -67,6 +74,7 @@ impl Vertex {
       position: pos,
       normal: norm,
       texture: text,
+      tangent: None,
       // material: None,
       rhw: 1.0,
     }
@@ -96,6 +104,7 @@ impl Vertex {
       position,
       normal,
       texture,
+      tangent: None,
       // material: vertex_material,
       rhw: 1.0,
     }
@@ -116,18 +125,54 @@ impl Vertex {
   // }
 }
 
+/// An indexed triangle mesh, every 3 `indices` into the `vertices` make a
+/// triangle.
 #[derive(Debug)]
 pub struct Model {
   pub vertices: Vec<Vertex>,
+  pub indices: Vec<u32>,
   name: String,
   material: Option<u32>,
+  /// in model space, `None` for the models without any vertex
+  bounding_box: Option<BoundingBox>,
+  bounding_sphere: Option<BoundingSphere>,
 }
 impl Model {
   pub fn get_material(&self) -> Option<u32> {
     self.material
   }
 
-  pub fn from_obj_model(obj_model: &ObjModel, scene: &Scene) -> Self {
+  pub fn get_bounding_box(&self) -> Option<&BoundingBox> {
+    self.bounding_box.as_ref()
+  }
+
+  pub fn get_bounding_sphere(&self) -> Option<&BoundingSphere> {
+    self.bounding_sphere.as_ref()
+  }
+
+  pub fn triangles(&self) -> impl Iterator<Item = [Vertex; 3]> + '_ {
+    self
+      .indices
+      .chunks_exact(3)
+      .map(|t| [0, 1, 2].map(|i| self.vertices[t[i] as usize]))
+  }
+
+  /// average of the vertex positions in model space
+  pub fn centroid(&self) -> Vec4 {
+    if self.vertices.is_empty() {
+      return Vec4::new(0.0, 0.0, 0.0, 1.0);
+    }
+
+    let sum = self
+      .vertices
+      .iter()
+      .fold(Vec4::zero(), |sum, v| sum + v.position);
+    sum / self.vertices.len() as f32
+  }
+
+  /// The faces without `vn` data get generated normals, a model without any
+  /// smoothing group is smoothed as a whole and split by the crease angle only.
+  pub fn from_obj_model(obj_model: &ObjModel, scene: &Scene, normals: &NormalGeneration) -> Self {
     let name = obj_model.name.clone();
     let mut vertices = vec![];
 
@@ -147,10 +192,96 @@ impl Model {
       }
     }
 
+    if vertices.iter().any(|v| v.normal.is_none()) {
+      let triangles: Vec<[usize; 3]> = obj_model
+        .faces
+        .iter()
+        .map(|f| f.vertices.map(|v| v.position_index as usize))
+        .collect();
+      let groups: Vec<u32> = if obj_model.faces.iter().all(|f| f.smoothing_group == 0) {
+        vec![]
+      } else {
+        obj_model.faces.iter().map(|f| f.smoothing_group).collect()
+      };
+
+      let generated = generate_normals(&scene.vertices, &triangles, &groups, normals);
+      for (vertex, normal) in vertices.iter_mut().zip(generated.iter().flatten()) {
+        vertex.normal.get_or_insert(*normal);
+      }
+    }
+
+    // the tangents of the faces with uvs, for the tangent space normal maps
+    let textured: Vec<usize> = (0..obj_model.faces.len())
+      .filter(|f| {
+        vertices[f * 3..f * 3 + 3]
+          .iter()
+          .all(|v| v.texture.is_some())
+      })
+      .collect();
+    if !textured.is_empty() {
+      let corners = |f: usize| &vertices[f * 3..f * 3 + 3];
+      let triangles: Vec<[usize; 3]> = textured
+        .iter()
+        .map(|f| {
+          obj_model.faces[*f]
+            .vertices
+            .map(|v| v.position_index as usize)
+        })
+        .collect();
+      let normals: Vec<[Vec3; 3]> = textured
+        .iter()
+        .map(|f| [0, 1, 2].map(|i| corners(*f)[i].normal.unwrap_or(Vec3::zero())))
+        .collect();
+      let uvs: Vec<[Vec2; 3]> = textured
+        .iter()
+        .map(|f| [0, 1, 2].map(|i| corners(*f)[i].texture.unwrap()))
+        .collect();
+
+      let tangents = generate_tangents(&scene.vertices, &triangles, &normals, &uvs);
+      for (f, tangents) in textured.iter().zip(tangents) {
+        for (vertex, tangent) in vertices[f * 3..f * 3 + 3].iter_mut().zip(tangents) {
+          vertex.tangent = Some(tangent);
+        }
+      }
+    }
+
+    // the corners with the same obj indices, and the same generated normal
+    // and tangent, share a vertex
+    let bits = |v: &[f32]| v.iter().map(|c| c.to_bits()).collect::<Vec<_>>();
+    let mut unique = HashMap::new();
+    let mut indexed = vec![];
+    let indices = obj_model
+      .faces
+      .iter()
+      .flat_map(|f| f.vertices.iter())
+      .zip(&vertices)
+      .map(|(index, vertex)| {
+        let key = (
+          index.position_index,
+          index.texture_index,
+          index.normal_index,
+          vertex.normal.map(|n| bits(&[n.x, n.y, n.z])),
+          vertex.tangent.map(|t| bits(&[t.x, t.y, t.z, t.w])),
+        );
+        *unique.entry(key).or_insert_with(|| {
+          indexed.push(*vertex);
+          (indexed.len() - 1) as u32
+        })
+      })
+      .collect();
+
+    let positions: Vec<Vec3> = indexed
+      .iter()
+      .map(|v| v.position.truncated_to_vec3())
+      .collect();
+
     Self {
       name,
-      vertices,
+      vertices: indexed,
+      indices,
       material,
+      bounding_box: BoundingBox::from_points(&positions),
+      bounding_sphere: BoundingSphere::from_points(&positions),
     }
   }
 }
@@ -183,7 +314,17 @@ impl Scene {
   pub fn add_model(&mut self, model: Model) {}
 }
 
-pub fn from_obj_path(path: &str, name: &'static str) -> Result<Scene, ParserError> {
+pub fn from_obj_path(path: &str, name: &str) -> Result<Scene, ParserError> {
+  from_obj_path_with_normals(path, name, &NormalGeneration::default())
+}
+
+/// `from_obj_path` with the options of the normals generated for the faces
+/// without `vn` data
+pub fn from_obj_path_with_normals(
+  path: &str,
+  name: &str,
+  normals: &NormalGeneration,
+) -> Result<Scene, ParserError> {
   let p = std::path::Path::new(path);
 
   let obj_path: &'static str = if p.is_dir() {
@@ -218,7 +359,50 @@ pub fn from_obj_path(path: &str, name: &'static str) -> Result<Scene, ParserErro
 
   let mut scene = Scene::from_obj_scene(obj_scene);
   for obj_model in &obj_scene.models {
-    scene.models.push(Model::from_obj_model(obj_model, &scene));
+    scene
+      .models
+      .push(Model::from_obj_model(obj_model, &scene, normals));
   }
   Ok(scene)
 }
+
+#[cfg(test)]
+mod tests {
+  use crate::test_utils::scene;
+
+  #[test]
+  fn shared_corners_are_indexed_once() {
+    let scene = scene(
+      "indexed-quad",
+      "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n",
+      "",
+    );
+    let model = &scene.models[0];
+
+    assert_eq!(model.vertices.len(), 4);
+    assert_eq!(model.indices.len(), 6);
+    assert_eq!(model.triangles().count(), 2);
+  }
+
+  #[test]
+  fn corners_with_other_normals_are_kept_apart() {
+    // two triangles folded by 45 degrees, sharing the corners 2 and 3
+    let fold = |groups: [&str; 2]| {
+      format!(
+        "v -1 0 0\nv 0 0 0\nv 0 1 0\nv 1 0 -1\n{}\nf 1 2 3\n{}\nf 2 4 3\n",
+        groups[0], groups[1]
+      )
+    };
+
+    let smooth = scene("indexed-smooth", &fold(["s 1", "s 1"]), "");
+    assert_eq!(smooth.models[0].vertices.len(), 4);
+
+    let hard = scene("indexed-hard", &fold(["s 1", "s 2"]), "");
+    let model = &hard.models[0];
+    assert_eq!(model.vertices.len(), 6);
+    assert_eq!(model.indices.len(), 6);
+    for [a, b, c] in model.triangles() {
+      assert!(a.normal == b.normal && b.normal == c.normal);
+    }
+  }
+}
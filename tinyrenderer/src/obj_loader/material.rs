@@ -1,7 +1,14 @@
+use super::sampler::{build_mip_chain, missing_texture, sample_levels, MipLevel, Sampler};
 use super::shader::Shader;
-use crate::utils::swap_and_move;
-use image::{GenericImageView, ImageError};
-use math::{Vec2, Vec3, Vec4};
+use crate::{
+  renderer::states::{BlendState, CullMode},
+  utils::swap_and_move,
+};
+use image::ImageError;
+use math::{
+  color::{srgb_to_linear_color, ColorSpace},
+  Vec2, Vec3, Vec4,
+};
 use std::path::Path;
 use std::{collections::HashMap, fmt::Debug};
 
@@ -45,7 +52,14 @@ make_material_base!(
   transmission_filter: Vec3,
   optical_density: f32,
   receive_shadow: bool,
-  illum: u8;;
+  illum: u8,
+  // the PBR extension of MTL, `Pr` and `Pm`
+  roughness: f32,
+  metallic: f32,
+  // overrides the renderer wide cull mode when set
+  cull: CullMode,
+  // the material is drawn in the transparent pass when set
+  blend: BlendState;;
   // below are fixed;
   shader: Shader,
   name: String,
@@ -75,6 +89,10 @@ macro_rules! make_texture_map {
         Self {$($prop,)+ }
       }
 
+      pub fn keys() -> &'static [&'static str] {
+        &[$(stringify!($prop),)+]
+      }
+
       pub fn get_by_key(&self, key: &str) -> Option<&T> {
         match key {
           $(
@@ -83,6 +101,17 @@ macro_rules! make_texture_map {
           _ => None,
         }
       }
+
+      /// `false` when `key` is not one of the fields
+      pub fn set_by_key(&mut self, key: &str, value: T) -> bool {
+        match key {
+          $(
+            stringify!($prop) => self.$prop = Some(value),
+          )+
+          _ => return false,
+        }
+        true
+      }
     }
 
   };
@@ -96,7 +125,10 @@ make_texture_map!(
   alpha,
   refl,
   bump,
-  norm
+  norm,
+  roughness,
+  metallic,
+  emissive
 );
 
 pub type TexturePointer = TextureMap<String>;
@@ -190,42 +222,98 @@ impl MoveMaterials for Mtl {
 pub struct Texture {
   id: u32,
   name: String,
-  image: Option<image::DynamicImage>,
   path: String,
+  loaded: bool,
+  levels: Vec<MipLevel>,
+  sampler: Sampler,
+  color_space: ColorSpace,
 }
 
 impl Texture {
+  /// An image that fails to load is replaced by a magenta checker,
+  /// see `is_loaded`.
   pub fn load(name: &str, path: &Path, id: u32) -> Result<Self, ImageError> {
+    Self::load_with_color_space(name, path, id, ColorSpace::Linear)
+  }
+
+  /// The texels of an sRGB image are decoded to linear once, before the mip
+  /// chain is built, so that the filtering and the lighting happen in linear.
+  pub fn load_with_color_space(
+    name: &str,
+    path: &Path,
+    id: u32,
+    color_space: ColorSpace,
+  ) -> Result<Self, ImageError> {
     let image_data = image::open(path).ok();
+    let loaded = image_data.is_some();
+
+    let base = match image_data {
+      Some(img) => {
+        let rgba = img.to_rgba32f();
+        let (width, height) = rgba.dimensions();
+        let texels = rgba
+          .pixels()
+          .map(|p| Vec4::new(p.0[0], p.0[1], p.0[2], p.0[3]))
+          .map(|texel| match color_space {
+            ColorSpace::Linear => texel,
+            ColorSpace::Srgb => srgb_to_linear_color(&texel),
+          })
+          .collect();
+        MipLevel::new(width, height, texels)
+      }
+      None => missing_texture(),
+    };
 
     Ok(Self {
       id,
       name: name.to_string(),
-      image: image_data,
       path: path.to_str().expect("Not a valid texture path").to_string(),
+      loaded,
+      levels: build_mip_chain(base),
+      sampler: Sampler::default(),
+      color_space,
     })
   }
 
-  ///  @param vt standard vt with x,y range from -1 to 1.
+  pub fn get_color_space(&self) -> ColorSpace {
+    self.color_space
+  }
+
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn get_path(&self) -> &str {
+    &self.path
+  }
+
+  /// false when the image file is missing or can not be decoded
+  pub fn is_loaded(&self) -> bool {
+    self.loaded
+  }
+
+  pub fn get_sampler(&self) -> &Sampler {
+    &self.sampler
+  }
+
+  pub fn set_sampler(&mut self, sampler: Sampler) {
+    self.sampler = sampler;
+  }
+
+  pub fn get_levels(&self) -> &Vec<MipLevel> {
+    &self.levels
+  }
+
+  ///  @param vt standard vt with x,y range from 0 to 1, wrapped outside.
   pub fn get_pixel(&self, vt: Vec2) -> Vec4 {
-    let img = self
-      .image
-      .as_ref()
-      .expect(&format!("Fail to load texture:{}", &self.path.as_str()));
-
-    let width = img.width();
-    let height = img.height();
-
-    let x = (vt.x * (width - 1) as f32) as u32;
-    let y = ((1.0 - vt.y) * (height - 1) as f32) as u32;
-
-    let rgba = img.get_pixel(x, y).0;
-    Vec4::new(
-      rgba[0] as f32 / 255.0,
-      rgba[1] as f32 / 255.0,
-      rgba[2] as f32 / 255.0,
-      rgba[3] as f32 / 255.0,
-    )
+    self.sample(vt, None)
+  }
+
+  /// Samples with the sampler of the texture. `derivatives` are the screen
+  /// space derivatives of `vt`, see `Varying::get_uv_derivatives`, the level
+  /// of detail is 0 without them.
+  pub fn sample(&self, vt: Vec2, derivatives: Option<(Vec2, Vec2)>) -> Vec4 {
+    sample_levels(&self.levels, &self.sampler, vt, derivatives)
   }
 }
 
@@ -242,13 +330,27 @@ pub trait GetTexture {
 
 impl Textures {
   pub fn load(&mut self, filepath: &str, name: &str) -> Result<u32, ImageError> {
+    self.load_with_color_space(filepath, name, ColorSpace::Linear)
+  }
+
+  /// A texture already loaded under `name` is returned as is, whatever its
+  /// color space.
+  pub fn load_with_color_space(
+    &mut self,
+    filepath: &str,
+    name: &str,
+    color_space: ColorSpace,
+  ) -> Result<u32, ImageError> {
     if let Some(id) = self.name_id_map.get(name) {
       return Ok(*id);
     }
 
     let id = self.auto_incr_id;
     let path = Path::new(filepath);
-    self.data.insert(id, Texture::load(name, path, id)?);
+    self.data.insert(
+      id,
+      Texture::load_with_color_space(name, path, id, color_space)?,
+    );
     self.name_id_map.insert(name.to_string(), id);
     self.auto_incr_id += 1;
     Ok(id)
@@ -258,6 +360,25 @@ impl Textures {
     self.data.get(&id)
   }
 
+  pub fn get_texture_by_name(&self, name: &str) -> Option<&Texture> {
+    self
+      .name_id_map
+      .get(name)
+      .and_then(|id| self.get_texture_by_id(*id))
+  }
+
+  /// applies the same sampler to every texture of the store
+  pub fn set_sampler(&mut self, sampler: Sampler) {
+    for texture in self.data.values_mut() {
+      texture.set_sampler(sampler);
+    }
+  }
+
+  pub fn get_texture_mut_by_name(&mut self, name: &str) -> Option<&mut Texture> {
+    let id = self.name_id_map.get(name)?;
+    self.data.get_mut(id)
+  }
+
   pub fn get_texture_by_ids(&self, ids: Vec<u32>) -> Vec<Option<&Texture>> {
     ids
       .into_iter()
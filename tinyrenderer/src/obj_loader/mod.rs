@@ -5,6 +5,7 @@ mod model;
 mod mtl_parser;
 mod obj_parser;
 mod parser;
+pub mod sampler;
 pub mod shader;
 
 pub use model::*;
@@ -1,5 +1,5 @@
 use crate::utils::swap_and_move;
-use math::{Vec2, Vec3};
+use math::{triangulate_face, ObjVertex, Vec2, Vec3};
 
 use super::{
   defines::ParserError,
@@ -20,14 +20,26 @@ impl VertexIndex {
     }
   }
 }
+impl From<ObjVertex> for VertexIndex {
+  fn from(v: ObjVertex) -> Self {
+    Self::new(v.position, v.normal, v.uv)
+  }
+}
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Face {
   pub vertices: [VertexIndex; 3],
+  /// the `s` group the face belongs to, `0` when smoothing is off
+  pub smoothing_group: u32,
 }
+
 #[derive(Debug, Default)]
 pub struct Model {
   pub name: String,
   pub faces: Vec<Face>,
+  /// the polylines of the `l` elements
+  pub lines: Vec<Vec<VertexIndex>>,
+  /// the vertices of the `p` elements
+  pub points: Vec<VertexIndex>,
   pub material: Option<String>,
 }
 
@@ -36,6 +48,8 @@ impl Model {
     Self {
       name,
       faces: Default::default(),
+      lines: Default::default(),
+      points: Default::default(),
       material: None,
     }
   }
@@ -43,6 +57,10 @@ impl Model {
   pub fn get_faces(&self) -> &Vec<Face> {
     &self.faces
   }
+
+  pub fn is_empty(&self) -> bool {
+    self.faces.is_empty() && self.lines.is_empty() && self.points.is_empty()
+  }
 }
 #[derive(Debug, Default)]
 pub struct Scene {
@@ -51,6 +69,7 @@ pub struct Scene {
   pub normals: Vec<Vec3>,
   pub texture_coordinates: Vec<Vec2>,
   pub stores: MtlStores,
+  smoothing_group: u32,
 }
 
 impl Scene {
@@ -61,6 +80,7 @@ impl Scene {
       normals: Default::default(),
       texture_coordinates: Default::default(),
       stores: Default::default(),
+      smoothing_group: 0,
     }
   }
 
@@ -78,6 +98,35 @@ impl Scene {
     Ok(())
   }
 
+  /// Adds a face with any number of vertices, see `triangulate_face`.
+  pub fn add_polygon(&mut self, vertices: &[VertexIndex]) -> Result<(), ParserError> {
+    let indices: Vec<usize> = vertices.iter().map(|v| v.position_index as usize).collect();
+    for [a, b, c] in triangulate_face(&self.vertices, &indices) {
+      self.add_face(Face {
+        vertices: [vertices[a], vertices[b], vertices[c]],
+        smoothing_group: self.smoothing_group,
+      })?;
+    }
+    Ok(())
+  }
+
+  pub fn add_polyline(&mut self, vertices: &[VertexIndex]) -> Result<(), ParserError> {
+    let model = self.models.last_mut().ok_or(ParserError::ModelNotInit)?;
+    model.lines.push(vertices.to_vec());
+    Ok(())
+  }
+
+  pub fn add_points(&mut self, vertices: &[VertexIndex]) -> Result<(), ParserError> {
+    let model = self.models.last_mut().ok_or(ParserError::ModelNotInit)?;
+    model.points.extend_from_slice(vertices);
+    Ok(())
+  }
+
+  /// `0` turns the smoothing off
+  pub fn set_smoothing_group(&mut self, group: u32) {
+    self.smoothing_group = group;
+  }
+
   pub fn add_vertex(&mut self, vertex: Vec3) {
     self.vertices.push(vertex)
   }
@@ -90,12 +139,18 @@ impl Scene {
     self.texture_coordinates.push(texture_coordinate);
   }
 
+  /// A `usemtl` after some faces starts a new model under the same name,
+  /// so every model is drawn with a single material.
   pub fn bind_material(&mut self, material_name: String) -> Result<(), ParserError> {
-    self
-      .models
-      .last_mut()
-      .ok_or(ParserError::ModelNotInit)?
-      .material = Some(material_name);
+    let model = self.models.last_mut().ok_or(ParserError::ModelNotInit)?;
+
+    if model.is_empty() {
+      model.material = Some(material_name);
+    } else if model.material.as_ref() != Some(&material_name) {
+      let mut next = Model::new(model.name.clone());
+      next.material = Some(material_name);
+      self.models.push(next);
+    }
     Ok(())
   }
 }
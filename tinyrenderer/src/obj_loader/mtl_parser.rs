@@ -1,5 +1,5 @@
 // mtl -> Material Template Library
-use math::Vec3;
+use math::{color::ColorSpace, Vec3};
 
 use super::{
   defines::{parse_num, parse_token, parse_token_ok, ParserError},
@@ -9,15 +9,18 @@ use super::{
 
 pub struct MtlParserImpl;
 
+// the color maps are sRGB, the data maps (normals, roughness...) are linear
 macro_rules! parse_texture_token {
   ($expr:expr; $textures:ident; $dir:ident) => {
+    parse_texture_token!($expr; $textures; $dir; ColorSpace::Linear)
+  };
+  ($expr:expr; $textures:ident; $dir:ident; $color_space:expr) => {
     {
       let name = parse_token_ok!($expr;String);
       if let Some(n) = &name {
-        let mut filepath = $dir.to_string();
-        filepath.push_str(&n);
+        let filepath = std::path::Path::new($dir).join(n);
 
-        let _ = $textures.load(&filepath, n);
+        let _ = $textures.load_with_color_space(filepath.to_str().unwrap_or(n), n, $color_space);
       }
       name
     }
@@ -55,12 +58,20 @@ impl ParseLine<Mtl> for MtlParserImpl {
           }
           "Ni" => current.optical_density = parse_token_ok!(tokens.next();f32),
           "d" => current.dissolve = parse_token_ok!(tokens.next();f32),
-          "Tr" => current.dissolve = parse_token_ok!(tokens.next();f32),
+          // transparency is the complement of dissolve
+          "Tr" => current.dissolve = parse_token_ok!(tokens.next();f32).map(|tr| 1.0 - tr),
           "illum" => current.illum = parse_token_ok!(tokens.next();u8),
-          "map_Ka" => map.ambient = parse_texture_token!(tokens.next();texutures;working_dir),
-          "map_Kd" => map.diffuse = parse_texture_token!(tokens.next();texutures;working_dir),
+          "Pr" => current.roughness = parse_token_ok!(tokens.next();f32),
+          "Pm" => current.metallic = parse_token_ok!(tokens.next();f32),
+          "map_Ka" => {
+            map.ambient = parse_texture_token!(tokens.next();texutures;working_dir;ColorSpace::Srgb)
+          }
+          "map_Kd" => {
+            map.diffuse = parse_texture_token!(tokens.next();texutures;working_dir;ColorSpace::Srgb)
+          }
           "map_Ks" => {
-            map.specular_color = parse_texture_token!(tokens.next();texutures;working_dir)
+            map.specular_color =
+              parse_texture_token!(tokens.next();texutures;working_dir;ColorSpace::Srgb)
           }
           "map_Ns" => {
             map.specular_highlight = parse_texture_token!(tokens.next();texutures;working_dir)
@@ -69,6 +80,11 @@ impl ParseLine<Mtl> for MtlParserImpl {
           "map_refl" => map.refl = parse_texture_token!(tokens.next();texutures;working_dir),
           "map_Bump" => map.bump = parse_texture_token!(tokens.next();texutures;working_dir),
           "norm" => map.norm = parse_texture_token!(tokens.next();texutures;working_dir),
+          "map_Pr" => map.roughness = parse_texture_token!(tokens.next();texutures;working_dir),
+          "map_Pm" => map.metallic = parse_texture_token!(tokens.next();texutures;working_dir),
+          "map_Ke" => {
+            map.emissive = parse_texture_token!(tokens.next();texutures;working_dir;ColorSpace::Srgb)
+          }
           _ => {}
         }
       }
@@ -1,13 +1,11 @@
-use std::ops::Not;
-
-use math::{Vec2, Vec3};
+use math::{parse_obj_vertices, ObjCounts, Vec2, Vec3};
 
 use super::{
   defines::{parse_num, parse_token, ParserError, ParserResult},
   material::MoveMaterials,
   mtl_parser::load_mtl,
   parser::{ParseLine, Parser},
-  Face, Scene, VertexIndex,
+  Scene, VertexIndex,
 };
 
 pub struct ObjParserImpl;
@@ -42,53 +40,32 @@ impl ParseLine<Scene> for ObjParserImpl {
       }
       "usemtl" => scene.bind_material(parse_token!(tokens.next(); String)?)?,
       "f" => {
-        let mut vertex_vec = vec![];
-        let mut done = false;
-
-        while !done {
-          match tokens.next() {
-            Some(str) => {
-              let splited: Vec<&str> = str.split("/").collect();
-              let indices = splited.as_slice();
-              if indices.len() > 3 || indices.len() < 1 {
-                return Err(ParserError::InvalidSyntax(
-                  "face vertex indices".to_string(),
-                ));
-              }
-
-              let (mut texture_index, mut normal_index) = (None, None);
-
-              match *indices {
-                [_, second, third] => {
-                  if second.is_empty().not() {
-                    texture_index = Some(parse_num!(second, u32) - 1);
-                  }
-                  normal_index = Some(parse_num!(third, u32) - 1)
-                }
-                [_, second] => {
-                  texture_index = Some(parse_num!(second, u32) - 1);
-                }
-                [_] => {}
-                _ => {
-                  dbg!(indices);
-                  return Err(ParserError::InvalidSyntax("face vertex format".to_string()))
-                },
-              }
-
-              let vertex_index = parse_num!(indices[0], u32) - 1;
-              vertex_vec.push(VertexIndex::new(vertex_index, normal_index, texture_index));
-            }
-            None => {
-              done = true;
-            }
-          }
-        }
-        if vertex_vec.len() != 3 {
+        let vertices = parse_vertex_indices(tokens, scene)?;
+        if vertices.len() < 3 {
           return Err(ParserError::InvalidSyntax("Face Vertices".to_string()));
         }
-
-        let vertices: [VertexIndex; 3] = [vertex_vec[0], vertex_vec[1], vertex_vec[2]];
-        scene.add_face(Face { vertices })?;
+        scene.add_polygon(&vertices)?;
+      }
+      "l" => {
+        let vertices = parse_vertex_indices(tokens, scene)?;
+        if vertices.len() < 2 {
+          return Err(ParserError::InvalidSyntax("Line Vertices".to_string()));
+        }
+        scene.add_polyline(&vertices)?;
+      }
+      "p" => {
+        let vertices = parse_vertex_indices(tokens, scene)?;
+        if vertices.is_empty() {
+          return Err(ParserError::InvalidSyntax("Point Vertices".to_string()));
+        }
+        scene.add_points(&vertices)?;
+      }
+      "s" => {
+        let group = match parse_token!(tokens.next(); String)?.as_str() {
+          "off" => 0,
+          group => parse_num!(group, u32),
+        };
+        scene.set_smoothing_group(group);
       }
       _ => {}
     };
@@ -96,11 +73,25 @@ impl ParseLine<Scene> for ObjParserImpl {
   }
 }
 
+/// the vertices of an `f`, `l` or `p` element
+fn parse_vertex_indices(
+  tokens: &mut std::str::SplitWhitespace,
+  scene: &Scene,
+) -> Result<Vec<VertexIndex>, ParserError> {
+  let counts = ObjCounts {
+    positions: scene.vertices.len(),
+    uvs: scene.texture_coordinates.len(),
+    normals: scene.normals.len(),
+  };
+  let vertices = parse_obj_vertices(tokens, counts).map_err(ParserError::InvalidSyntax)?;
+  Ok(vertices.into_iter().map(VertexIndex::from).collect())
+}
+
 pub type ObjParser<'a, 'b> = Parser<'a, 'b, Scene, ObjParserImpl>;
 
 pub fn load_obj(
   relative_path: &'static str,
-  name: &'static str,
+  name: &str,
 ) -> Result<ObjParser<'static, 'static>, ParserError> {
   let fullpath = std::path::Path::new(relative_path);
   let mut parser = ObjParser::new(fullpath);
@@ -109,3 +100,43 @@ pub fn load_obj(
   }
   parser
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(obj: &str) -> Scene {
+    let mut scene = Scene::new();
+    scene.add_model("test".to_string());
+    for line in obj.lines() {
+      let mut tokens = line.split_whitespace();
+      if let Some(s) = tokens.next() {
+        ObjParserImpl::parse_line(&mut scene, &mut tokens, "", s).unwrap();
+      }
+    }
+    scene
+  }
+
+  #[test]
+  fn lines_and_points() {
+    let scene = parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nl 1 2 3\np -1\nl -3 -1\n");
+    let model = &scene.models[0];
+    let positions = |vertices: &[VertexIndex]| -> Vec<u32> {
+      vertices.iter().map(|v| v.position_index).collect()
+    };
+
+    assert!(model.faces.is_empty());
+    assert_eq!(model.lines.len(), 2);
+    assert_eq!(positions(&model.lines[0]), [0, 1, 2]);
+    assert_eq!(positions(&model.lines[1]), [0, 2]);
+    assert_eq!(positions(&model.points), [2]);
+  }
+
+  #[test]
+  fn lines_need_two_vertices() {
+    let mut scene = Scene::new();
+    scene.add_model("test".to_string());
+    let mut tokens = "1".split_whitespace();
+    assert!(ObjParserImpl::parse_line(&mut scene, &mut tokens, "", "l").is_err());
+  }
+}
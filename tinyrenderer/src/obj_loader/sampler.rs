@@ -0,0 +1,367 @@
+use math::{lerp, Vec2, Vec4};
+use std::fmt::Debug;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FilterMode {
+  #[default]
+  Nearest,
+  /// bilinear within a level
+  Linear,
+}
+
+/// How the level of detail is picked from the uv derivatives.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MipmapMode {
+  /// always sample the base level
+  #[default]
+  None,
+  /// sample the closest level
+  Nearest,
+  /// blend the two closest levels, trilinear with `FilterMode::Linear`
+  Linear,
+}
+
+/// How the texel coordinates outside the image are folded back.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WrapMode {
+  #[default]
+  Repeat,
+  ClampToEdge,
+  MirroredRepeat,
+}
+
+impl WrapMode {
+  fn apply(&self, i: i32, size: i32) -> i32 {
+    match self {
+      WrapMode::Repeat => i.rem_euclid(size),
+      WrapMode::ClampToEdge => i.clamp(0, size - 1),
+      WrapMode::MirroredRepeat => {
+        let period = i.rem_euclid(size * 2);
+        if period >= size {
+          size * 2 - 1 - period
+        } else {
+          period
+        }
+      }
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Sampler {
+  pub filter: FilterMode,
+  pub mipmap: MipmapMode,
+  pub wrap_s: WrapMode,
+  pub wrap_t: WrapMode,
+}
+
+impl Sampler {
+  pub fn new(filter: FilterMode, mipmap: MipmapMode, wrap: WrapMode) -> Self {
+    Self {
+      filter,
+      mipmap,
+      wrap_s: wrap,
+      wrap_t: wrap,
+    }
+  }
+
+  pub fn nearest() -> Self {
+    Self::new(FilterMode::Nearest, MipmapMode::None, WrapMode::Repeat)
+  }
+
+  pub fn bilinear() -> Self {
+    Self::new(FilterMode::Linear, MipmapMode::None, WrapMode::Repeat)
+  }
+
+  pub fn trilinear() -> Self {
+    Self::new(FilterMode::Linear, MipmapMode::Linear, WrapMode::Repeat)
+  }
+}
+
+/// One level of the mip chain, texels are RGBA in [0, 1], row 0 at the top.
+#[derive(Default, Clone)]
+pub struct MipLevel {
+  width: u32,
+  height: u32,
+  texels: Vec<Vec4>,
+}
+
+impl Debug for MipLevel {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("MipLevel")
+      .field("width", &self.width)
+      .field("height", &self.height)
+      .finish()
+  }
+}
+
+impl MipLevel {
+  pub fn new(width: u32, height: u32, texels: Vec<Vec4>) -> Self {
+    Self {
+      width,
+      height,
+      texels,
+    }
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  fn texel(&self, x: i32, y: i32, sampler: &Sampler) -> Vec4 {
+    let x = sampler.wrap_s.apply(x, self.width as i32);
+    let y = sampler.wrap_t.apply(y, self.height as i32);
+    self.texels[(x + y * self.width as i32) as usize]
+  }
+
+  /// texel centres sit at half integers, v = 0 is the bottom row
+  pub fn sample(&self, uv: Vec2, sampler: &Sampler) -> Vec4 {
+    let x = uv.x * self.width as f32 - 0.5;
+    let y = (1.0 - uv.y) * self.height as f32 - 0.5;
+
+    match sampler.filter {
+      FilterMode::Nearest => self.texel(x.round() as i32, y.round() as i32, sampler),
+      FilterMode::Linear => {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        // the casts saturate far away from the image, so do the neighbours
+        let (x0, y0) = (x0 as i32, y0 as i32);
+        let (x1, y1) = (x0.saturating_add(1), y0.saturating_add(1));
+
+        let top = lerp(self.texel(x0, y0, sampler), self.texel(x1, y0, sampler), tx);
+        let bottom = lerp(self.texel(x0, y1, sampler), self.texel(x1, y1, sampler), tx);
+        lerp(top, bottom, ty)
+      }
+    }
+  }
+
+  /// 2x2 box filter, the odd last row / column is folded into its neighbour
+  pub fn downsample(&self) -> Self {
+    let width = (self.width / 2).max(1);
+    let height = (self.height / 2).max(1);
+    let at = |x: u32, y: u32| {
+      let x = x.min(self.width - 1);
+      let y = y.min(self.height - 1);
+      self.texels[(x + y * self.width) as usize]
+    };
+
+    let mut texels = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+      for x in 0..width {
+        let (sx, sy) = (x * 2, y * 2);
+        let sum = at(sx, sy) + at(sx + 1, sy) + at(sx, sy + 1) + at(sx + 1, sy + 1);
+        texels.push(sum / 4.0);
+      }
+    }
+
+    Self::new(width, height, texels)
+  }
+}
+
+/// Builds the whole chain down to 1x1 from the base level.
+pub fn build_mip_chain(base: MipLevel) -> Vec<MipLevel> {
+  let mut levels = vec![base];
+  loop {
+    let last = levels.last().unwrap();
+    if last.width == 1 && last.height == 1 {
+      break;
+    }
+    let next = last.downsample();
+    levels.push(next);
+  }
+  levels
+}
+
+/// Samples the mip chain, `derivatives` are the screen space derivatives of
+/// the uv (d/dx, d/dy), the level of detail falls back to 0 without them.
+pub fn sample_levels(
+  levels: &[MipLevel],
+  sampler: &Sampler,
+  uv: Vec2,
+  derivatives: Option<(Vec2, Vec2)>,
+) -> Vec4 {
+  let base = &levels[0];
+
+  let lod = match (sampler.mipmap, derivatives) {
+    (MipmapMode::None, _) | (_, None) => 0.0,
+    (_, Some((dx, dy))) => {
+      let size = Vec2::new(base.width as f32, base.height as f32);
+      let rho_x = Vec2::new(dx.x * size.x, dx.y * size.y).length();
+      let rho_y = Vec2::new(dy.x * size.x, dy.y * size.y).length();
+      rho_x.max(rho_y).max(f32::MIN_POSITIVE).log2()
+    }
+  };
+  let lod = lod.clamp(0.0, (levels.len() - 1) as f32);
+
+  match sampler.mipmap {
+    MipmapMode::None => base.sample(uv, sampler),
+    MipmapMode::Nearest => levels[lod.round() as usize].sample(uv, sampler),
+    MipmapMode::Linear => {
+      let lower = lod.floor() as usize;
+      let upper = (lower + 1).min(levels.len() - 1);
+      let t = lod - lower as f32;
+      lerp(
+        levels[lower].sample(uv, sampler),
+        levels[upper].sample(uv, sampler),
+        t,
+      )
+    }
+  }
+}
+
+/// 64x64 magenta / black checker of 8 pixel squares, shown in place of a
+/// missing image.
+pub fn missing_texture() -> MipLevel {
+  let size: u32 = 64;
+  let magenta = Vec4::new(1.0, 0.0, 1.0, 1.0);
+  let black = Vec4::new(0.0, 0.0, 0.0, 1.0);
+
+  let texels = (0..size * size)
+    .map(|i| {
+      let (x, y) = (i % size, i / size);
+      if (x / 8 + y / 8).is_multiple_of(2) {
+        magenta
+      } else {
+        black
+      }
+    })
+    .collect();
+
+  MipLevel::new(size, size, texels)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// a grey scale level, row 0 at the top
+  fn level(width: u32, height: u32, values: &[f32]) -> MipLevel {
+    let texels = values.iter().map(|v| Vec4::new(*v, *v, *v, 1.0)).collect();
+    MipLevel::new(width, height, texels)
+  }
+
+  fn bilinear(wrap: WrapMode) -> Sampler {
+    Sampler::new(FilterMode::Linear, MipmapMode::None, wrap)
+  }
+
+  #[track_caller]
+  fn assert_grey(color: Vec4, expected: f32) {
+    assert!(
+      (color.x - expected).abs() < 1e-5,
+      "{} != {}",
+      color.x,
+      expected
+    );
+  }
+
+  #[test]
+  fn wrap_modes_fold_the_texel_coordinates() {
+    let fold = |wrap: WrapMode| [-5, -1, 0, 3, 4, 5, 8].map(|i| wrap.apply(i, 4));
+    assert_eq!(fold(WrapMode::Repeat), [3, 3, 0, 3, 0, 1, 0]);
+    assert_eq!(fold(WrapMode::ClampToEdge), [0, 0, 0, 3, 3, 3, 3]);
+    assert_eq!(fold(WrapMode::MirroredRepeat), [3, 0, 0, 3, 3, 2, 0]);
+  }
+
+  #[test]
+  fn bilinear_blends_the_neighbours_across_the_edge() {
+    // black then white
+    let level = level(2, 1, &[0.0, 1.0]);
+    let sample = |u: f32, wrap| level.sample(Vec2::new(u, 0.5), &bilinear(wrap));
+
+    // between the two texel centres
+    assert_grey(sample(0.5, WrapMode::ClampToEdge), 0.5);
+    assert_grey(sample(0.375, WrapMode::ClampToEdge), 0.25);
+
+    // the left edge, halfway to the texel left of the image
+    assert_grey(sample(0.0, WrapMode::Repeat), 0.5);
+    assert_grey(sample(0.0, WrapMode::ClampToEdge), 0.0);
+    assert_grey(sample(0.0, WrapMode::MirroredRepeat), 0.0);
+    assert_grey(sample(1.0, WrapMode::Repeat), 0.5);
+    assert_grey(sample(1.0, WrapMode::ClampToEdge), 1.0);
+    assert_grey(sample(1.0, WrapMode::MirroredRepeat), 1.0);
+
+    // the second period, mirrored or not
+    assert_grey(sample(1.25, WrapMode::Repeat), 0.0);
+    assert_grey(sample(1.25, WrapMode::MirroredRepeat), 1.0);
+  }
+
+  #[test]
+  fn far_away_uvs_do_not_overflow() {
+    let level = level(2, 2, &[0.0, 1.0, 1.0, 0.0]);
+    for wrap in [
+      WrapMode::Repeat,
+      WrapMode::ClampToEdge,
+      WrapMode::MirroredRepeat,
+    ] {
+      for uv in [Vec2::new(1e10, 1e10), Vec2::new(-1e10, -1e10)] {
+        let c = level.sample(uv, &bilinear(wrap));
+        assert!((0.0..=1.0).contains(&c.x));
+      }
+    }
+  }
+
+  /// black on the left half, white on the right half, the levels are 4x4, 2x2
+  /// and a grey 1x1
+  fn halves() -> Vec<MipLevel> {
+    let row = [0.0, 0.0, 1.0, 1.0];
+    build_mip_chain(level(4, 4, &row.repeat(4)))
+  }
+
+  /// the derivatives of a footprint of `texels` texels of the base level
+  fn footprint(texels: f32) -> Option<(Vec2, Vec2)> {
+    Some((Vec2::new(texels / 4.0, 0.0), Vec2::new(0.0, texels / 4.0)))
+  }
+
+  #[test]
+  fn mip_chain_averages_down_to_one_texel() {
+    let levels = halves();
+    let sizes: Vec<_> = levels.iter().map(|l| (l.width(), l.height())).collect();
+    assert_eq!(sizes, [(4, 4), (2, 2), (1, 1)]);
+    assert_grey(levels[2].texels[0], 0.5);
+  }
+
+  #[test]
+  fn nearest_mipmap_picks_the_level_of_the_footprint() {
+    let levels = halves();
+    let sampler = Sampler::new(
+      FilterMode::Linear,
+      MipmapMode::Nearest,
+      WrapMode::ClampToEdge,
+    );
+    let sample = |derivatives| sample_levels(&levels, &sampler, Vec2::new(0.25, 0.5), derivatives);
+
+    assert_grey(sample(None), 0.0);
+    assert_grey(sample(footprint(1.0)), 0.0);
+    assert_grey(sample(footprint(2.0)), 0.0);
+    assert_grey(sample(footprint(4.0)), 0.5);
+    // clamped to the last level
+    assert_grey(sample(footprint(64.0)), 0.5);
+  }
+
+  #[test]
+  fn trilinear_blends_the_two_closest_levels() {
+    let levels = halves();
+    let sampler = Sampler {
+      wrap_s: WrapMode::ClampToEdge,
+      wrap_t: WrapMode::ClampToEdge,
+      ..Sampler::trilinear()
+    };
+    let sample = |derivatives| sample_levels(&levels, &sampler, Vec2::new(0.25, 0.5), derivatives);
+
+    // lod 1.5, halfway between the black of level 1 and the grey of level 2
+    assert_grey(sample(footprint(2f32.powf(1.5))), 0.25);
+    assert_grey(sample(footprint(4.0)), 0.5);
+    // no mipmapping, the base level only
+    let base = sample_levels(
+      &levels,
+      &bilinear(WrapMode::ClampToEdge),
+      Vec2::new(0.25, 0.5),
+      footprint(4.0),
+    );
+    assert_grey(base, 0.0);
+  }
+}
@@ -4,10 +4,10 @@ use std::{
   ops::{Add, Mul},
 };
 
-use crate::model::Vertex;
+use crate::{data_array::DepthBuffer, model::Vertex};
 use math::{Barycentric, Mat4, Vec2, Vec3, Vec4};
 
-use super::material::Textures;
+use super::material::{Texture, Textures};
 
 pub trait Extract<T> {
   fn extract(self) -> Option<T>;
@@ -130,6 +130,35 @@ impl Varyings {
 
     // self.data.insert(key.to_string(), val);
   }
+
+  /// Appends the values of `other`, the varyings of the next vertex.
+  pub fn append(&mut self, other: &Varyings) {
+    for (key, values) in &other.data {
+      self
+        .data
+        .entry(key.clone())
+        .or_default()
+        .extend_from_slice(values);
+    }
+  }
+
+  /// Re-interpolates the per vertex varyings for a triangle whose vertices are
+  /// blended from the source triangle with the given barycentric `weights`,
+  /// e.g. the triangles produced by clipping. Flat values are copied as is.
+  pub fn blend(&self, weights: &[Vec3; 3]) -> Self {
+    let mut result = Self::new();
+    for (key, vec) in &self.data {
+      let values = match vec.len() {
+        3 => weights
+          .iter()
+          .map(|w| vec[0] * w.x + vec[1] * w.y + vec[2] * w.z)
+          .collect(),
+        _ => vec.clone(),
+      };
+      result.data.insert(key.clone(), values);
+    }
+    result
+  }
 }
 
 pub type GlTypeMap = HashMap<String, GLTypes>;
@@ -137,6 +166,7 @@ pub type GlTypeMap = HashMap<String, GLTypes>;
 #[derive(Debug, Default)]
 pub struct Varying {
   data: GlTypeMap,
+  uv_derivatives: Option<(Vec2, Vec2)>,
 }
 
 impl Varying {
@@ -144,6 +174,12 @@ impl Varying {
     self.data.insert(key.to_string(), gl_values);
   }
 
+  /// Screen space derivatives (d/dx, d/dy) of the texture coordinate of the
+  /// vertices, the `dFdx`/`dFdy` of GLSL, used to pick the mipmap level.
+  pub fn get_uv_derivatives(&self) -> Option<(Vec2, Vec2)> {
+    self.uv_derivatives
+  }
+
   pub fn get(&self, key: &str) -> Option<GLTypes> {
     self.data.get(key).map(take_value)
   }
@@ -156,11 +192,37 @@ pub fn take_value<T: Copy>(v: &T) -> T {
 pub struct Uniform<'a> {
   global: &'a GlTypeMap,
   data: GlTypeMap,
+  samplers: HashMap<String, &'a Texture>,
+  shadow_map: Option<&'a DepthBuffer>,
 }
 
 impl<'a> Uniform<'a> {
   pub fn new(global: &'a GlTypeMap, data: GlTypeMap) -> Self {
-    Self { global, data }
+    Self {
+      global,
+      data,
+      samplers: HashMap::new(),
+      shadow_map: None,
+    }
+  }
+
+  /// textures bound by the renderer from the material of the model, named
+  /// after the `TextureMap` fields, e.g. "diffuse", "norm", "specular_color"
+  pub fn get_sampler(&self, name: &str) -> Option<&'a Texture> {
+    self.samplers.get(name).copied()
+  }
+
+  pub fn set_sampler(&mut self, name: &str, texture: &'a Texture) {
+    self.samplers.insert(name.to_string(), texture);
+  }
+
+  /// the depth map rendered by the shadow pass, if enabled
+  pub fn get_shadow_map(&self) -> Option<&'a DepthBuffer> {
+    self.shadow_map
+  }
+
+  pub fn set_shadow_map(&mut self, shadow_map: &'a DepthBuffer) {
+    self.shadow_map = Some(shadow_map);
   }
 
   pub fn get(&self, key: &str) -> Option<GLTypes> {
@@ -178,12 +240,17 @@ impl<'a> Uniform<'a> {
   }
 }
 
-type VertexShader = Box<dyn Fn(&Vertex, &Uniform, &mut Varyings) -> Vertex>;
-type FragmentShader = Box<dyn Fn(&Uniform, &Varying, &Textures) -> Vec4>;
+// `Send + Sync` as the tiles of a frame are shaded from several threads
+type VertexShader = Box<dyn Fn(&Vertex, &Uniform, &mut Varyings) -> Vertex + Send + Sync>;
+type FragmentShader = Box<dyn Fn(&Uniform, &Varying, &Textures) -> Vec4 + Send + Sync>;
 
 pub struct Shader {
   pub vertex: VertexShader,
   pub fragment: FragmentShader,
+  /// The vertex shader reads the `vertex_index` uniform, the corner of the
+  /// triangle being shaded, so its results are cached per corner rather than
+  /// per vertex of the index buffer.
+  pub per_corner: bool,
 }
 
 impl Debug for Shader {
@@ -191,6 +258,7 @@ impl Debug for Shader {
     f.debug_struct("Shader")
       .field("vertex", &"/** vertex clousure */".to_string())
       .field("fragment", &"/** fragment clousure */".to_string())
+      .field("per_corner", &self.per_corner)
       .finish()
   }
 }
@@ -199,6 +267,7 @@ impl Default for Shader {
     Self {
       vertex: Self::default_vertex(),
       fragment: Self::default_fragment(),
+      per_corner: false,
     }
   }
 }
@@ -237,12 +306,14 @@ impl Shader {
     uniforms: &Uniform,
     varyings: &Varyings,
     textures: &Textures,
+    uv_derivatives: Option<(Vec2, Vec2)>,
   ) -> Vec4 {
     let rhws = vertices.map(|v| v.rhw);
     let inv_z = bar.apply_weight(&vertices.map(|v| v.rhw));
     let z = 1.0 / inv_z;
 
-    let varying = self.lerp_varyings(bar, varyings, rhws, z);
+    let mut varying = self.lerp_varyings(bar, varyings, rhws, z);
+    varying.uv_derivatives = uv_derivatives;
 
     (self.fragment)(uniforms, &varying, textures)
   }
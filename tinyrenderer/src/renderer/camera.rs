@@ -1,10 +1,20 @@
 use math::{apply_eular_rotate_xyz, apply_translate, Mat4, Vec3, Vec4};
 
+/// The shape of the view volume.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+  /// `fov` is the angle between the view direction and the left & right planes
+  Perspective { fov: f32 },
+  /// a box reaching `half_width` on the left & right of the view direction,
+  /// the height follows the aspect
+  Orthographic { half_width: f32 },
+}
+
 pub struct Frustum {
   near: f32,
   far: f32,
   aspect: f32,
-  fov: f32,
+  projection: Projection,
   mat: Mat4,
 }
 
@@ -19,7 +29,7 @@ impl Frustum {
       near,
       far,
       aspect,
-      fov,
+      projection: Projection::Perspective { fov },
       mat: if cfg!(feature="cpu") {
         let a = 1.0 / (near * fov.tan());
         // without far plane, clamp x,y in [-1, 1], z = near
@@ -40,11 +50,57 @@ impl Frustum {
     }
   }
 
+  /// The parallel projection of the box `[-half_width, half_width]` x
+  /// `[-half_width / aspect, half_width / aspect]` x `[-far, -near]` in view
+  /// space, mapped to the same clip space as the perspective one.
+  #[rustfmt::skip]
+  pub fn orthographic(near: f32, far: f32, aspect: f32, half_width: f32) -> Self {
+    let half_h = half_width / aspect;
+    let near = near.abs();
+    let far = far.abs();
+    Self {
+      near,
+      far,
+      aspect,
+      projection: Projection::Orthographic { half_width },
+      mat: Mat4::from_row([
+        1.0 / half_width, 0.0,          0.0,                0.0,
+        0.0,              1.0 / half_h, 0.0,                0.0,
+        0.0,              0.0,          2.0 / (near - far), (far + near) / (near - far),
+        0.0,              0.0,          0.0,                1.0,
+      ]),
+    }
+  }
+
+  pub fn get_projection(&self) -> Projection {
+    self.projection
+  }
+
   pub fn get_projection_matrix(&self) -> &Mat4 {
     &self.mat
   }
+
+  /// the distance along the view direction of a point at the NDC depth `ndc_z`
+  pub fn linear_depth(&self, ndc_z: f32) -> f32 {
+    let (n, f) = (self.near, self.far);
+    match self.projection {
+      Projection::Perspective { .. } => 2.0 * f * n / ((f + n) - ndc_z * (f - n)),
+      Projection::Orthographic { .. } => (ndc_z * (f - n) + (f + n)) / 2.0,
+    }
+  }
+
   pub fn contains(&self, pt: &Vec3) -> bool {
-    let half_width = self.near * self.fov.tan();
+    let fov = match self.projection {
+      Projection::Perspective { fov } => fov,
+      Projection::Orthographic { half_width } => {
+        let half_height = half_width / self.aspect;
+        return pt.x.abs() <= half_width
+          && pt.y.abs() <= half_height
+          && pt.z < -self.near
+          && pt.z > -self.far;
+      }
+    };
+    let half_width = self.near * fov.tan();
     let half_height = half_width / self.aspect;
     // let h_fovy_cos = self.fov.cos();
     // let h_fovy_sin = self.fov.sin();
@@ -89,6 +145,15 @@ impl Camera {
     self.frustum = Frustum::new(near, far, self.frustum.aspect, fov);
   }
 
+  /// switches to a parallel projection, see `Frustum::orthographic`
+  pub fn update_orthographic(&mut self, near: f32, far: f32, half_width: f32) {
+    self.frustum = Frustum::orthographic(near, far, self.frustum.aspect, half_width);
+  }
+
+  pub fn get_position(&self) -> Vec3 {
+    self.position
+  }
+
   fn compute_view_matrix(&mut self) {
     let rotation = apply_eular_rotate_xyz(&self.rotation);
     //SRT
@@ -118,8 +183,8 @@ impl Camera {
     let back = (self.position - point).normalize();
     self.view_direction =   back * -1.0;
 
-    // 0,1,0
-    let up = Vec3::y_axis();
+    // 0,1,0, or 0,0,1 to avoid a degenerated basis when looking straight up or down
+    let up = if back.dot(Vec3::y_axis()).abs() > 0.999 { Vec3::z_axis() } else { Vec3::y_axis() };
     //  up x back = right basis
     let right = up.cross(&back).normalize();
     //  back x right= top basis
@@ -163,3 +228,41 @@ impl Camera {
     &self.frustum
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn orthographic_maps_the_box_to_ndc() {
+    let frustum = Frustum::orthographic(1.0, 9.0, 2.0, 4.0);
+    let ndc = |x: f32, y: f32, z: f32| {
+      let clip = *frustum.get_projection_matrix() * Vec4::new(x, y, z, 1.0);
+      assert_eq!(clip.w, 1.0);
+      clip.truncated_to_vec3()
+    };
+
+    assert_eq!(ndc(-4.0, -2.0, -1.0), Vec3::new(-1.0, -1.0, -1.0));
+    assert_eq!(ndc(4.0, 2.0, -9.0), Vec3::new(1.0, 1.0, 1.0));
+    assert_eq!(ndc(2.0, 1.0, -5.0), Vec3::new(0.5, 0.5, 0.0));
+
+    assert!(frustum.contains(&Vec3::new(3.9, -1.9, -8.0)));
+    assert!(!frustum.contains(&Vec3::new(0.0, 2.1, -5.0)));
+    assert!(!frustum.contains(&Vec3::new(0.0, 0.0, -9.5)));
+  }
+
+  #[test]
+  fn linear_depth_undoes_the_projection() {
+    let frustums = [
+      Frustum::orthographic(1.0, 9.0, 2.0, 4.0),
+      Frustum::new(1.0, 9.0, 2.0, 0.3),
+    ];
+    for frustum in frustums {
+      for distance in [1.0, 2.5, 5.0, 9.0] {
+        let clip = *frustum.get_projection_matrix() * Vec4::new(0.0, 0.0, -distance, 1.0);
+        let depth = frustum.linear_depth(clip.z / clip.w);
+        assert!((depth - distance).abs() < 1e-4, "{} != {}", depth, distance);
+      }
+    }
+  }
+}
@@ -0,0 +1,41 @@
+use crate::model::Vertex;
+use math::Vec4;
+
+pub use math::clip::{clip_triangle, triangulate, ClipResult, ClipVertex};
+
+/// Builds a vertex of the clipped polygon from the three shaded vertices
+/// of the source triangle.
+pub fn blend_vertex(vertices: &[Vertex; 3], clip_vertex: &ClipVertex) -> Vertex {
+  let w = [
+    clip_vertex.weights.x,
+    clip_vertex.weights.y,
+    clip_vertex.weights.z,
+  ];
+
+  let normal = match vertices.map(|v| v.normal) {
+    [Some(n0), Some(n1), Some(n2)] => Some(n0 * w[0] + n1 * w[1] + n2 * w[2]),
+    _ => None,
+  };
+
+  let texture = match vertices.map(|v| v.texture) {
+    [Some(t0), Some(t1), Some(t2)] => Some(t0 * w[0] + t1 * w[1] + t2 * w[2]),
+    _ => None,
+  };
+
+  // the sign of the bitangent is the same over the triangle
+  let tangent = match vertices.map(|v| v.tangent) {
+    [Some(t0), Some(t1), Some(t2)] => {
+      let t = t0 * w[0] + t1 * w[1] + t2 * w[2];
+      Some(Vec4::new(t.x, t.y, t.z, t0.w))
+    }
+    _ => None,
+  };
+
+  Vertex {
+    position: clip_vertex.position,
+    normal,
+    texture,
+    tangent,
+    rhw: 1.0,
+  }
+}
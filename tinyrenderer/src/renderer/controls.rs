@@ -0,0 +1,254 @@
+//! Camera controllers driven by abstract input deltas, e.g. the pixels of a
+//! mouse drag or the steps of a wheel, so that any windowing toolkit can feed
+//! them. `update` writes the result into a `Camera`.
+
+use std::f32::consts::FRAC_PI_2;
+
+use math::Vec3;
+
+use super::camera::Camera;
+
+/// Keeps the camera off the poles, past them the view turns upside down and at
+/// them `Camera::lookat` swaps its up vector, which rolls the view.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+/// Orbits around a target on a sphere, `azimuth` turning around the y axis
+/// from +z and `elevation` towards +y.
+#[derive(Debug, Clone)]
+pub struct OrbitController {
+  pub azimuth: f32,
+  pub elevation: f32,
+  pub distance: f32,
+  pub target: Vec3,
+  /// radians per unit of `rotate`
+  pub rotate_speed: f32,
+  /// world units per unit of `zoom`
+  pub zoom_speed: f32,
+  pub min_distance: f32,
+}
+
+impl OrbitController {
+  /// starts from the camera at `eye` looking at `target`
+  pub fn new(eye: Vec3, target: Vec3) -> Self {
+    let offset = eye - target;
+    let distance = offset.length();
+    let elevation = if distance > 0.0 {
+      (offset.y / distance).clamp(-1.0, 1.0).asin()
+    } else {
+      0.0
+    };
+
+    Self {
+      azimuth: offset.x.atan2(offset.z),
+      elevation: elevation.clamp(-MAX_PITCH, MAX_PITCH),
+      distance,
+      target,
+      rotate_speed: 0.005,
+      zoom_speed: 0.5,
+      min_distance: 0.5,
+    }
+  }
+
+  /// Dragging to the right turns the camera to the left around the target,
+  /// dragging down moves it up, as if grabbing the model.
+  pub fn rotate(&mut self, dx: f32, dy: f32) {
+    self.azimuth -= dx * self.rotate_speed;
+    self.elevation = (self.elevation + dy * self.rotate_speed).clamp(-MAX_PITCH, MAX_PITCH);
+  }
+
+  /// positive steps move closer to the target
+  pub fn zoom(&mut self, steps: f32) {
+    self.distance = (self.distance - steps * self.zoom_speed).max(self.min_distance);
+  }
+
+  /// Moves the target, and the camera with it, in the view plane. The deltas
+  /// are in world units.
+  pub fn pan(&mut self, dx: f32, dy: f32) {
+    let back = (self.get_position() - self.target).normalize();
+    let right = Vec3::y_axis().cross(&back).normalize();
+    let up = back.cross(&right);
+    self.target += right * dx + up * dy;
+  }
+
+  pub fn get_position(&self) -> Vec3 {
+    let (sin_e, cos_e) = self.elevation.sin_cos();
+    let (sin_a, cos_a) = self.azimuth.sin_cos();
+    self.target + Vec3::new(cos_e * sin_a, sin_e, cos_e * cos_a) * self.distance
+  }
+
+  pub fn update(&self, camera: &mut Camera) {
+    camera.move_to(self.get_position());
+    camera.lookat(self.target);
+  }
+}
+
+/// First person camera, `yaw` turning around the y axis from -z and `pitch`
+/// looking up.
+#[derive(Debug, Clone)]
+pub struct FlyController {
+  pub position: Vec3,
+  pub yaw: f32,
+  pub pitch: f32,
+  /// radians per unit of `look`
+  pub look_speed: f32,
+  /// world units per unit of `translate`
+  pub move_speed: f32,
+}
+
+impl FlyController {
+  /// starts from the camera at `eye` looking at `target`
+  pub fn new(eye: Vec3, target: Vec3) -> Self {
+    let forward = target - eye;
+    let length = forward.length();
+    let pitch = if length > 0.0 {
+      (forward.y / length).clamp(-1.0, 1.0).asin()
+    } else {
+      0.0
+    };
+
+    Self {
+      position: eye,
+      yaw: (-forward.x).atan2(-forward.z),
+      pitch: pitch.clamp(-MAX_PITCH, MAX_PITCH),
+      look_speed: 0.005,
+      move_speed: 0.1,
+    }
+  }
+
+  /// moving the mouse to the right turns right, moving it down looks down
+  pub fn look(&mut self, dx: f32, dy: f32) {
+    self.yaw -= dx * self.look_speed;
+    self.pitch = (self.pitch - dy * self.look_speed).clamp(-MAX_PITCH, MAX_PITCH);
+  }
+
+  /// Moves along the view direction, to the right, and up the world y axis.
+  pub fn translate(&mut self, forward: f32, right: f32, up: f32) {
+    let direction = self.get_direction();
+    let side = direction.cross(Vec3::y_axis()).normalize();
+    self.position += (direction * forward + side * right + *Vec3::y_axis() * up) * self.move_speed;
+  }
+
+  pub fn get_direction(&self) -> Vec3 {
+    let (sin_p, cos_p) = self.pitch.sin_cos();
+    let (sin_y, cos_y) = self.yaw.sin_cos();
+    Vec3::new(-sin_y * cos_p, sin_p, -cos_y * cos_p)
+  }
+
+  pub fn update(&self, camera: &mut Camera) {
+    camera.move_to(self.position);
+    camera.lookat(self.position + self.get_direction());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use math::Vec4;
+
+  #[track_caller]
+  fn assert_vec3(actual: Vec3, expected: Vec3) {
+    assert!(
+      (actual - expected).length() < 1e-5,
+      "{:?} != {:?}",
+      actual,
+      expected
+    );
+  }
+
+  /// `point` in the view space of the camera
+  fn in_view(camera: &Camera, point: Vec3) -> Vec3 {
+    (*camera.get_view_matarix() * Vec4::from_vec3(&point, 1.0)).truncated_to_vec3()
+  }
+
+  #[test]
+  fn orbit_starts_from_the_eye() {
+    let orbit = OrbitController::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zero());
+    assert_eq!(
+      (orbit.azimuth, orbit.elevation, orbit.distance),
+      (0.0, 0.0, 5.0)
+    );
+
+    let (eye, target) = (Vec3::new(1.0, 2.0, 3.0), Vec3::new(0.5, 0.0, -1.0));
+    let orbit = OrbitController::new(eye, target);
+    assert_vec3(orbit.get_position(), eye);
+
+    let mut camera = Camera::new(1.0, 1.0);
+    orbit.update(&mut camera);
+    assert_vec3(
+      in_view(&camera, target),
+      Vec3::new(0.0, 0.0, -orbit.distance),
+    );
+  }
+
+  #[test]
+  fn orbit_rotates_around_the_target() {
+    let target = Vec3::new(1.0, 0.0, 0.0);
+    let mut orbit = OrbitController::new(Vec3::new(1.0, 0.0, 2.0), target);
+
+    // a quarter turn to the left, from +z to -x
+    orbit.rotate(FRAC_PI_2 / orbit.rotate_speed, 0.0);
+    assert_vec3(orbit.get_position(), Vec3::new(-1.0, 0.0, 0.0));
+
+    // stops short of the pole
+    orbit.rotate(0.0, 1e6);
+    assert_eq!(orbit.elevation, MAX_PITCH);
+    assert!((orbit.get_position() - target).length() - 2.0 < 1e-5);
+    orbit.rotate(0.0, -1e6);
+    assert_eq!(orbit.elevation, -MAX_PITCH);
+  }
+
+  #[test]
+  fn orbit_zooms_and_pans() {
+    let mut orbit = OrbitController::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zero());
+
+    orbit.zoom(2.0);
+    assert_eq!(orbit.distance, 4.0);
+    orbit.zoom(100.0);
+    assert_eq!(orbit.distance, orbit.min_distance);
+
+    // the view plane is the xy plane, the camera follows the target
+    orbit.pan(1.0, 2.0);
+    assert_vec3(orbit.target, Vec3::new(1.0, 2.0, 0.0));
+    assert_vec3(
+      orbit.get_position(),
+      Vec3::new(1.0, 2.0, orbit.min_distance),
+    );
+  }
+
+  #[test]
+  fn fly_starts_from_the_eye() {
+    let fly = FlyController::new(Vec3::zero(), Vec3::new(0.0, 0.0, -1.0));
+    assert_eq!((fly.yaw, fly.pitch), (0.0, 0.0));
+
+    let (eye, target) = (Vec3::new(1.0, 2.0, 3.0), Vec3::new(-2.0, 0.0, 1.0));
+    let fly = FlyController::new(eye, target);
+    assert_vec3(fly.get_direction(), (target - eye).normalize());
+
+    let mut camera = Camera::new(1.0, 1.0);
+    fly.update(&mut camera);
+    assert_vec3(
+      in_view(&camera, eye + fly.get_direction()),
+      Vec3::new(0.0, 0.0, -1.0),
+    );
+  }
+
+  #[test]
+  fn fly_looks_and_moves() {
+    let mut fly = FlyController::new(Vec3::zero(), Vec3::new(0.0, 0.0, -1.0));
+
+    // a quarter turn to the right, from -z to +x
+    fly.look(FRAC_PI_2 / fly.look_speed, 0.0);
+    assert_vec3(fly.get_direction(), Vec3::new(1.0, 0.0, 0.0));
+
+    fly.translate(10.0, 0.0, 0.0);
+    assert_vec3(fly.position, Vec3::new(1.0, 0.0, 0.0));
+    // the right of +x is +z
+    fly.translate(0.0, 10.0, 10.0);
+    assert_vec3(fly.position, Vec3::new(1.0, 1.0, 1.0));
+
+    fly.look(0.0, 1e6);
+    assert_eq!(fly.pitch, -MAX_PITCH);
+    fly.look(0.0, -1e6);
+    assert_eq!(fly.pitch, MAX_PITCH);
+  }
+}
@@ -0,0 +1,195 @@
+//! Render modes showing data about the frame in place of the colors of the
+//! materials. The fragments are colored from the attributes of the vertices
+//! returned by the vertex shader, the fragment shader does not run, so that
+//! they work with any material.
+
+use math::{
+  color::srgb_to_linear_color, multisample::SampleBuffer, Barycentric, Mat4, Vec2, Vec3, Vec4,
+};
+
+use super::camera::Frustum;
+use crate::{
+  data_array::{DepthBuffer, HdrColorBuffer},
+  model::Vertex,
+  obj_loader::shader::{uniform, Extract, Uniform},
+};
+
+/// Selected with `Renderer::set_debug_view`, the views are drawn over black
+/// and are neither tone mapped nor blended.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DebugView {
+  /// the colors of the materials
+  #[default]
+  None,
+  /// the linearized depth buffer, from white at the nearest surface to dark
+  /// grey at the farthest one
+  Depth,
+  /// the view space normals of the vertices, mapped from [-1, 1] to [0, 1]
+  Normals,
+  /// a checkerboard of 8 x 8 squares per uv tile, tinted by the uv, magenta
+  /// without them
+  UvChecker,
+  /// The fragments per pixel as a heat map, from blue for one fragment to
+  /// white for eight or more. Every fragment is counted, the hidden ones too.
+  Overdraw,
+  /// a random color per triangle of the model
+  TriangleId,
+  /// a random color per model of the scene
+  ModelId,
+}
+
+/// the color of a fragment, `None` with `DebugView::None`
+pub(super) fn fragment_color(
+  view: DebugView,
+  vertices: &[Vertex; 3],
+  bar: &Barycentric,
+  uniforms: &Uniform,
+  triangle_id: usize,
+  model_id: usize,
+) -> Option<Vec4> {
+  let color = match view {
+    DebugView::None => return None,
+    // filled in by `resolve`
+    DebugView::Depth => Vec3::zero(),
+    // added up by the blending
+    DebugView::Overdraw => return Some(Vec4::new(1.0, 0.0, 0.0, 0.0)),
+    DebugView::Normals => match vertices.map(|v| v.normal) {
+      [Some(n0), Some(n1), Some(n2)] => {
+        let mv_it = uniform!(uniforms, Mat4, "mv_it", !);
+        let n = perspective_lerp(vertices, bar, &[n0, n1, n2]);
+        let n = (mv_it * Vec4::from_vec3(&n, 0.0)).truncated_to_vec3();
+        n.normalize() * 0.5 + 0.5
+      }
+      _ => Vec3::zero(),
+    },
+    DebugView::UvChecker => match vertices.map(|v| v.texture) {
+      [Some(uv0), Some(uv1), Some(uv2)] => {
+        checker(perspective_lerp(vertices, bar, &[uv0, uv1, uv2]))
+      }
+      _ => Vec3::new(1.0, 0.0, 1.0),
+    },
+    DebugView::TriangleId => id_color(triangle_id),
+    DebugView::ModelId => id_color(model_id),
+  };
+
+  Some(Vec4::from_vec3(&color, 1.0))
+}
+
+/// `Σ(b·v·rhw) / Σ(b·rhw)`, as the shaders interpolate their varyings
+fn perspective_lerp<T>(vertices: &[Vertex; 3], bar: &Barycentric, values: &[T; 3]) -> T
+where
+  T: std::ops::Mul<f32, Output = T> + std::ops::Add<T, Output = T> + Copy,
+{
+  let rhws = vertices.map(|v| v.rhw);
+  let z = 1.0 / bar.apply_weight(&rhws);
+  bar.apply_weight(&[
+    values[0] * rhws[0],
+    values[1] * rhws[1],
+    values[2] * rhws[2],
+  ]) * z
+}
+
+fn checker(uv: Vec2) -> Vec3 {
+  let (u, v) = (uv.x.rem_euclid(1.0), uv.y.rem_euclid(1.0));
+  let square = ((u * 8.0).floor() + (v * 8.0).floor()) as i32;
+  let shade = if square % 2 == 0 { 1.0 } else { 0.35 };
+  Vec3::new(0.4 + 0.6 * u, 0.4 + 0.6 * v, 0.7) * shade
+}
+
+/// a stable color for an index, hashed to keep the neighbours apart
+fn id_color(id: usize) -> Vec3 {
+  // the finalizer of MurmurHash3
+  let mut h = id as u32 ^ 0x9e37_79b9;
+  h ^= h >> 16;
+  h = h.wrapping_mul(0x85eb_ca6b);
+  h ^= h >> 13;
+  h = h.wrapping_mul(0xc2b2_ae35);
+  h ^= h >> 16;
+
+  let channel = |shift: u32| 0.15 + 0.85 * ((h >> shift) & 0xff) as f32 / 255.0;
+  Vec3::new(channel(0), channel(8), channel(16))
+}
+
+/// from black through blue, cyan, green, yellow and red up to white
+fn heat(count: f32) -> Vec3 {
+  let stops = [
+    (0.0, Vec3::new(0.0, 0.0, 0.0)),
+    (1.0, Vec3::new(0.0, 0.0, 1.0)),
+    (2.0, Vec3::new(0.0, 1.0, 1.0)),
+    (3.0, Vec3::new(0.0, 1.0, 0.0)),
+    (4.0, Vec3::new(1.0, 1.0, 0.0)),
+    (6.0, Vec3::new(1.0, 0.0, 0.0)),
+    (8.0, Vec3::new(1.0, 1.0, 1.0)),
+  ];
+
+  for pair in stops.windows(2) {
+    let ((c0, color0), (c1, color1)) = (pair[0], pair[1]);
+    if count < c1 {
+      let t = ((count - c0) / (c1 - c0)).max(0.0);
+      return color0 * (1.0 - t) + color1 * t;
+    }
+  }
+  stops[stops.len() - 1].1
+}
+
+/// keeps the nearest sample of every pixel, for the depth view of the
+/// antialiased modes
+pub(super) fn resolve_depth(samples: &mut SampleBuffer, depth: &mut DepthBuffer) {
+  let count = samples.samples();
+  let rows = samples.as_rows_mut();
+  for y in 0..depth.height() {
+    for x in 0..depth.width() {
+      let z = (0..count)
+        .map(|sample| rows.get_depth(x, y, sample))
+        .fold(f32::MAX, f32::min);
+      depth.set(x, y, z);
+    }
+  }
+}
+
+/// Finishes the frame of a debug view, the depth and the overdraw views are
+/// computed over the whole frame. The colors are stored linear, so that the
+/// sRGB encoding gives them back as they were computed.
+pub(super) fn resolve(
+  view: DebugView,
+  frustum: &Frustum,
+  color: &mut HdrColorBuffer,
+  depth: &DepthBuffer,
+) {
+  let (w, h) = (color.width(), color.height());
+  let pixels = || (0..h).flat_map(move |y| (0..w).map(move |x| (x, y)));
+
+  match view {
+    DebugView::None => return,
+    DebugView::Depth => {
+      // the z buffer holds the NDC depth remapped to [0, 1]
+      let distance = |z: f32| (z < f32::MAX).then(|| frustum.linear_depth(z * 2.0 - 1.0));
+      let (near, far) = pixels()
+        .filter_map(|(x, y)| distance(depth.get(x, y)))
+        .fold((f32::MAX, f32::MIN), |(n, f), d| (n.min(d), f.max(d)));
+
+      for (x, y) in pixels() {
+        let value = distance(depth.get(x, y)).map_or(0.0, |d| {
+          let t = if far > near {
+            (d - near) / (far - near)
+          } else {
+            0.0
+          };
+          1.0 - 0.8 * t
+        });
+        color.set(x, y, &Vec4::new(value, value, value, 1.0));
+      }
+    }
+    DebugView::Overdraw => {
+      for (x, y) in pixels() {
+        let count = color.get(x, y).x;
+        color.set(x, y, &Vec4::from_vec3(&heat(count), 1.0));
+      }
+    }
+    _ => {}
+  }
+
+  for (x, y) in pixels() {
+    color.set(x, y, &srgb_to_linear_color(&color.get(x, y)));
+  }
+}
@@ -0,0 +1,398 @@
+//! Punctual lights, every one of them lights a point from a single direction.
+//!
+//! The renderer uploads its `lights` as the `light_count` uniform and one
+//! `Mat4` per light under `lights[i]`, the shaders read them back with
+//! `lights`, so that moving a light does not need a new shader.
+
+use math::{Mat4, Vec3, Vec4};
+
+use crate::obj_loader::shader::{uniform, Extract, GLTypes, GlTypeMap, Uniform};
+
+/// the lights after the first `MAX_LIGHTS` are not uploaded
+pub const MAX_LIGHTS: usize = 8;
+
+const LIGHT_KEYS: [&str; MAX_LIGHTS] = [
+  "lights[0]",
+  "lights[1]",
+  "lights[2]",
+  "lights[3]",
+  "lights[4]",
+  "lights[5]",
+  "lights[6]",
+  "lights[7]",
+];
+
+#[derive(Debug, Clone, Copy)]
+pub enum Light {
+  /// shines along `-direction` from infinitely far away, like the `light_dir`
+  /// of the shadow map
+  Directional {
+    direction: Vec3,
+    color: Vec3,
+    intensity: f32,
+  },
+  /// Shines from `position` in all the directions, falling off with the
+  /// square of the distance. The falloff is windowed to reach zero at `range`,
+  /// a zero `range` never cuts it.
+  Point {
+    position: Vec3,
+    color: Vec3,
+    intensity: f32,
+    range: f32,
+  },
+  /// A point light restricted to a cone around `direction`, the axis it shines
+  /// along. The light fades out between the inner and the outer half angles,
+  /// in radians.
+  Spot {
+    position: Vec3,
+    direction: Vec3,
+    color: Vec3,
+    intensity: f32,
+    range: f32,
+    inner_angle: f32,
+    outer_angle: f32,
+  },
+}
+
+impl Light {
+  pub fn directional(direction: Vec3, color: Vec3, intensity: f32) -> Self {
+    Self::Directional {
+      direction,
+      color,
+      intensity,
+    }
+  }
+
+  pub fn point(position: Vec3, color: Vec3, intensity: f32) -> Self {
+    Self::Point {
+      position,
+      color,
+      intensity,
+      range: 0.0,
+    }
+  }
+
+  pub fn spot(
+    position: Vec3,
+    direction: Vec3,
+    color: Vec3,
+    intensity: f32,
+    inner_angle: f32,
+    outer_angle: f32,
+  ) -> Self {
+    Self::Spot {
+      position,
+      direction,
+      color,
+      intensity,
+      range: 0.0,
+      inner_angle,
+      outer_angle,
+    }
+  }
+
+  /// limits the point and the spot lights to `range`
+  pub fn with_range(mut self, new_range: f32) -> Self {
+    match &mut self {
+      Light::Point { range, .. } | Light::Spot { range, .. } => *range = new_range,
+      Light::Directional { .. } => {}
+    }
+    self
+  }
+
+  /// The unit vector from `position` towards the light, and the radiance
+  /// arriving there. `position` is in the space of the light.
+  pub fn incident(&self, position: &Vec3) -> (Vec3, Vec3) {
+    match self {
+      Light::Directional {
+        direction,
+        color,
+        intensity,
+      } => (direction.normalize(), *color * *intensity),
+      Light::Point {
+        position: light_position,
+        color,
+        intensity,
+        range,
+      } => {
+        let (l, attenuation) = attenuate(*light_position - *position, *range);
+        (l, *color * (*intensity * attenuation))
+      }
+      Light::Spot {
+        position: light_position,
+        direction,
+        color,
+        intensity,
+        range,
+        inner_angle,
+        outer_angle,
+      } => {
+        let (l, attenuation) = attenuate(*light_position - *position, *range);
+        let cone = smoothstep(
+          outer_angle.cos(),
+          inner_angle.cos(),
+          -l.dot(&direction.normalize()),
+        );
+        (l, *color * (*intensity * attenuation * cone))
+      }
+    }
+  }
+
+  /// moves the light into another space, e.g. the view space with the view
+  /// matrix, which is expected to be rigid
+  pub fn transform(&self, matrix: &Mat4) -> Self {
+    let point = |p: &Vec3| (*matrix * Vec4::from_vec3(p, 1.0)).truncated_to_vec3();
+    let vector = |v: &Vec3| (*matrix * Vec4::from_vec3(v, 0.0)).truncated_to_vec3();
+
+    let mut light = *self;
+    match &mut light {
+      Light::Directional { direction, .. } => *direction = vector(direction),
+      Light::Point { position, .. } => *position = point(position),
+      Light::Spot {
+        position,
+        direction,
+        ..
+      } => {
+        *position = point(position);
+        *direction = vector(direction);
+      }
+    }
+    light
+  }
+
+  /// The rows hold the position & the kind, the direction & the range, the
+  /// radiant color, and the cone angles.
+  fn to_uniform(self) -> Mat4 {
+    let (kind, position, direction, color, intensity, range, cone) = match self {
+      Light::Directional {
+        direction,
+        color,
+        intensity,
+      } => (
+        0.0,
+        Vec3::zero(),
+        direction,
+        color,
+        intensity,
+        0.0,
+        (0.0, 0.0),
+      ),
+      Light::Point {
+        position,
+        color,
+        intensity,
+        range,
+      } => (
+        1.0,
+        position,
+        Vec3::zero(),
+        color,
+        intensity,
+        range,
+        (0.0, 0.0),
+      ),
+      Light::Spot {
+        position,
+        direction,
+        color,
+        intensity,
+        range,
+        inner_angle,
+        outer_angle,
+      } => (
+        2.0,
+        position,
+        direction,
+        color,
+        intensity,
+        range,
+        (inner_angle, outer_angle),
+      ),
+    };
+
+    let color = color * intensity;
+    #[rustfmt::skip]
+    let uniform = Mat4::from_row([
+      position.x , position.y , position.z , kind ,
+      direction.x, direction.y, direction.z, range,
+      color.x    , color.y    , color.z    , 0.0  ,
+      cone.0     , cone.1     , 0.0        , 1.0  ,
+    ]);
+    uniform
+  }
+
+  fn from_uniform(m: &Mat4) -> Option<Self> {
+    let row = |y: usize| Vec3::new(m.get(0, y), m.get(1, y), m.get(2, y));
+    let (position, direction, color) = (row(0), row(1), row(2));
+    let range = m.get(3, 1);
+
+    match m.get(3, 0) as u32 {
+      0 => Some(Light::directional(direction, color, 1.0)),
+      1 => Some(Light::point(position, color, 1.0).with_range(range)),
+      2 => Some(
+        Light::spot(position, direction, color, 1.0, m.get(0, 3), m.get(1, 3)).with_range(range),
+      ),
+      _ => None,
+    }
+  }
+}
+
+/// the unit vector along `to_light` and the inverse square falloff
+fn attenuate(to_light: Vec3, range: f32) -> (Vec3, f32) {
+  let distance_square = to_light.length_square().max(f32::EPSILON);
+  let distance = distance_square.sqrt();
+
+  let window = if range > 0.0 {
+    (1.0 - (distance / range).powi(4)).clamp(0.0, 1.0).powi(2)
+  } else {
+    1.0
+  };
+  (to_light / distance, window / distance_square)
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+  if edge1 <= edge0 {
+    return if x >= edge0 { 1.0 } else { 0.0 };
+  }
+  let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+  t * t * (3.0 - 2.0 * t)
+}
+
+/// sets the `light_count` and `lights[i]` uniforms
+pub fn upload_lights(lights: &[Light], uniforms: &mut GlTypeMap) {
+  let lights = &lights[..lights.len().min(MAX_LIGHTS)];
+  uniforms.insert("light_count".to_string(), GLTypes::Int(lights.len() as i32));
+  for (key, light) in LIGHT_KEYS.iter().zip(lights) {
+    uniforms.insert(key.to_string(), GLTypes::Mat4(light.to_uniform()));
+  }
+}
+
+/// the lights uploaded by the renderer, in world space
+pub fn lights<'a>(uniforms: &'a Uniform) -> impl Iterator<Item = Light> + 'a {
+  let count = uniform!(uniforms, i32, "light_count").unwrap_or(0) as usize;
+  LIGHT_KEYS
+    .iter()
+    .take(count)
+    .filter_map(|key| uniform!(uniforms, Mat4, key))
+    .filter_map(|m| Light::from_uniform(&m))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const WHITE: Vec3 = Vec3 {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+  };
+
+  #[track_caller]
+  fn assert_close(actual: f32, expected: f32) {
+    assert!(
+      (actual - expected).abs() < 1e-5,
+      "{} != {}",
+      actual,
+      expected
+    );
+  }
+
+  #[test]
+  fn inverse_square_falloff() {
+    let (l, attenuation) = attenuate(Vec3::new(0.0, 2.0, 0.0), 0.0);
+    assert_eq!(l, Vec3::new(0.0, 1.0, 0.0));
+    assert_close(attenuation, 0.25);
+
+    // no division by zero on the light itself
+    let (_, attenuation) = attenuate(Vec3::zero(), 0.0);
+    assert!(attenuation.is_finite());
+  }
+
+  #[test]
+  fn range_windows_the_falloff() {
+    let at = |distance: f32| attenuate(Vec3::new(distance, 0.0, 0.0), 4.0).1;
+    // (1 - (2 / 4)^4)^2 / 2^2
+    assert_close(at(2.0), (15.0f32 / 16.0).powi(2) / 4.0);
+    assert_eq!(at(4.0), 0.0);
+    assert_eq!(at(5.0), 0.0);
+
+    let mut previous = f32::MAX;
+    for step in 1..40 {
+      let attenuation = at(step as f32 * 0.1);
+      assert!(attenuation <= previous);
+      previous = attenuation;
+    }
+  }
+
+  #[test]
+  fn spot_fades_between_the_cone_angles() {
+    let spot = Light::spot(
+      Vec3::new(0.0, 0.0, 1.0),
+      Vec3::new(0.0, 0.0, -1.0),
+      WHITE,
+      1.0,
+      0.2,
+      0.4,
+    );
+    // the points 1 away from the light, at the angle `angle` from its axis
+    let radiance = |angle: f32| {
+      let position = Vec3::new(angle.sin(), 0.0, 1.0 - angle.cos());
+      spot.incident(&position).1.x
+    };
+
+    assert_close(radiance(0.0), 1.0);
+    assert_close(radiance(0.19), 1.0);
+    assert_close(radiance(0.41), 0.0);
+    assert_close(radiance(1.5), 0.0);
+
+    let halfway = radiance(0.3);
+    assert!(halfway > 0.0 && halfway < 1.0, "{}", halfway);
+    assert!(radiance(0.25) > halfway && halfway > radiance(0.35));
+  }
+
+  #[test]
+  fn lights_round_trip_through_the_uniforms() {
+    let lights_in = [
+      Light::directional(Vec3::new(0.0, 1.0, 0.0), WHITE, 2.0),
+      Light::point(Vec3::new(1.0, 2.0, 3.0), Vec3::new(1.0, 0.5, 0.0), 4.0).with_range(10.0),
+      Light::spot(
+        Vec3::new(-1.0, 3.0, 0.0),
+        Vec3::new(0.6, -0.8, 0.0),
+        WHITE,
+        3.0,
+        0.3,
+        0.5,
+      )
+      .with_range(8.0),
+    ];
+    let mut global = GlTypeMap::new();
+    upload_lights(&lights_in, &mut global);
+    let uniforms = Uniform::new(&global, GlTypeMap::new());
+    let lights_out: Vec<_> = lights(&uniforms).collect();
+    assert_eq!(lights_out.len(), lights_in.len());
+
+    // the intensity is folded into the color, the lights shine the same
+    let points = [
+      Vec3::zero(),
+      Vec3::new(2.0, 0.0, 1.0),
+      Vec3::new(0.5, 1.5, 0.0),
+    ];
+    for (light_in, light_out) in lights_in.iter().zip(&lights_out) {
+      for point in &points {
+        let (l_in, radiance_in) = light_in.incident(point);
+        let (l_out, radiance_out) = light_out.incident(point);
+        assert!((l_in - l_out).length() < 1e-5);
+        assert!((radiance_in - radiance_out).length() < 1e-5);
+      }
+    }
+  }
+
+  #[test]
+  fn lights_past_the_limit_are_dropped() {
+    let many = vec![Light::directional(Vec3::new(0.0, 1.0, 0.0), WHITE, 1.0); MAX_LIGHTS + 2];
+    let mut global = GlTypeMap::new();
+    upload_lights(&many, &mut global);
+    let uniforms = Uniform::new(&global, GlTypeMap::new());
+    assert_eq!(lights(&uniforms).count(), MAX_LIGHTS);
+  }
+}
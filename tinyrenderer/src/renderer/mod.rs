@@ -1,3 +1,9 @@
 pub mod camera;
+pub mod clip;
+pub mod controls;
+pub mod debug;
+pub mod light;
 pub mod renderer;
-pub mod shader;
\ No newline at end of file
+pub mod shader;
+pub mod shadow_map;
+pub mod states;
\ No newline at end of file
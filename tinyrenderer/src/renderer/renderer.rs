@@ -1,13 +1,30 @@
-use super::{camera::Camera, shader};
+use super::{
+  camera::Camera,
+  clip::{self, ClipResult},
+  debug::{self, DebugView},
+  light::{upload_lights, Light},
+  shadow_map::ShadowMap,
+  states::{BlendEquation, BlendFactor, BlendState, CullMode},
+};
 use crate::{
-  data_array::{ColorBuffer, DepthBuffer},
-  model::Scene,
+  data_array::{ColorBuffer, DataRows, DepthBuffer, HdrColorBuffer},
+  model::{Model, Scene, Vertex},
   obj_loader::{
-    material::{self, Material, MtlStores, Texture},
+    material::{Material, MtlStores, Texture, TexturePointer, Textures},
     shader::{GLTypes, GlTypeMap, Shader, Uniform, Varyings},
   },
 };
-use math::{Barycentric, BoundaryBox, Mat4, Vec2, Vec4};
+use std::collections::HashMap;
+
+type ColorRows<'a> = DataRows<'a, Vec4>;
+type DepthRows<'a> = DataRows<'a, f32>;
+
+use math::{
+  color::{ColorSpace, ToneMapping},
+  frustum::FrustumPlanes,
+  multisample::{Antialiasing, SampleBuffer, SampleRows},
+  Barycentric, EdgeRasterizer, Mat4, Vec2, Vec3, Vec4,
+};
 
 /// It means that the bi-unit cube [-1,1]*[-1,1]*[-1,1]
 /// is mapped onto the screen cube [x,x+w]*[y,y+h]*[0,d].
@@ -69,37 +86,60 @@ macro_rules! f {
 pub struct Renderer {
   viewport: Viewport,
   pub camera: Camera,
-  color: ColorBuffer,
+  /// in world space, uploaded to the shaders on every `render`
+  pub lights: Vec<Light>,
+  ambient_light: Vec3,
+  color: HdrColorBuffer,
   depth: DepthBuffer,
   stores: MtlStores,
-  default_shader: Shader,
-  blend: bool,
-  shadow_map: bool,
-  cull: bool,
+  blend: Option<BlendState>,
+  shadow_map: Option<ShadowMap>,
+  cull: CullMode,
+  threads: usize,
+  antialiasing: Antialiasing,
+  samples: Option<SampleBuffer>,
+  frustum_culling: bool,
+  culled_models: usize,
+  tone_mapping: ToneMapping,
+  exposure: f32,
+  debug_view: DebugView,
 }
 
 impl Renderer {
   pub fn new(w: u32, h: u32) -> Self {
     let mut depth = DepthBuffer::new(w, h);
-    depth.clear(std::f32::MAX);
+    depth.clear(f32::MAX);
 
     Self {
       viewport: Viewport::new(0.0, 0.0, w as f32, h as f32),
       camera: Camera::new(w as f32, h as f32),
-      color: ColorBuffer::new(w, h),
+      lights: vec![Light::directional(
+        Vec3::new(1.0, 1.0, 1.0),
+        Vec3::new(1.0, 1.0, 1.0),
+        1.0,
+      )],
+      ambient_light: Vec3::new(0.03, 0.03, 0.03),
+      color: HdrColorBuffer::new(w, h),
       depth,
       stores: Default::default(),
-      default_shader: Default::default(),
-      blend: false,
-      shadow_map: false,
-      cull: false,
+      blend: None,
+      shadow_map: None,
+      cull: CullMode::None,
+      threads: available_threads(),
+      antialiasing: Antialiasing::None,
+      samples: None,
+      frustum_culling: true,
+      culled_models: 0,
+      tone_mapping: ToneMapping::None,
+      exposure: 1.0,
+      debug_view: DebugView::None,
     }
   }
 
+  /// Draws every model of the scene with the shader of `material`. The textures
+  /// and the coefficients come from the MTL material of each model, and are
+  /// bound as named samplers and uniforms, see `MaterialBinding`.
   pub fn render(&mut self, scene: &Scene, model_matrix: Mat4, material: &Material) {
-    let width = self.color.width();
-    let height = self.color.height();
-
     let frustum: &super::camera::Frustum = self.camera.get_frustum();
     let viewport_matrix = self.viewport.get_viewport_matrix();
 
@@ -107,99 +147,936 @@ impl Renderer {
     let projection_matrix = *(frustum.get_projection_matrix());
     let mvp_it = (view_matrix * model_matrix).inverse_transpose();
 
-    let global_uniforms: GlTypeMap = GlTypeMap::from([
+    let mut global_uniforms: GlTypeMap = GlTypeMap::from([
       (f!("model_matrix"), GLTypes::Mat4(model_matrix)),
       (f!("view_matrix"), GLTypes::Mat4(view_matrix)),
       (f!("projection_matrix"), GLTypes::Mat4(projection_matrix)),
       (f!("viewport_matrix"), GLTypes::Mat4(*viewport_matrix)),
       (f!("mv_it"), GLTypes::Mat4(mvp_it.unwrap_or_default())),
+      (
+        f!("camera_position"),
+        GLTypes::Vec3(self.camera.get_position()),
+      ),
+      (f!("ambient_light"), GLTypes::Vec3(self.ambient_light)),
     ]);
+    upload_lights(&self.lights, &mut global_uniforms);
 
-    // todo make material mutable then it can call the mutable shaders
-    for model in &scene.models {
-      let vertices = &model.vertices;
-      let mut uniforms = Uniform::new(&global_uniforms, Default::default());
-      // let material = model
-      //   .get_material()
-      //   .map_or(None, |id| scene.stores.materials.get_material_by_id(id));
-      let material = Some(material);
-      let shader = material.map(|m| &m.shader).unwrap_or(&self.default_shader);
-      for i in 0..vertices.len() / 3_usize {
-        let index = (i * 3) as usize;
-        let mut vertices = [vertices[index], vertices[index + 1], vertices[index + 2]];
-        let mut varyings = Varyings::default();
-
-        let mut index = 0.0;
-        for v in &mut vertices {
-          uniforms.set("vertex_index", GLTypes::Float(index as f32));
-          *v = shader.run_vertex(v, &uniforms, &mut varyings);
-          index += 1.0;
-        }
+    // the shadows are cast by the first directional light, if any
+    let shadow_light = self.lights.iter().find_map(|light| match light {
+      Light::Directional { direction, .. } => Some(*direction),
+      _ => None,
+    });
+    let shadow_map = self.shadow_map.as_mut().zip(shadow_light);
+    if let Some((shadow_map, light_dir)) = shadow_map {
+      shadow_map.render(scene, model_matrix, light_dir, self.threads);
+      shadow_map.upload_uniforms(&mut global_uniforms);
+    }
 
-        // restore the x,y,z  with 1/w, as the computation times `w` before
+    // the view volume in model space, where the bounds of the models are
+    let planes = FrustumPlanes::from_matrix(&(projection_matrix * view_matrix * model_matrix));
+    let is_visible = |model: &Model| {
+      model
+        .get_bounding_sphere()
+        .is_none_or(|s| planes.intersects_sphere(s))
+        && model
+          .get_bounding_box()
+          .is_none_or(|b| planes.intersects_box(b))
+    };
+
+    // opaque models go first, then the transparent ones from back to front
+    let mut opaque = vec![];
+    let mut transparent = vec![];
+    self.culled_models = 0;
+    for (model_id, model) in scene.models.iter().enumerate() {
+      if self.frustum_culling && !is_visible(model) {
+        self.culled_models += 1;
+        continue;
+      }
+
+      let binding = MaterialBinding {
+        material,
+        textures: &self.stores.textures,
+        mtl: model
+          .get_material()
+          .and_then(|id| scene.stores.materials.get_material_by_id(id)),
+        mtl_textures: &scene.stores.textures,
+      };
 
-        // store the rhw and perform the v.position.w
-        for v in &mut vertices {
-          v.rhw = 1.0 / v.position.w;
-          v.position /= v.position.w;
+      match binding.blend(self.blend) {
+        Some(blend) => {
+          let depth = (view_matrix * model_matrix * model.centroid()).z;
+          transparent.push((model_id, model, binding, Some(blend), depth));
         }
+        None => opaque.push((model_id, model, binding, None, 0.0)),
+      }
+    }
+    // camera looks along -z, the farthest has the smallest z
+    transparent.sort_by(|a, b| a.4.total_cmp(&b.4));
+
+    let mut target = match &mut self.samples {
+      Some(samples) => Target::Samples(samples, self.antialiasing),
+      None => Target::Pixels(&mut self.color, &mut self.depth),
+    };
+
+    for (model_id, model, binding, blend, _) in opaque.into_iter().chain(transparent) {
+      let blend = match self.debug_view {
+        DebugView::None => blend,
+        // every fragment adds one, without writing the depth
+        DebugView::Overdraw => Some(BlendState::new(
+          BlendFactor::One,
+          BlendFactor::One,
+          BlendEquation::Add,
+        )),
+        _ => None,
+      };
+
+      let state = DrawState {
+        viewport_matrix: *viewport_matrix,
+        cull: binding.cull(self.cull),
+        blend,
+        dissolve: binding.coefficient(|m| m.dissolve).unwrap_or(1.0),
+        alpha_map: binding.texture("alpha"),
+        shader: &material.shader,
+        textures: &self.stores.textures,
+        uniforms: binding.uniforms(),
+        samplers: binding.samplers(),
+        shadow_map: self
+          .shadow_map
+          .as_ref()
+          .filter(|_| shadow_light.is_some())
+          .map(|s| s.get_depth()),
+        threads: self.threads,
+        debug_view: self.debug_view,
+        model_id,
+      };
+
+      draw_model(&state, model, &global_uniforms, &mut target);
+    }
+  }
+
+  /// Enables the shadow pass, the scene is rendered from the first directional
+  /// light of `lights` into a `size` x `size` depth map before each frame.
+  /// Nothing is shadowed without a directional light.
+  pub fn enable_shadow_map(&mut self, size: u32) {
+    self.shadow_map = Some(ShadowMap::new(size));
+  }
+
+  pub fn disable_shadow_map(&mut self) {
+    self.shadow_map = None;
+  }
+
+  pub fn get_shadow_map(&self) -> Option<&ShadowMap> {
+    self.shadow_map.as_ref()
+  }
+
+  pub fn get_shadow_map_mut(&mut self) -> Option<&mut ShadowMap> {
+    self.shadow_map.as_mut()
+  }
+
+  /// Blend state used by the materials without their own one,
+  /// `None` draws them as opaque.
+  pub fn set_blend_state(&mut self, blend: Option<BlendState>) {
+    self.blend = blend;
+  }
+
+  pub fn set_cull_mode(&mut self, cull: CullMode) {
+    self.cull = cull;
+  }
+
+  /// Threads rasterizing the tiles, `1` renders on the calling thread.
+  /// Defaults to `available_threads()`.
+  pub fn set_threads(&mut self, threads: usize) {
+    self.threads = threads.max(1);
+  }
+
+  /// loads a data map, e.g. a normal map, whose texels are linear
+  pub fn load_texture(&mut self, filepath: &str, name: &str) {
+    let _ = self.stores.textures.load(filepath, name);
+  }
+
+  /// loads a color map, e.g. a diffuse map, decoded from sRGB
+  pub fn load_color_texture(&mut self, filepath: &str, name: &str) {
+    let _ = self
+      .stores
+      .textures
+      .load_with_color_space(filepath, name, ColorSpace::Srgb);
+  }
+
+  /// Selects how the edges get antialiased, the samples are resolved into
+  /// the color buffer by `take_color`.
+  pub fn set_antialiasing(&mut self, antialiasing: Antialiasing) {
+    self.antialiasing = antialiasing;
+    self.samples = match antialiasing {
+      Antialiasing::None => None,
+      _ => {
+        let (w, h) = (self.color.width(), self.color.height());
+        let mut samples = SampleBuffer::new(w, h, antialiasing.sample_count());
+        samples.clear(&self.clear_color(), f32::MAX);
+        Some(samples)
+      }
+    };
+  }
+
+  /// Skips the models whose bounds are outside the view, on by default.
+  pub fn set_frustum_culling(&mut self, frustum_culling: bool) {
+    self.frustum_culling = frustum_culling;
+  }
+
+  /// how many models the last `render` skipped
+  pub fn get_culled_models(&self) -> usize {
+    self.culled_models
+  }
+
+  /// the light reaching every surface from all the directions, scaled by the
+  /// `Ka` of the materials, 0.03 by default
+  pub fn set_ambient_light(&mut self, ambient_light: Vec3) {
+    self.ambient_light = ambient_light;
+  }
+
+  /// Compresses the linear colors of the frame into the displayable range in
+  /// `take_color`, `ToneMapping::None` by default.
+  pub fn set_tone_mapping(&mut self, tone_mapping: ToneMapping) {
+    self.tone_mapping = tone_mapping;
+  }
+
+  /// scales the linear colors before the tone mapping, 1 by default
+  pub fn set_exposure(&mut self, exposure: f32) {
+    self.exposure = exposure;
+  }
+
+  /// Replaces the colors of the materials with one of the debug views, until
+  /// it is set back to `DebugView::None`. The frame being drawn is cleared.
+  pub fn set_debug_view(&mut self, debug_view: DebugView) {
+    self.debug_view = debug_view;
+
+    let clear_color = self.clear_color();
+    self.color.clear(&clear_color);
+    self.depth.clear(f32::MAX);
+    if let Some(samples) = &mut self.samples {
+      samples.clear(&clear_color, f32::MAX);
+    }
+  }
+
+  pub fn get_debug_view(&self) -> DebugView {
+    self.debug_view
+  }
+
+  /// white for the materials, black for the debug views
+  fn clear_color(&self) -> Vec4 {
+    match self.debug_view {
+      DebugView::None => Vec4::new(1.0, 1.0, 1.0, 1.0),
+      _ => Vec4::new(0.0, 0.0, 0.0, 1.0),
+    }
+  }
+
+  /// The linear colors of the frame, before the tone mapping. The buffers are
+  /// cleared for the next frame.
+  pub fn take_hdr_color(&mut self) -> HdrColorBuffer {
+    let w = self.color.width();
+    let h = self.color.height();
+    let clear_color = self.clear_color();
+
+    if let Some(samples) = &mut self.samples {
+      samples.resolve(|x, y, c| self.color.set(x, y, &c));
+      if self.debug_view == DebugView::Depth {
+        debug::resolve_depth(samples, &mut self.depth);
+      }
+      samples.clear(&clear_color, f32::MAX);
+    }
+
+    debug::resolve(
+      self.debug_view,
+      self.camera.get_frustum(),
+      &mut self.color,
+      &self.depth,
+    );
+    self.depth.clear(f32::MAX);
+
+    let mut next = HdrColorBuffer::new(w, h);
+    next.clear(&clear_color);
+    std::mem::replace(&mut self.color, next)
+  }
+
+  /// The frame tone mapped and encoded to sRGB, ready to be displayed. The
+  /// debug views skip the tone mapping.
+  pub fn take_color(&mut self) -> ColorBuffer {
+    let (tone_mapping, exposure) = match self.debug_view {
+      DebugView::None => (self.tone_mapping, self.exposure),
+      _ => (ToneMapping::None, 1.0),
+    };
+    self
+      .take_hdr_color()
+      .to_color_buffer(tone_mapping, exposure)
+  }
+}
+
+/// Pairs the material given to `Renderer::render`, which provides the shader
+/// and the render states, with the MTL material of the model, which provides
+/// the textures and the coefficients. Whatever the MTL material lacks falls
+/// back to the render material, whose textures are looked up among the ones
+/// loaded by `Renderer::load_texture`.
+struct MaterialBinding<'a> {
+  material: &'a Material,
+  textures: &'a Textures,
+  mtl: Option<&'a Material>,
+  mtl_textures: &'a Textures,
+}
+
+impl<'a> MaterialBinding<'a> {
+  fn coefficient<T>(&self, f: impl Fn(&Material) -> Option<T>) -> Option<T> {
+    self.mtl.and_then(&f).or_else(|| f(self.material))
+  }
+
+  /// `key` is one of the `TextureMap` fields, e.g. "diffuse" for `map_Kd`
+  fn texture(&self, key: &str) -> Option<&'a Texture> {
+    let lookup = |material: &'a Material, textures: &'a Textures| {
+      material
+        .texture_map
+        .get_by_key(key)
+        .and_then(|name| textures.get_texture_by_name(name))
+    };
+
+    self
+      .mtl
+      .and_then(|mtl| lookup(mtl, self.mtl_textures))
+      .or_else(|| lookup(self.material, self.textures))
+  }
+
+  fn cull(&self, default: CullMode) -> CullMode {
+    self.coefficient(|m| m.cull).unwrap_or(default)
+  }
+
+  /// The model is drawn with blending when a material has its own blend state,
+  /// the renderer has a default one, or the MTL data makes it see-through.
+  fn blend(&self, default: Option<BlendState>) -> Option<BlendState> {
+    self.coefficient(|m| m.blend).or(default).or_else(|| {
+      let see_through = self.coefficient(|m| m.dissolve).is_some_and(|d| d < 1.0)
+        || self.texture("alpha").is_some();
+      see_through.then(BlendState::over)
+    })
+  }
+
+  /// the MTL coefficients under their MTL names
+  fn uniforms(&self) -> GlTypeMap {
+    let mut uniforms = GlTypeMap::new();
+    let vectors = [
+      ("Ka", self.coefficient(|m| m.ambient)),
+      ("Kd", self.coefficient(|m| m.diffuse)),
+      ("Ks", self.coefficient(|m| m.specular)),
+      ("Ke", self.coefficient(|m| m.emissive_coeficient)),
+    ];
+    for (key, value) in vectors {
+      if let Some(v) = value {
+        uniforms.insert(key.to_string(), GLTypes::Vec3(v));
+      }
+    }
+
+    let scalars = [
+      ("Ns", self.coefficient(|m| m.specular_exponent)),
+      ("d", self.coefficient(|m| m.dissolve)),
+      ("Pr", self.coefficient(|m| m.roughness)),
+      ("Pm", self.coefficient(|m| m.metallic)),
+    ];
+    for (key, value) in scalars {
+      if let Some(v) = value {
+        uniforms.insert(key.to_string(), GLTypes::Float(v));
+      }
+    }
+
+    uniforms
+  }
+
+  /// every texture of the material, named after its `TextureMap` field
+  fn samplers(&self) -> HashMap<String, &'a Texture> {
+    TexturePointer::keys()
+      .iter()
+      .filter_map(|key| self.texture(key).map(|t| (key.to_string(), t)))
+      .collect()
+  }
+}
+
+/// Per draw call state shared by all the triangles of a model.
+pub(super) struct DrawState<'a> {
+  pub viewport_matrix: Mat4,
+  pub cull: CullMode,
+  pub blend: Option<BlendState>,
+  pub dissolve: f32,
+  pub alpha_map: Option<&'a Texture>,
+  pub shader: &'a Shader,
+  pub textures: &'a Textures,
+  pub uniforms: GlTypeMap,
+  pub samplers: HashMap<String, &'a Texture>,
+  pub shadow_map: Option<&'a DepthBuffer>,
+  pub threads: usize,
+  pub debug_view: DebugView,
+  /// the index of the model in the scene, for `DebugView::ModelId`
+  pub model_id: usize,
+}
+
+/// Where `draw_model` writes to, either one sample per pixel or the samples
+/// of the antialiased modes.
+pub(super) enum Target<'a> {
+  Pixels(&'a mut HdrColorBuffer, &'a mut DepthBuffer),
+  Samples(&'a mut SampleBuffer, Antialiasing),
+}
+
+/// The rows of the target owned by a tile.
+enum Tile<'a> {
+  Pixels(ColorRows<'a>, DepthRows<'a>),
+  Samples(SampleRows<'a>, Antialiasing),
+}
 
-        for v in &mut vertices {
-          v.position = *viewport_matrix * v.position;
+impl<'a> Tile<'a> {
+  fn rows(&self) -> std::ops::Range<u32> {
+    match self {
+      Tile::Pixels(color, _) => color.y_min()..color.y_max(),
+      Tile::Samples(samples, _) => samples.y_min()..samples.y_max(),
+    }
+  }
+}
+
+/// Rows of pixels per tile, a tile spans the whole width of the target.
+const TILE_ROWS: u32 = 16;
+
+/// The number of threads the hardware runs in parallel, at least 1.
+pub fn available_threads() -> usize {
+  std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// A triangle after the perspective divide and the viewport transform,
+/// waiting in the bins of the tiles it overlaps.
+struct ScreenTriangle {
+  /// the index of the triangle in the model, shared by its clipped parts
+  id: usize,
+  vertices: [Vertex; 3],
+  varyings: Varyings,
+  gradients: [Vec2; 3],
+  raster: EdgeRasterizer,
+}
+
+/// Runs the vertex shader over every triangle of the model and clips the
+/// results, then bins them into tiles of `TILE_ROWS` rows which are rasterized
+/// in parallel. Every tile owns its rows of both buffers and draws its
+/// triangles in the submission order, so the output does not depend on the
+/// number of threads.
+pub(super) fn draw_model(
+  state: &DrawState,
+  model: &Model,
+  global_uniforms: &GlTypeMap,
+  target: &mut Target,
+) {
+  let shader = state.shader;
+  let mut uniforms = Uniform::new(global_uniforms, state.uniforms.clone());
+  for (name, texture) in &state.samplers {
+    uniforms.set_sampler(name, texture);
+  }
+  if let Some(shadow_map) = state.shadow_map {
+    uniforms.set_shadow_map(shadow_map);
+  }
+
+  let (width, height) = match target {
+    Target::Pixels(color, _) => (color.width(), color.height()),
+    Target::Samples(samples, _) => (samples.width(), samples.height()),
+  };
+  let mut triangles = vec![];
+
+  // the vertex shader runs once per vertex of the model, or once per corner
+  // for the shaders reading `vertex_index`
+  let corners = if shader.per_corner { 3 } else { 1 };
+  let mut cache: Vec<Option<(Vertex, Varyings)>> = vec![];
+  cache.resize_with(model.vertices.len() * corners, || None);
+
+  for (id, triangle) in model.indices.chunks_exact(3).enumerate() {
+    let mut vertices = [Vertex::default(); 3];
+    let mut varyings = Varyings::default();
+
+    for (corner, index) in triangle.iter().enumerate() {
+      let slot = *index as usize * corners + corner % corners;
+      let (vertex, vertex_varyings) = cache[slot].get_or_insert_with(|| {
+        let mut vertex_varyings = Varyings::default();
+        uniforms.set("vertex_index", GLTypes::Float(corner as f32));
+        let vertex = shader.run_vertex(
+          &model.vertices[*index as usize],
+          &uniforms,
+          &mut vertex_varyings,
+        );
+        (vertex, vertex_varyings)
+      });
+
+      vertices[corner] = *vertex;
+      varyings.append(vertex_varyings);
+    }
+
+    let positions = vertices.map(|v| v.position);
+    match clip::clip_triangle(&positions) {
+      ClipResult::Outside => {}
+      ClipResult::Inside => triangles.extend(project_triangle(
+        state, id, vertices, varyings, width, height,
+      )),
+      ClipResult::Clipped(polygon) => {
+        for triangle in clip::triangulate(&polygon) {
+          let clipped_varyings = varyings.blend(&triangle.map(|c| c.weights));
+          let clipped_vertices = triangle.map(|c| clip::blend_vertex(&vertices, &c));
+
+          triangles.extend(project_triangle(
+            state,
+            id,
+            clipped_vertices,
+            clipped_varyings,
+            width,
+            height,
+          ));
         }
+      }
+    }
+  }
+
+  let tiles: Vec<Tile> = match target {
+    Target::Pixels(color, depth) => color
+      .split_rows_mut(TILE_ROWS)
+      .into_iter()
+      .zip(depth.split_rows_mut(TILE_ROWS))
+      .map(|(color, depth)| Tile::Pixels(color, depth))
+      .collect(),
+    Target::Samples(samples, antialiasing) => samples
+      .split_rows_mut(TILE_ROWS)
+      .into_iter()
+      .map(|samples| Tile::Samples(samples, *antialiasing))
+      .collect(),
+  };
+
+  let mut bins: Vec<Vec<usize>> = vec![vec![]; tiles.len()];
+  for (index, triangle) in triangles.iter().enumerate() {
+    let rows = triangle.raster.y_range();
+    let first = rows.start / TILE_ROWS;
+    let last = (rows.end - 1) / TILE_ROWS;
+    for bin in bins.iter_mut().take(last as usize + 1).skip(first as usize) {
+      bin.push(index);
+    }
+  }
 
-        let vertices_2d = vertices.map(|v| v.position.truncate_to_vec2());
+  let tiles: Vec<_> = tiles.into_iter().zip(bins).collect();
+  let uniforms = &uniforms;
+  let triangles = &triangles;
+  let shade_tile = |(mut tile, bin): (Tile, Vec<usize>)| {
+    for index in bin {
+      rasterize_triangle(state, &triangles[index], uniforms, &mut tile);
+    }
+  };
 
-        let BoundaryBox {
-          x_max,
-          x_min,
-          y_max,
-          y_min,
-        } = BoundaryBox::new(&vertices_2d, width as f32, height as f32);
+  let threads = state.threads.clamp(1, tiles.len().max(1));
+  if threads == 1 {
+    tiles.into_iter().for_each(shade_tile);
+    return;
+  }
 
-        for x in (x_min as u32)..(x_max as u32 + 1) {
-          for y in (y_min as u32)..(y_max as u32 + 1) {
-            let barycentric = Barycentric::new(&Vec2::new(x as f32, y as f32), &vertices_2d);
+  // neighbouring tiles tend to cost the same, so they are dealt out in turn
+  let mut groups: Vec<Vec<_>> = (0..threads).map(|_| vec![]).collect();
+  for (i, tile) in tiles.into_iter().enumerate() {
+    groups[i % threads].push(tile);
+  }
 
-            if !barycentric.is_inside() {
-              continue;
-            }
+  let shade_tile = &shade_tile;
+  std::thread::scope(|scope| {
+    for group in groups {
+      scope.spawn(move || group.into_iter().for_each(shade_tile));
+    }
+  });
+}
+
+/// Performs the perspective divide and the viewport transform on a clip space
+/// triangle, `None` when it is culled by its screen space winding or does not
+/// cover any pixel.
+fn project_triangle(
+  state: &DrawState,
+  id: usize,
+  mut vertices: [Vertex; 3],
+  varyings: Varyings,
+  width: u32,
+  height: u32,
+) -> Option<ScreenTriangle> {
+  // restore the x,y,z  with 1/w, as the computation times `w` before
+
+  // store the rhw and perform the v.position.w
+  for v in &mut vertices {
+    v.rhw = 1.0 / v.position.w;
+    v.position /= v.position.w;
+  }
+
+  for v in &mut vertices {
+    v.position = state.viewport_matrix * v.position;
+  }
+
+  let vertices_2d = vertices.map(|v| v.position.truncate_to_vec2());
+
+  if state.cull.should_cull(&vertices_2d) {
+    return None;
+  }
+
+  let raster = EdgeRasterizer::new(&vertices_2d, width, height)?;
+
+  Some(ScreenTriangle {
+    id,
+    vertices,
+    varyings,
+    gradients: barycentric_gradients(&vertices_2d),
+    raster,
+  })
+}
+
+/// Shades the pixels of the triangle that fall into the rows of the tile.
+/// The multisampled tiles test and store every sample, but shade once per
+/// pixel with `Antialiasing::Msaa`.
+fn rasterize_triangle(
+  state: &DrawState,
+  triangle: &ScreenTriangle,
+  uniforms: &Uniform,
+  tile: &mut Tile,
+) {
+  let ScreenTriangle {
+    id,
+    vertices,
+    varyings,
+    gradients,
+    raster,
+  } = triangle;
+
+  let depth_at =
+    |barycentric: &Barycentric| barycentric.apply_weight(&vertices.map(|v| v.position.z));
+  let shade = |barycentric: &Barycentric| {
+    if let Some(c) = debug::fragment_color(
+      state.debug_view,
+      vertices,
+      barycentric,
+      uniforms,
+      *id,
+      state.model_id,
+    ) {
+      return c;
+    }
+
+    let uv_derivatives = uv_derivatives(vertices, barycentric, gradients);
+    let mut c = state.shader.run_fragment(
+      vertices,
+      barycentric,
+      uniforms,
+      varyings,
+      state.textures,
+      uv_derivatives,
+    );
+    if state.blend.is_some() {
+      c.w *= state.dissolve * sample_alpha(state.alpha_map, vertices, barycentric);
+    }
+    c
+  };
+
+  let rows = tile.rows();
+  match tile {
+    Tile::Pixels(color, depth) => raster.rasterize(rows, |x, y, barycentric| {
+      let z = depth_at(barycentric);
+
+      if depth.get(x, y) < z {
+        return;
+      }
 
-            let depth = barycentric.apply_weight(&vertices.map(|v| v.position.z));
+      let c = shade(barycentric);
+      match state.blend {
+        // transparent surfaces are depth tested but do not occlude
+        Some(blend) => color.set(x, y, &blend.blend(&c, &color.get(x, y))),
+        None => {
+          depth.set(x, y, z);
+          color.set(x, y, &c);
+        }
+      }
+    }),
+    Tile::Samples(samples, antialiasing) => {
+      let positions = antialiasing.sample_positions();
+      let per_sample = antialiasing.shade_per_sample();
 
-            if self.depth.get(x, y) >= depth {
-              self.depth.set(x, y, depth);
+      raster.rasterize_samples(rows, positions, |x, y, coverage| {
+        let mut pixel_color = None;
 
-              let color = shader.run_fragment(
-                &vertices,
-                &barycentric,
-                &uniforms,
-                &varyings,
-                &self.stores.textures,
-              );
+        for sample in 0..positions.len() {
+          if !coverage.is_covered(sample) {
+            continue;
+          }
 
-              // let material = model.get_material().unwrap();
-              // let diffuse_texture = material.texture_map.diffuse.unwrap();
+          let barycentric = coverage.sample(sample);
+          let z = depth_at(barycentric);
 
-              // let color = texture.get_pixel(vt);
+          if samples.get_depth(x, y, sample) < z {
+            continue;
+          }
 
-              self.color.set(x, y, &color);
+          let c = match per_sample {
+            true => shade(barycentric),
+            false => *pixel_color.get_or_insert_with(|| shade(coverage.centre())),
+          };
+          match state.blend {
+            Some(blend) => {
+              let dst = samples.get_color(x, y, sample);
+              samples.set_color(x, y, sample, &blend.blend(&c, &dst));
+            }
+            None => {
+              samples.set_depth(x, y, sample, z);
+              samples.set_color(x, y, sample, &c);
             }
           }
         }
-      }
+      });
     }
   }
+}
 
-  pub fn load_texture(&mut self, filepath: &str, name: &str) {
-    let _ = self.stores.textures.load(filepath, name);
+/// Screen space gradients (d/dx, d/dy) of the three barycentric weights,
+/// constant over the triangle.
+fn barycentric_gradients(vertices: &[Vec2; 3]) -> [Vec2; 3] {
+  let [a, b, c] = vertices;
+  let area = (*b - *a).cross(&(*c - *a));
+
+  [
+    Vec2::new(b.y - c.y, c.x - b.x) / area,
+    Vec2::new(c.y - a.y, a.x - c.x) / area,
+    Vec2::new(a.y - b.y, b.x - a.x) / area,
+  ]
+}
+
+/// Derivatives of the perspective correct uv, by the quotient rule on
+/// `uv = Σ(b·uv·rhw) / Σ(b·rhw)`.
+fn uv_derivatives(
+  vertices: &[Vertex; 3],
+  bar: &Barycentric,
+  gradients: &[Vec2; 3],
+) -> Option<(Vec2, Vec2)> {
+  let uvs = match vertices.map(|v| v.texture) {
+    [Some(uv0), Some(uv1), Some(uv2)] => [uv0, uv1, uv2],
+    _ => return None,
+  };
+  let rhws = vertices.map(|v| v.rhw);
+
+  let numerator = bar.apply_weight(&[uvs[0] * rhws[0], uvs[1] * rhws[1], uvs[2] * rhws[2]]);
+  let denominator = bar.apply_weight(&rhws);
+  let uv = numerator / denominator;
+
+  let derive = |axis: fn(&Vec2) -> f32| {
+    let mut d_numerator = Vec2::zero();
+    let mut d_denominator = 0.0;
+    for ((uv, rhw), gradient) in uvs.iter().zip(rhws).zip(gradients) {
+      let g = axis(gradient);
+      d_numerator += *uv * (rhw * g);
+      d_denominator += rhw * g;
+    }
+    (d_numerator - uv * d_denominator) / denominator
+  };
+
+  Some((derive(|g| g.x), derive(|g| g.y)))
+}
+
+/// Samples the `map_d` texture with the perspective correct uv of the fragment.
+fn sample_alpha(alpha_map: Option<&Texture>, vertices: &[Vertex; 3], bar: &Barycentric) -> f32 {
+  let uvs = match vertices.map(|v| v.texture) {
+    [Some(uv0), Some(uv1), Some(uv2)] => [uv0, uv1, uv2],
+    _ => return 1.0,
+  };
+
+  alpha_map.map_or(1.0, |texture| {
+    let rhws = vertices.map(|v| v.rhw);
+    let z = 1.0 / bar.apply_weight(&rhws);
+    let uv = bar.apply_weight(&[uvs[0] * rhws[0], uvs[1] * rhws[1], uvs[2] * rhws[2]]) * z;
+    texture.get_pixel(uv).x
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_utils::{assert_color, dir, flat_material, quad, scene, scene_in};
+  use math::multisample::SampleCount;
+
+  fn binding<'a>(
+    material: &'a Material,
+    mtl: Option<&'a Material>,
+    textures: &'a Textures,
+  ) -> MaterialBinding<'a> {
+    MaterialBinding {
+      material,
+      textures,
+      mtl,
+      mtl_textures: textures,
+    }
   }
 
-  pub fn take_color(&mut self) -> ColorBuffer {
-    let w = self.color.width();
-    let h = self.color.height();
-    self.depth.clear(std::f32::MAX);
+  #[test]
+  fn material_cull_overrides_the_renderer() {
+    let textures = Textures::default();
+    let plain = Material::default();
+    let double_sided = Material {
+      cull: Some(CullMode::None),
+      ..Default::default()
+    };
+
+    let cull = |material, mtl| binding(material, mtl, &textures).cull(CullMode::Back);
+    assert_eq!(cull(&plain, None), CullMode::Back);
+    assert_eq!(cull(&plain, Some(&plain)), CullMode::Back);
+    // the MTL material wins over the render material, which wins over the renderer
+    assert_eq!(cull(&double_sided, None), CullMode::None);
+    assert_eq!(cull(&plain, Some(&double_sided)), CullMode::None);
+
+    let front = Material {
+      cull: Some(CullMode::Front),
+      ..Default::default()
+    };
+    assert_eq!(cull(&front, Some(&double_sided)), CullMode::None);
+  }
+
+  #[test]
+  fn transparent_models_are_drawn_back_to_front() {
+    // the near model is the first one of the file
+    let obj = format!(
+      "mtllib scene.mtl\no near\nusemtl green\n{}o far\nusemtl red\n{}",
+      quad((-0.2, -0.2), (0.2, 0.2), -5.0, 1),
+      quad((-0.2, -0.2), (0.2, 0.2), -10.0, 5),
+    );
+    let mtl = "newmtl green\nKd 0 1 0\nd 0.5\nnewmtl red\nKd 1 0 0\nd 0.5\n";
+    let scene = scene("transparent-order", &obj, mtl);
+
+    let mut renderer = Renderer::new(16, 16);
+    renderer.render(&scene, Mat4::identity(), &flat_material());
+    let color = renderer.take_hdr_color();
+
+    // the red over the white clear color, then the green over both
+    assert_color(color.get(8, 8), Vec3::new(0.5, 0.75, 0.25));
+  }
+
+  #[test]
+  fn mtl_maps_and_coefficients_are_bound() {
+    let dir = dir("mtl-binding");
+    image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255]))
+      .save(dir.join("diffuse.png"))
+      .unwrap();
+    let obj = format!(
+      "mtllib scene.mtl\nusemtl textured\n{}",
+      quad((-1.0, -1.0), (1.0, 1.0), 0.0, 1)
+    );
+    let mtl = "newmtl textured\nKd 0.2 0.4 0.6\nmap_Kd diffuse.png\n";
+    let scene = scene_in(&dir, &obj, mtl);
+
+    let model = scene.models.iter().find(|m| !m.indices.is_empty()).unwrap();
+    let mtl = model
+      .get_material()
+      .and_then(|id| scene.stores.materials.get_material_by_id(id));
+    assert!(mtl.is_some());
+
+    let material = Material::default();
+    let binding = binding(&material, mtl, &scene.stores.textures);
+
+    let samplers = binding.samplers();
+    let diffuse = samplers["diffuse"];
+    assert_eq!(diffuse.get_name(), "diffuse.png");
+    assert_eq!(diffuse.get_color_space(), ColorSpace::Srgb);
+    assert_eq!(samplers.len(), 1);
+
+    match binding.uniforms().get("Kd") {
+      Some(GLTypes::Vec3(kd)) => assert_eq!(*kd, Vec3::new(0.2, 0.4, 0.6)),
+      kd => panic!("unexpected Kd {:?}", kd),
+    }
+  }
+
+  #[test]
+  fn threads_do_not_change_the_output() {
+    // a square, and a slanted triangle through it spanning all the tile bands
+    let obj = format!(
+      "mtllib scene.mtl\nusemtl red\n{}usemtl green\n\
+       v -1.5 -1.5 -4\nv 1.2 -0.8 -6\nv 0 1.4 -5\nf 5 6 7\n",
+      quad((-0.6, -0.6), (0.6, 0.6), -5.0, 1),
+    );
+    let mtl = "newmtl red\nKd 1 0 0\nnewmtl green\nKd 0 1 0\n";
+    let scene = scene("threads", &obj, mtl);
+
+    let render = |threads| {
+      let mut renderer = Renderer::new(64, 64);
+      renderer.set_threads(threads);
+      renderer.render(&scene, Mat4::identity(), &flat_material());
+      renderer
+    };
+    let bits = |renderer: &Renderer| {
+      let color = renderer
+        .color
+        .data()
+        .iter()
+        .flat_map(|c| [c.x, c.y, c.z, c.w]);
+      let depth = renderer.depth.data().iter().copied();
+      color.chain(depth).map(f32::to_bits).collect::<Vec<_>>()
+    };
+
+    let single = render(1);
+    let rows_of = |color: Vec4| -> Vec<u32> {
+      (0..64)
+        .filter(|y| (0..64).any(|x| single.color.get(x, *y) == color))
+        .collect()
+    };
+    let green = rows_of(Vec4::new(0.0, 1.0, 0.0, 1.0));
+    assert!(green[0] < TILE_ROWS && green[green.len() - 1] >= 64 - TILE_ROWS);
+    assert!(!rows_of(Vec4::new(1.0, 0.0, 0.0, 1.0)).is_empty());
+
+    for threads in [2, 3, 8] {
+      assert!(
+        bits(&render(threads)) == bits(&single),
+        "{} threads",
+        threads
+      );
+    }
+  }
+
+  #[test]
+  fn msaa_resolves_the_coverage_of_the_edges() {
+    // 1/8 of a unit per pixel, the right edge of the black square at x = 8.5
+    // halves the pixels of the 9th column
+    let obj = format!(
+      "mtllib scene.mtl\nusemtl black\n{}",
+      quad((-2.0, -2.0), (0.0625, 2.0), -5.0, 1),
+    );
+    let scene = scene("msaa", &obj, "newmtl black\nKd 0 0 0\n");
+
+    let render = |antialiasing| {
+      let mut renderer = Renderer::new(16, 16);
+      renderer.camera.update_orthographic(1.0, 10.0, 1.0);
+      renderer.set_antialiasing(antialiasing);
+      renderer.render(&scene, Mat4::identity(), &flat_material());
+      renderer.take_hdr_color()
+    };
+    let aliased = render(Antialiasing::None);
+    let resolved = render(Antialiasing::Msaa(SampleCount::X4));
+
+    // 2 of the 4 samples are covered
+    assert_color(resolved.get(8, 8), Vec3::new(0.5, 0.5, 0.5));
+    assert_color(aliased.get(7, 8), Vec3::new(0.0, 0.0, 0.0));
+    for x in [4, 7, 9, 12] {
+      assert_eq!(resolved.get(x, 8), aliased.get(x, 8));
+    }
+  }
+
+  #[test]
+  fn overdraw_counts_the_hidden_fragments() {
+    // 1/8 of a unit per pixel, the far square overlaps the right half of the
+    // near one
+    let obj = format!(
+      "mtllib scene.mtl\n{}{}",
+      quad((-0.75, -0.5), (0.25, 0.5), -5.0, 1),
+      quad((-0.25, -0.5), (0.75, 0.5), -6.0, 5),
+    );
+    let scene = scene("overdraw", &obj, "");
+
+    let mut renderer = Renderer::new(16, 16);
+    renderer.camera.update_orthographic(1.0, 10.0, 1.0);
+    renderer.set_debug_view(DebugView::Overdraw);
+    renderer.render(&scene, Mat4::identity(), &flat_material());
+    let color = renderer.take_hdr_color();
 
-    std::mem::replace(&mut self.color, ColorBuffer::new(w, h))
+    // blue for one fragment, cyan for two
+    assert_color(color.get(8, 8), Vec3::new(0.0, 1.0, 1.0));
+    assert_color(color.get(3, 8), Vec3::new(0.0, 0.0, 1.0));
+    assert_color(color.get(13, 8), Vec3::new(0.0, 0.0, 1.0));
+    assert_color(color.get(15, 8), Vec3::zero());
   }
 }
@@ -1,15 +1,24 @@
-use math::{Vec2, Vec3, Vec4};
-use crate::obj_loader::shader::{take_value, Extract, GLTypes, Shader};
+use math::{Mat4, Vec2, Vec3, Vec4};
+use crate::obj_loader::shader::{take_value, uniform, Extract, GLTypes, Shader};
+use crate::renderer::light::lights;
 
-pub fn make_gouraud_shader(light_dir: Vec3) -> Shader {
+/// Lambertian lighting per vertex, summed over the lights of the renderer.
+pub fn make_gouraud_shader() -> Shader {
   let mut shader = Shader::default();
   let default_vertex = shader.vertex;
   shader.vertex = Box::new(move |gl_vertex, uniforms, varying| {
     if let Some(normal) = gl_vertex.normal {
-      varying.set(
-        "light-intense",
-        GLTypes::Float(normal.dot(&light_dir.normalize()).max(0.0)),
-      )
+      let model_matrix = uniform!(uniforms, Mat4, "model_matrix", !);
+      let n = (model_matrix * Vec4::from_vec3(&normal, 0.0))
+        .truncated_to_vec3()
+        .normalize();
+      let p = (model_matrix * gl_vertex.position).truncated_to_vec3();
+
+      let intense = lights(uniforms).fold(Vec3::zero(), |sum, light| {
+        let (l, radiance) = light.incident(&p);
+        sum + radiance * n.dot(&l).max(0.0)
+      });
+      varying.set("light-intense", GLTypes::Vec3(intense))
     }
 
     if let Some(uv) = gl_vertex.texture {
@@ -19,22 +28,26 @@ pub fn make_gouraud_shader(light_dir: Vec3) -> Shader {
     default_vertex(gl_vertex, uniforms, varying)
   });
 
-  shader.fragment = Box::new(|_, varying, textures| {
+  shader.fragment = Box::new(|uniforms, varying, _| {
     let s = varying
       .get("light-intense")
-      .map_or(None as Option<f32>, |v| v.extract())
-      .map_or(1.0, |v| v.min(1.0));
+      .map_or(None as Option<Vec3>, |v| v.extract())
+      .unwrap_or(Vec3::new(1.0, 1.0, 1.0));
 
     let vUv = varying
       .get("vUv")
       .map_or(None as Option<Vec2>, |v| v.extract());
 
-    if let (Some(texture), Some(uv)) = (textures.get_texture_by_id(0), vUv) {
-      let mut res = texture.get_pixel(uv) * s;
-      res.w = 1.0;
-      res
+    if let (Some(texture), Some(uv)) = (uniforms.get_sampler("diffuse"), vUv) {
+      let mut res = texture
+        .sample(uv, varying.get_uv_derivatives())
+        .truncated_to_vec3();
+      res *= s;
+      Vec4::from_vec3(&res, 1.0)
     } else {
-      Vec4::new(s, s, s, 1.0)
+      let mut kd = uniform!(uniforms, Vec3, "Kd").unwrap_or(Vec3::new(1.0, 1.0, 1.0));
+      kd *= s;
+      Vec4::from_vec3(&kd, 1.0)
     }
 
     // let s = if s > 0.85 {
@@ -1,4 +1,5 @@
 pub mod gouraud;
+pub mod pbr;
 pub mod phong;
 pub mod shadow;
 pub mod wireframe;
\ No newline at end of file
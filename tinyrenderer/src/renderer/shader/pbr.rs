@@ -0,0 +1,266 @@
+use std::f32::consts::PI;
+
+use crate::{
+  obj_loader::shader::{uniform, varying, Extract, GLTypes, Shader, Uniform, Varying},
+  renderer::light::lights,
+};
+use math::{fresnel::schlick, Mat4, Vec2, Vec3, Vec4};
+
+/// the reflectance of the dielectrics at normal incidence
+const DIELECTRIC_F0: f32 = 0.04;
+
+/// The surface properties of a fragment, the textures of the material
+/// modulate its coefficients.
+struct Surface {
+  base_color: Vec4,
+  metallic: f32,
+  roughness: f32,
+  emissive: Vec3,
+  ambient: Vec3,
+}
+
+/// `Kd`, `Pm`, `Pr`, `Ke` and `Ka` of the MTL material, with `map_Kd`,
+/// `map_Pm`, `map_Pr` and `map_Ke`. The metallic and roughness maps are read
+/// from their red channel and scaled by their coefficient, a map without its
+/// coefficient is used as is.
+fn surface(uniforms: &Uniform, uv: Option<Vec2>, derivatives: Option<(Vec2, Vec2)>) -> Surface {
+  let sample = |name: &str| {
+    uniforms
+      .get_sampler(name)
+      .zip(uv)
+      .map(|(texture, uv)| texture.sample(uv, derivatives))
+  };
+  let scalar = |key: &str, map: &str, default: f32| {
+    let factor = uniform!(uniforms, f32, key);
+    match sample(map) {
+      Some(texel) => factor.unwrap_or(1.0) * texel.x,
+      None => factor.unwrap_or(default),
+    }
+  };
+
+  let kd = uniform!(uniforms, Vec3, "Kd").unwrap_or(Vec3::new(1.0, 1.0, 1.0));
+  // `Ka` is a reflectance, the light comes from the renderer
+  let ka = uniform!(uniforms, Vec3, "Ka").unwrap_or(Vec3::new(1.0, 1.0, 1.0));
+  let ambient_light = uniform!(uniforms, Vec3, "ambient_light").unwrap_or(Vec3::zero());
+  let ke = uniform!(uniforms, Vec3, "Ke");
+  let emissive = match sample("emissive") {
+    Some(texel) => multiply(
+      &ke.unwrap_or(Vec3::new(1.0, 1.0, 1.0)),
+      &texel.truncated_to_vec3(),
+    ),
+    None => ke.unwrap_or(Vec3::zero()),
+  };
+
+  Surface {
+    base_color: sample("diffuse").unwrap_or(Vec4::from_vec3(&kd, 1.0)),
+    metallic: scalar("Pm", "metallic", 0.0).clamp(0.0, 1.0),
+    // perfectly smooth surfaces would reflect the punctual lights as points
+    roughness: scalar("Pr", "roughness", 0.5).clamp(0.04, 1.0),
+    emissive,
+    ambient: multiply(&ka, &ambient_light),
+  }
+}
+
+fn multiply(a: &Vec3, b: &Vec3) -> Vec3 {
+  Vec3::new(a.x * b.x, a.y * b.y, a.z * b.z)
+}
+
+/// the GGX / Trowbridge-Reitz distribution of the microfacet normals
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+  let a2 = roughness.powi(4);
+  let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+  a2 / (PI * d * d)
+}
+
+/// Smith's shadowing-masking with the Schlick-GGX term of each direction,
+/// remapped for the punctual lights
+fn geometry_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+  let k = (roughness + 1.0).powi(2) / 8.0;
+  let g1 = |cosine: f32| cosine / (cosine * (1.0 - k) + k);
+  g1(n_dot_v) * g1(n_dot_l)
+}
+
+/// The share of the radiance arriving along `l` that is reflected towards `v`,
+/// cosine weighted. `n`, `v` and `l` are unit vectors, `v` above the surface.
+fn cook_torrance(surface: &Surface, n: &Vec3, v: &Vec3, l: &Vec3) -> Vec3 {
+  let n_dot_l = n.dot(l);
+  if n_dot_l <= 0.0 {
+    return Vec3::zero();
+  }
+  let n_dot_v = n.dot(v);
+
+  let base = surface.base_color.truncated_to_vec3();
+  let f0 =
+    base * surface.metallic + Vec3::new(1.0, 1.0, 1.0) * (DIELECTRIC_F0 * (1.0 - surface.metallic));
+
+  let h = (*l + *v).normalize();
+  let n_dot_h = n.dot(&h).max(0.0);
+  let h_dot_v = h.dot(v).max(0.0);
+
+  let fresnel = Vec3::new(
+    schlick(h_dot_v, f0.x),
+    schlick(h_dot_v, f0.y),
+    schlick(h_dot_v, f0.z),
+  );
+  let d = distribution_ggx(n_dot_h, surface.roughness);
+  let g = geometry_smith(n_dot_v, n_dot_l, surface.roughness);
+  let specular = fresnel * (d * g / (4.0 * n_dot_v * n_dot_l));
+
+  // what is not reflected is refracted, and the metals absorb it
+  let kd = (Vec3::new(1.0, 1.0, 1.0) - fresnel) * (1.0 - surface.metallic);
+  let diffuse = multiply(&kd, &base) * (1.0 / PI);
+
+  (diffuse + specular) * n_dot_l
+}
+
+/// The world space normal of the fragment, perturbed by the tangent space
+/// normal map (`map_Bump`) or the object space one (`norm`) when the material
+/// has them.
+fn shading_normal(uniforms: &Uniform, varyings: &Varying) -> Vec3 {
+  let model_matrix = uniform!(uniforms, Mat4, "model_matrix", !);
+  let to_world = |v: Vec3| (model_matrix * Vec4::from_vec3(&v, 0.0)).truncated_to_vec3();
+
+  let uv = varying!(varyings, Vec2, "vUv");
+  let normal = varying!(varyings, Vec3, "normal").map(|n| n.normalize());
+  let tangent = varying!(varyings, Vec4, "tangent");
+
+  let sample = |name: &str| -> Option<Vec3> {
+    let texel = uniforms.get_sampler(name)?.get_pixel(uv?);
+    Some(texel.truncated_to_vec3() * 2.0 - 1.0)
+  };
+
+  let n = match (normal, tangent, sample("bump"), sample("norm")) {
+    (Some(bn), Some(tangent), Some(nn), _) => {
+      // the tangent is orthogonalized again after the interpolation
+      let t = tangent.truncated_to_vec3();
+      let t = (t - bn * bn.dot(&t)).normalize();
+      let b = bn.cross(&t) * tangent.w;
+      t * nn.x + b * nn.y + bn * nn.z
+    }
+    (_, _, _, Some(nn)) => nn,
+    (Some(bn), ..) => bn,
+    _ => return Vec3::zero(),
+  };
+
+  to_world(n).normalize()
+}
+
+/// Physically based shading with the metallic-roughness workflow, a Cook-Torrance
+/// specular lobe made of the GGX distribution, Smith's geometry term and
+/// Schlick's Fresnel over a Lambertian diffuse. The output is linear, without
+/// any tone mapping.
+///
+/// Lit by the lights and the ambient light of the renderer. The fragments
+/// without a normal only get their ambient and emissive terms.
+pub fn make_pbr_shader() -> Shader {
+  let mut shader = Shader::default();
+  let default_vertex = shader.vertex;
+
+  shader.vertex = Box::new(move |gl_vertex, uniforms, varyings| {
+    let model_matrix = uniform!(uniforms, Mat4, "model_matrix", !);
+
+    if let Some(uv) = gl_vertex.texture {
+      varyings.set("vUv", GLTypes::Vec2(uv));
+    }
+
+    if let Some(n) = gl_vertex.normal {
+      varyings.set("normal", GLTypes::Vec3(n));
+    }
+
+    if let Some(t) = gl_vertex.tangent {
+      varyings.set("tangent", GLTypes::Vec4(t));
+    }
+
+    let world_position = model_matrix * gl_vertex.position;
+    varyings.set("world_position", GLTypes::Vec4(world_position));
+
+    default_vertex(gl_vertex, uniforms, varyings)
+  });
+
+  shader.fragment = Box::new(move |uniforms, varyings, _| {
+    let uv = varying!(varyings, Vec2, "vUv");
+    let world_position = varying!(varyings, Vec4, "world_position", !).truncated_to_vec3();
+    let camera_position = uniform!(uniforms, Vec3, "camera_position", !);
+
+    let surface = surface(uniforms, uv, varyings.get_uv_derivatives());
+    let base = surface.base_color.truncated_to_vec3();
+    let mut color = multiply(&surface.ambient, &base) + surface.emissive;
+
+    let n = shading_normal(uniforms, varyings);
+    let v = (camera_position - world_position).normalize();
+
+    if n.length_square() > 0.0 && n.dot(&v) > 0.0 {
+      for light in lights(uniforms) {
+        let (l, radiance) = light.incident(&world_position);
+        color += multiply(&cook_torrance(&surface, &n, &v, &l), &radiance);
+      }
+    }
+
+    Vec4::from_vec3(&color, surface.base_color.w)
+  });
+
+  shader
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn rough(base: Vec3, metallic: f32) -> Surface {
+    Surface {
+      base_color: Vec4::from_vec3(&base, 1.0),
+      metallic,
+      roughness: 1.0,
+      emissive: Vec3::zero(),
+      ambient: Vec3::zero(),
+    }
+  }
+
+  #[track_caller]
+  fn assert_vec3(actual: Vec3, expected: Vec3) {
+    assert!(
+      (actual - expected).length() < 1e-5,
+      "{:?} != {:?}",
+      actual,
+      expected
+    );
+  }
+
+  #[test]
+  fn cook_torrance_at_normal_incidence() {
+    let n = Vec3::new(0.0, 0.0, 1.0);
+    let white = Vec3::new(1.0, 1.0, 1.0);
+
+    // h = n: D = 1 / PI for the roughest surface, G = 1 and F = f0
+    let dielectric = cook_torrance(&rough(white, 0.0), &n, &n, &n);
+    let expected = (1.0 - DIELECTRIC_F0) / PI + DIELECTRIC_F0 / (4.0 * PI);
+    assert_vec3(dielectric, white * expected);
+
+    // the metals only reflect, tinted by their base color
+    let gold = Vec3::new(1.0, 0.5, 0.25);
+    let metal = cook_torrance(&rough(gold, 1.0), &n, &n, &n);
+    assert_vec3(metal, gold * (1.0 / (4.0 * PI)));
+  }
+
+  #[test]
+  fn cook_torrance_at_grazing_light() {
+    let n = Vec3::new(0.0, 0.0, 1.0);
+    let white = Vec3::new(1.0, 1.0, 1.0);
+    // n.l = 0.5, the half vector is 30 degrees away from n and v
+    let l = Vec3::new(0.75f32.sqrt(), 0.0, 0.5);
+
+    let fresnel = schlick(0.75f32.sqrt(), DIELECTRIC_F0);
+    // D = 1 / PI, G = 1 * (0.5 / (0.5 * 0.5 + 0.5))
+    let specular = fresnel * (2.0 / 3.0) / (4.0 * PI * 0.5);
+    let diffuse = (1.0 - fresnel) / PI;
+    let dielectric = cook_torrance(&rough(white, 0.0), &n, &n, &l);
+    assert_vec3(dielectric, white * ((diffuse + specular) * 0.5));
+
+    // nothing comes from below the surface
+    let below = Vec3::new(0.0, 0.6, -0.8);
+    assert_vec3(
+      cook_torrance(&rough(white, 0.0), &n, &n, &below),
+      Vec3::zero(),
+    );
+  }
+}
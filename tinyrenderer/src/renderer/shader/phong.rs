@@ -1,31 +1,54 @@
-use crate::obj_loader::shader::{uniform, varying, Extract, GLTypes, Shader};
-use math::{Mat3, Mat4, Vec2, Vec3, Vec4};
+use crate::{
+  obj_loader::shader::{uniform, varying, Extract, GLTypes, Shader, Uniform},
+  renderer::{
+    light::{lights, Light},
+    shadow_map::sample_shadow,
+  },
+};
+use math::{color::linear_to_srgb, Mat4, Vec2, Vec3, Vec4};
+
+/// `Kd` of the material when there is no diffuse texture
+fn base_color(uniforms: &Uniform) -> Vec4 {
+  let kd = uniform!(uniforms, Vec3, "Kd").unwrap_or(Vec3::new(1.0, 1.0, 1.0));
+  Vec4::from_vec3(&kd, 1.0)
+}
 
-pub fn make_phong_shader(light_dir: Vec3) -> Shader {
+/// Phong shading with an object space normal map, bound through `norm`, and a
+/// specular map, lit in view space by the lights of the renderer.
+pub fn make_phong_shader() -> Shader {
   let mut shader = Shader::default();
   let default_vertex = shader.vertex;
 
   shader.vertex = Box::new(move |gl_vertex, uniforms, varyings| {
+    let model_matrix = uniform!(uniforms, Mat4, "model_matrix", !);
+    let view_matrix = uniform!(uniforms, Mat4, "view_matrix", !);
+
     if let Some(uv) = gl_vertex.texture {
       varyings.set("vUv", GLTypes::Vec2(uv));
     }
 
+    let view_position = view_matrix * model_matrix * gl_vertex.position;
+    varyings.set("view_position", GLTypes::Vec4(view_position));
+
     default_vertex(gl_vertex, uniforms, varyings)
   });
 
-  shader.fragment = Box::new(move |uniforms, varyings, textures| {
+  shader.fragment = Box::new(move |uniforms, varyings, _| {
     let vUv = varying!(varyings, Vec2, "vUv");
     let mv_it = uniform!(uniforms, Mat4, "mv_it", !);
-    let mut color = Vec4::new(1.0, 1.0, 1.0, 1.0);
+    let view_matrix = uniform!(uniforms, Mat4, "view_matrix", !);
+    let p = varying!(varyings, Vec4, "view_position", !).truncated_to_vec3();
+    let mut color = base_color(uniforms);
 
     if let Some(uv) = vUv {
-      if let Some(diffuse) = textures.get_texture_by_id(0) {
-        color = diffuse.get_pixel(uv);
+      if let Some(diffuse) = uniforms.get_sampler("diffuse") {
+        color = diffuse.sample(uv, varyings.get_uv_derivatives());
       }
 
-      let t = textures.get_texture_by_ids(vec![1, 3]);
+      let normal = uniforms.get_sampler("norm");
+      let specular = uniforms.get_sampler("specular_color");
 
-      if let (Some(normal), Some(specular)) = (t[0], t[1]) {
+      if let (Some(normal), Some(specular)) = (normal, specular) {
         let mut nn = normal.get_pixel(uv);
         nn = nn * 2.0 - 1.0;
         // bgr ---> zyx ---> xyz
@@ -33,19 +56,24 @@ pub fn make_phong_shader(light_dir: Vec3) -> Shader {
 
         // dbg!(*mit);
         let n = (mv_it * nn).truncated_to_vec3().normalize();
-        let l = light_dir.normalize();
+        // the exponents are stored in the specular color map, which is
+        // decoded from sRGB, encoding it back gives the stored value
+        let shininess = linear_to_srgb(specular.get_pixel(uv).z) * 255.0;
+
+        let lit = lights(uniforms).fold(Vec3::zero(), |sum, light| {
+          let (l, radiance) = light.transform(&view_matrix).incident(&p);
 
-        let r = (n * (n.dot(&l) * 2.0) - l).normalize();
-        let spec_strength = r.z.max(0.0).powf(specular.get_pixel(uv).z * 255.0);
+          let r = (n * (n.dot(&l) * 2.0) - l).normalize();
+          let spec_strength = r.z.max(0.0).powf(shininess);
 
-        let intense = n.dot(&l).max(0.0);
+          let intense = n.dot(&l).max(0.0);
+          sum + radiance * (intense + 1.6 * spec_strength)
+        });
 
-        color = Vec4::new(
-          (5.0 + color.x * 255.0 * (intense + 1.6 * spec_strength)).min(255.0) / 255.0,
-          (5.0 + color.y * 255.0 * (intense + 1.6 * spec_strength)).min(255.0) / 255.0,
-          (5.0 + color.z * 255.0 * (intense + 1.6 * spec_strength)).min(255.0) / 255.0,
-          color.w,
-        );
+        let mut rgb = color.truncated_to_vec3();
+        rgb *= lit;
+        // a faint ambient term
+        color = Vec4::from_vec3(&(rgb + 5.0 / 255.0), color.w);
       }
     }
 
@@ -55,77 +83,139 @@ pub fn make_phong_shader(light_dir: Vec3) -> Shader {
   shader
 }
 
-pub fn make_phong_shader_with_tangent_normal_map(light_dir: Vec3) -> Shader {
+/// Phong shading with a tangent space normal map, bound through `map_Bump`.
+/// The tangent frames come from the model, see `Vertex::tangent`.
+pub fn make_phong_shader_with_tangent_normal_map() -> Shader {
   let mut shader = Shader::default();
   let default_vertex = shader.vertex;
   shader.vertex = Box::new(move |gl_vertex, uniforms, varyings| {
-    let i = uniform!(uniforms, f32, "vertex_index", !);
+    let model_matrix = uniform!(uniforms, Mat4, "model_matrix", !);
+    let world_position = model_matrix * gl_vertex.position;
+    varyings.set("world_position", GLTypes::Vec4(world_position));
 
     if let Some(uv) = gl_vertex.texture {
       varyings.set("vUv", GLTypes::Vec2(uv));
-      varyings.set(&format!("uv_{}", i), GLTypes::Vec2(uv));
     }
 
     if let Some(n) = gl_vertex.normal {
       varyings.set("normal", GLTypes::Vec3(n));
     }
 
-    let v = default_vertex(gl_vertex, uniforms, varyings);
-    let p = v.position;
-
-    varyings.set(&format!("vertex_{}", i), GLTypes::Vec4(p / p.w));
+    if let Some(t) = gl_vertex.tangent {
+      varyings.set("tangent", GLTypes::Vec4(t));
+    }
 
-    v
+    default_vertex(gl_vertex, uniforms, varyings)
   });
 
-  shader.fragment = Box::new(move |uniforms, varyings, textures| {
+  shader.fragment = Box::new(move |uniforms, varyings, _| {
     let uv = varying!(varyings, Vec2, "vUv", !);
+    let model_matrix = uniform!(uniforms, Mat4, "model_matrix", !);
+    let p = varying!(varyings, Vec4, "world_position", !).truncated_to_vec3();
 
-    let mut color = Vec4::new(1.0, 1.0, 1.0, 1.0);
+    let mut color = base_color(uniforms);
 
-    if let Some(diffuse) = textures.get_texture_by_id(0) {
-      color = diffuse.get_pixel(uv);
+    if let Some(diffuse) = uniforms.get_sampler("diffuse") {
+      color = diffuse.sample(uv, varyings.get_uv_derivatives());
     }
 
-    let p0 = varying!(varyings, Vec4, "vertex_0", !);
-    let p1 = varying!(varyings, Vec4, "vertex_1", !);
-    let p2 = varying!(varyings, Vec4, "vertex_2", !);
+    let bn = varying!(varyings, Vec3, "normal", !).normalize();
+    let tangent = varying!(varyings, Vec4, "tangent");
+
+    let normal = uniforms.get_sampler("bump");
+    let specular = uniforms.get_sampler("specular_color");
+
+    if let (Some(normal), Some(_), Some(tangent)) = (normal, specular, tangent) {
+      // the tangent is orthogonalized again after the interpolation
+      let t = tangent.truncated_to_vec3();
+      let t = (t - bn * bn.dot(&t)).normalize();
+      let b = bn.cross(&t) * tangent.w;
+
+      // from tangent space, the columns of the TBN matrix
+      let nn = normal.get_pixel(uv).truncated_to_vec3() * 2.0 - 1.0;
+      let n = t * nn.x + b * nn.y + bn * nn.z;
+      let n = (model_matrix * Vec4::from_vec3(&n, 0.0))
+        .truncated_to_vec3()
+        .normalize();
+
+      let mut diff = lights(uniforms).fold(Vec3::zero(), |sum, light| {
+        let (l, radiance) = light.incident(&p);
+        sum + radiance * n.dot(&l).max(0.0)
+      });
+      diff *= color.truncated_to_vec3();
+      color = Vec4::from_vec3(&diff, 1.0);
+    };
 
-    let uv0 = varying!(varyings, Vec2, "uv_0", !);
-    let uv1 = varying!(varyings, Vec2, "uv_1", !);
-    let uv2 = varying!(varyings, Vec2, "uv_2", !);
+    color
+  });
 
-    let uv = varying!(varyings, Vec2, "vUv", !);
-    let bn = varying!(varyings, Vec3, "normal", !);
-    let mut A = Mat3::default();
+  shader
+}
 
-    A.set_col(0, (p1 - p0).truncated_to_vec3());
-    A.set_col(1, (p2 - p0).truncated_to_vec3());
-    A.set_col(2, bn);
+/// Phong shading lit in world space by the lights of the renderer, the first
+/// directional light looks the fragment up in the depth map of the shadow
+/// pass, see `Renderer::enable_shadow_map`.
+pub fn make_phong_shader_with_shadow() -> Shader {
+  let mut shader = Shader::default();
+  let default_vertex = shader.vertex;
 
-    let AI = A.inverse().unwrap();
+  shader.vertex = Box::new(move |gl_vertex, uniforms, varyings| {
+    let model_matrix = uniform!(uniforms, Mat4, "model_matrix", !);
 
-    let i: Vec3 = AI * Vec3::new(uv1.x - uv0.x, uv2.x - uv0.x, 0.0);
-    let j: Vec3 = AI * Vec3::new(uv1.y - uv0.y, uv2.y - uv0.y, 0.0);
+    if let Some(uv) = gl_vertex.texture {
+      varyings.set("vUv", GLTypes::Vec2(uv));
+    }
 
-    let mut B = Mat3::default();
-    B.set_col(0, i.normalize());
-    B.set_col(1, j.normalize());
-    B.set_col(2, bn);
+    if let Some(n) = gl_vertex.normal {
+      let world_normal = model_matrix * Vec4::from_vec3(&n, 0.0);
+      varyings.set("normal", GLTypes::Vec3(world_normal.truncated_to_vec3()));
+    }
 
-    let t = textures.get_texture_by_ids(vec![2, 3]);
+    let world_position = model_matrix * gl_vertex.position;
+    varyings.set("world_position", GLTypes::Vec4(world_position));
 
-    if let (Some(normal), Some(specular)) = (t[0], t[1]) {
-      let nn = normal.get_pixel(uv);
+    default_vertex(gl_vertex, uniforms, varyings)
+  });
 
-      // let mut n = Vec3::zero();
-      let n = B * nn.truncated_to_vec3();
-      let diff = n.normalize().dot(&light_dir).max(0.0);
-      color = color * diff;
-      color.w = 1.0;
-    };
+  shader.fragment = Box::new(move |uniforms, varyings, _| {
+    let uv = varying!(varyings, Vec2, "vUv");
+    let normal = varying!(varyings, Vec3, "normal");
+    let world_position = varying!(varyings, Vec4, "world_position", !);
 
-    color
+    let mut color = base_color(uniforms);
+    if let (Some(diffuse), Some(uv)) = (uniforms.get_sampler("diffuse"), uv) {
+      color = diffuse.sample(uv, varyings.get_uv_derivatives());
+    }
+
+    let camera_position = uniform!(uniforms, Vec3, "camera_position", !);
+    let p = world_position.truncated_to_vec3();
+    let v = (camera_position - p).normalize();
+    let normal = normal.map(|n| n.normalize());
+
+    // the shadow map is rendered from the first directional light only
+    let mut shadowed = false;
+    let lit = lights(uniforms).fold(Vec3::zero(), |sum, light| {
+      let shadow = match light {
+        Light::Directional { .. } if !shadowed => {
+          shadowed = true;
+          sample_shadow(uniforms, &world_position)
+        }
+        _ => 1.0,
+      };
+
+      let (l, radiance) = light.incident(&p);
+      let (intense, spec_strength) = match normal {
+        Some(n) => {
+          let r = (n * (n.dot(&l) * 2.0) - l).normalize();
+          (n.dot(&l).max(0.0), r.dot(&v).max(0.0).powf(20.0))
+        }
+        None => (1.0, 0.0),
+      };
+      sum + radiance * (shadow * (0.8 * intense + 0.4 * spec_strength))
+    });
+    let s = lit + 0.2;
+
+    Vec4::new(color.x * s.x, color.y * s.y, color.z * s.z, color.w)
   });
 
   shader
@@ -1,4 +1,5 @@
 use crate::obj_loader::shader::{uniform, varying, Extract, GLTypes, Shader};
+use crate::renderer::light::lights;
 use math::{Mat4, Vec3, Vec4};
 
 /// Renders the mesh with flat material color and black wireframe edges.
@@ -7,10 +8,12 @@ use math::{Mat4, Vec3, Vec4};
 /// `edge_width` are drawn black.
 ///
 /// Since the OBJ may not have normal data, this shader computes face normals
-/// from the three vertex positions and applies simple diffuse lighting.
-pub fn make_wireframe_shader(edge_width: f32, light_dir: Vec3) -> Shader {
+/// from the three vertex positions and applies Phong lighting from the lights
+/// of the renderer.
+pub fn make_wireframe_shader(edge_width: f32) -> Shader {
   let mut shader = Shader::default();
   let default_vertex = shader.vertex;
+  shader.per_corner = true;
 
   shader.vertex = Box::new(move |gl_vertex, uniforms, varyings| {
     let i = uniform!(uniforms, f32, "vertex_index", !);
@@ -33,7 +36,7 @@ pub fn make_wireframe_shader(edge_width: f32, light_dir: Vec3) -> Shader {
     default_vertex(gl_vertex, uniforms, varyings)
   });
 
-  shader.fragment = Box::new(move |_uniforms, varyings, _textures| {
+  shader.fragment = Box::new(move |uniforms, varyings, _textures| {
     let bary = varying!(varyings, Vec3, "bary", !);
 
     let on_edge = bary.x < edge_width || bary.y < edge_width || bary.z < edge_width;
@@ -54,38 +57,41 @@ pub fn make_wireframe_shader(edge_width: f32, light_dir: Vec3) -> Shader {
     let edge2 = v2 - v0;
     let normal = edge1.cross(&edge2).normalize();
 
-    // Material properties from sample_0001.mtl
-    let ka = Vec3::new(0.30, 0.22, 0.20); // ambient color
-    let kd = Vec3::new(0.85, 0.65, 0.55); // diffuse color
-    let ks = Vec3::new(0.15, 0.15, 0.15); // specular color
-    let ns = 25.0; // specular exponent (shininess)
+    // Material properties, defaulting to the ones of sample_0001.mtl
+    let ka = uniform!(uniforms, Vec3, "Ka").unwrap_or(Vec3::new(0.30, 0.22, 0.20)); // ambient color
+    let kd = uniform!(uniforms, Vec3, "Kd").unwrap_or(Vec3::new(0.85, 0.65, 0.55)); // diffuse color
+    let ks = uniform!(uniforms, Vec3, "Ks").unwrap_or(Vec3::new(0.15, 0.15, 0.15)); // specular color
+    let ns = uniform!(uniforms, f32, "Ns").unwrap_or(25.0); // specular exponent (shininess)
 
-    // Light direction (normalized)
-    let l = light_dir.normalize();
-
-    // View direction - assume camera at origin looking at object
+    // View direction
     // Use center of triangle as approximate surface point
     let center = (v0 + v1 + v2) / 3.0;
-    let view_dir = (center * -1.0).normalize();
+    let camera_position = uniform!(uniforms, Vec3, "camera_position", !);
+    let view_dir = (camera_position - center).normalize();
 
     // Ambient term
-    let ambient = ka;
-
-    // Diffuse term
-    let diff_intensity = normal.dot(&l).max(0.0);
-    let diffuse = kd * diff_intensity;
-
-    // Specular term (Phong reflection) - only when light faces surface
-    let specular = if diff_intensity > 0.0 {
-      let r = (normal * (2.0 * normal.dot(&l)) - l).normalize();
-      let spec_intensity = r.dot(&view_dir).max(0.0).powf(ns);
-      ks * spec_intensity
-    } else {
-      Vec3::new(0.0, 0.0, 0.0)
-    };
-
-    // Combine lighting
-    let color = ambient + diffuse + specular;
+    let mut color = ka;
+
+    for light in lights(uniforms) {
+      let (l, radiance) = light.incident(&center);
+
+      // Diffuse term
+      let diff_intensity = normal.dot(&l).max(0.0);
+      let mut diffuse = kd * diff_intensity;
+
+      // Specular term (Phong reflection) - only when light faces surface
+      let mut specular = if diff_intensity > 0.0 {
+        let r = (normal * (2.0 * normal.dot(&l)) - l).normalize();
+        let spec_intensity = r.dot(&view_dir).max(0.0).powf(ns);
+        ks * spec_intensity
+      } else {
+        Vec3::new(0.0, 0.0, 0.0)
+      };
+
+      diffuse *= radiance;
+      specular *= radiance;
+      color += diffuse + specular;
+    }
 
     Vec4::new(color.x, color.y, color.z, 1.0)
   });
@@ -0,0 +1,271 @@
+use super::{
+  camera::{Camera, Frustum},
+  debug::DebugView,
+  renderer::{draw_model, DrawState, Target, Viewport},
+  shader::shadow::make_shadow_shader,
+  states::CullMode,
+};
+use crate::{
+  data_array::{DepthBuffer, HdrColorBuffer},
+  model::Scene,
+  obj_loader::{
+    material::Textures,
+    shader::{uniform, Extract, GLTypes, GlTypeMap, Shader, Uniform},
+  },
+};
+use math::{BoundingBox, BoundingSphere, Mat4, Vec3, Vec4};
+
+/// Depth map of the scene seen from a directional light, a.k.a. lesson 7.
+///
+/// The light looks at the bounding sphere of the scene through an orthographic
+/// projection, `light_space_matrix` maps a world position straight onto the
+/// pixel and the depth stored in the map.
+pub struct ShadowMap {
+  size: u32,
+  bias: f32,
+  pcf_radius: u32,
+  viewport: Viewport,
+  shader: Shader,
+  color: HdrColorBuffer,
+  depth: DepthBuffer,
+  light_space_matrix: Mat4,
+}
+
+impl ShadowMap {
+  pub fn new(size: u32) -> Self {
+    Self {
+      size,
+      bias: 0.005,
+      pcf_radius: 0,
+      viewport: Viewport::new(0.0, 0.0, size as f32, size as f32),
+      shader: make_shadow_shader(),
+      color: HdrColorBuffer::new(size, size),
+      depth: DepthBuffer::new(size, size),
+      light_space_matrix: Mat4::identity(),
+    }
+  }
+
+  /// depth offset subtracted before the comparison, fights the shadow acne
+  pub fn set_bias(&mut self, bias: f32) {
+    self.bias = bias;
+  }
+
+  /// `0` gives hard shadows, `n` averages a (2n+1) x (2n+1) kernel of comparisons
+  pub fn set_pcf_radius(&mut self, radius: u32) {
+    self.pcf_radius = radius;
+  }
+
+  pub fn get_size(&self) -> u32 {
+    self.size
+  }
+
+  pub fn get_depth(&self) -> &DepthBuffer {
+    &self.depth
+  }
+
+  /// the grey scale output of the shadow shader, handy for debugging
+  pub fn get_color(&self) -> &HdrColorBuffer {
+    &self.color
+  }
+
+  pub fn get_light_space_matrix(&self) -> &Mat4 {
+    &self.light_space_matrix
+  }
+
+  /// `light_dir` points from the surface towards the light, the same as the
+  /// direction of a `Light::Directional`.
+  pub fn render(&mut self, scene: &Scene, model_matrix: Mat4, light_dir: Vec3, threads: usize) {
+    let (center, radius) = bounding_sphere(scene, &model_matrix);
+
+    let mut camera = Camera::new(1.0, 1.0);
+    camera.move_to(center + light_dir.normalize() * (radius * 2.0));
+    camera.lookat(center);
+    let view_matrix = *camera.get_view_matarix();
+    let frustum = Frustum::orthographic(radius, radius * 3.0, 1.0, radius);
+    let projection_matrix = *frustum.get_projection_matrix();
+    let viewport_matrix = *self.viewport.get_viewport_matrix();
+
+    self.light_space_matrix = viewport_matrix * projection_matrix * view_matrix;
+
+    let uniforms = GlTypeMap::from([
+      ("model_matrix".to_string(), GLTypes::Mat4(model_matrix)),
+      ("view_matrix".to_string(), GLTypes::Mat4(view_matrix)),
+      (
+        "projection_matrix".to_string(),
+        GLTypes::Mat4(projection_matrix),
+      ),
+      (
+        "viewport_matrix".to_string(),
+        GLTypes::Mat4(viewport_matrix),
+      ),
+    ]);
+
+    self.depth.clear(f32::MAX);
+    self.color.clear(&Vec4::new(1.0, 1.0, 1.0, 1.0));
+
+    let textures = Textures::default();
+    let state = DrawState {
+      viewport_matrix,
+      cull: CullMode::None,
+      blend: None,
+      dissolve: 1.0,
+      alpha_map: None,
+      shader: &self.shader,
+      textures: &textures,
+      uniforms: Default::default(),
+      samplers: Default::default(),
+      shadow_map: None,
+      threads,
+      debug_view: DebugView::None,
+      model_id: 0,
+    };
+
+    let mut target = Target::Pixels(&mut self.color, &mut self.depth);
+    for model in &scene.models {
+      draw_model(&state, model, &uniforms, &mut target);
+    }
+  }
+
+  /// Exposes the light space matrix and the filtering settings to the shaders.
+  pub fn upload_uniforms(&self, uniforms: &mut GlTypeMap) {
+    uniforms.insert(
+      "light_space_matrix".to_string(),
+      GLTypes::Mat4(self.light_space_matrix),
+    );
+    uniforms.insert("shadow_bias".to_string(), GLTypes::Float(self.bias));
+    uniforms.insert(
+      "shadow_pcf_radius".to_string(),
+      GLTypes::Int(self.pcf_radius as i32),
+    );
+  }
+}
+
+/// Fraction of light reaching `world_position`, `1.0` is fully lit.
+/// Always lit when the shadow pass is disabled.
+pub fn sample_shadow(uniforms: &Uniform, world_position: &Vec4) -> f32 {
+  let shadow_map = match uniforms.get_shadow_map() {
+    Some(map) => map,
+    None => return 1.0,
+  };
+
+  let light_space_matrix = uniform!(uniforms, Mat4, "light_space_matrix", !);
+  let bias = uniform!(uniforms, f32, "shadow_bias").unwrap_or(0.0);
+  let radius = uniform!(uniforms, i32, "shadow_pcf_radius").unwrap_or(0);
+
+  let p = light_space_matrix * *world_position;
+  let p = p / p.w;
+
+  // beyond the far plane of the light
+  if p.z > 1.0 {
+    return 1.0;
+  }
+
+  let (w, h) = (shadow_map.width() as i32, shadow_map.height() as i32);
+  // the texel holding the point, the map being sampled at the pixel centers
+  let (x, y) = (p.x.floor() as i32, p.y.floor() as i32);
+
+  let mut lit = 0;
+  let mut total = 0;
+  for dx in -radius..=radius {
+    for dy in -radius..=radius {
+      let sx = (x + dx).clamp(0, w - 1) as u32;
+      let sy = (y + dy).clamp(0, h - 1) as u32;
+      if p.z - bias <= shadow_map.get(sx, sy) {
+        lit += 1;
+      }
+      total += 1;
+    }
+  }
+
+  lit as f32 / total as f32
+}
+
+/// the union of the bounding spheres of the models, in world space
+fn bounding_sphere(scene: &Scene, model_matrix: &Mat4) -> (Vec3, f32) {
+  let spheres: Vec<BoundingSphere> = scene
+    .models
+    .iter()
+    .filter_map(|model| model.get_bounding_sphere())
+    .map(|sphere| sphere.transform(model_matrix))
+    .collect();
+
+  let bounds = spheres.iter().flat_map(|s| {
+    let extent = Vec3::new(s.radius, s.radius, s.radius);
+    [s.center - extent, s.center + extent]
+  });
+  let Some(bounds) = BoundingBox::from_points(&bounds.collect::<Vec<_>>()) else {
+    return (Vec3::zero(), 1.0);
+  };
+
+  let center = bounds.center();
+  let radius = spheres
+    .iter()
+    .map(|s| (s.center - center).length() + s.radius)
+    .fold(0.0, f32::max);
+  (center, radius.max(1e-3))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_utils::{quad, scene};
+
+  /// the light shining along -z on a ground at z = 0, under a square at z = 1
+  fn occluded_ground(name: &str) -> Scene {
+    let obj = format!(
+      "{}{}",
+      quad((-2.0, -2.0), (2.0, 2.0), 0.0, 1),
+      quad((-0.5, -0.5), (0.5, 0.5), 1.0, 5),
+    );
+    scene(name, &obj, "")
+  }
+
+  fn shadow_at(shadow_map: &ShadowMap, points: &[Vec4]) -> Vec<f32> {
+    let mut globals = GlTypeMap::new();
+    shadow_map.upload_uniforms(&mut globals);
+    let mut uniforms = Uniform::new(&globals, GlTypeMap::new());
+    uniforms.set_shadow_map(shadow_map.get_depth());
+
+    points.iter().map(|p| sample_shadow(&uniforms, p)).collect()
+  }
+
+  /// along the x axis of the ground, from the center of the square to the lit
+  /// ground
+  fn shadow_profile(shadow_map: &ShadowMap) -> Vec<f32> {
+    let points: Vec<Vec4> = (0..=100)
+      .map(|i| Vec4::new(i as f32 / 100.0, 0.0, 0.0, 1.0))
+      .collect();
+    shadow_at(shadow_map, &points)
+  }
+
+  #[test]
+  fn square_shadows_the_ground() {
+    let scene = occluded_ground("shadow-map-hard");
+    let mut shadow_map = ShadowMap::new(64);
+    shadow_map.render(&scene, Mat4::identity(), Vec3::new(0.0, 0.0, 1.0), 1);
+
+    let profile = shadow_profile(&shadow_map);
+    assert_eq!(profile[0], 0.0);
+    assert_eq!(profile[100], 1.0);
+    assert!(profile.iter().all(|s| *s == 0.0 || *s == 1.0));
+
+    // the square itself is lit
+    let top = Vec4::new(0.0, 0.0, 1.0, 1.0);
+    assert_eq!(shadow_at(&shadow_map, &[top]), [1.0]);
+  }
+
+  #[test]
+  fn pcf_softens_the_edge() {
+    let scene = occluded_ground("shadow-map-pcf");
+    let mut shadow_map = ShadowMap::new(64);
+    shadow_map.set_pcf_radius(1);
+    shadow_map.render(&scene, Mat4::identity(), Vec3::new(0.0, 0.0, 1.0), 1);
+
+    let profile = shadow_profile(&shadow_map);
+    assert_eq!(profile[0], 0.0);
+    assert_eq!(profile[100], 1.0);
+    assert!(profile.iter().any(|s| *s > 0.0 && *s < 1.0));
+    // darker towards the center of the shadow
+    assert!(profile.windows(2).all(|w| w[0] <= w[1]));
+  }
+}
@@ -0,0 +1,270 @@
+use math::{Vec2, Vec4};
+
+/// Which faces get discarded before rasterization. The facing is decided by
+/// the winding of the triangle on screen, counter-clockwise in NDC is front.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CullMode {
+  #[default]
+  None,
+  Back,
+  Front,
+}
+
+impl CullMode {
+  /// `vertices` are in screen space, i.e. after the viewport transform.
+  pub fn should_cull(&self, vertices: &[Vec2; 3]) -> bool {
+    let [a, b, c] = vertices;
+    let signed_area = (*b - *a).cross(&(*c - *a));
+
+    // the viewport flips the y axis, so the front faces turn clockwise on screen
+    let front_facing = signed_area < 0.0;
+
+    match self {
+      CullMode::None => false,
+      CullMode::Back => !front_facing,
+      CullMode::Front => front_facing,
+    }
+  }
+}
+
+/// Weight applied to the source (fragment) or destination (color buffer)
+/// color before they are combined by the `BlendEquation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendFactor {
+  Zero,
+  One,
+  SrcColor,
+  OneMinusSrcColor,
+  DstColor,
+  OneMinusDstColor,
+  SrcAlpha,
+  OneMinusSrcAlpha,
+  DstAlpha,
+  OneMinusDstAlpha,
+}
+
+impl BlendFactor {
+  fn weight(&self, src: &Vec4, dst: &Vec4) -> Vec4 {
+    match self {
+      BlendFactor::Zero => Vec4::new(0.0, 0.0, 0.0, 0.0),
+      BlendFactor::One => Vec4::new(1.0, 1.0, 1.0, 1.0),
+      BlendFactor::SrcColor => *src,
+      BlendFactor::OneMinusSrcColor => Vec4::new(1.0, 1.0, 1.0, 1.0) - *src,
+      BlendFactor::DstColor => *dst,
+      BlendFactor::OneMinusDstColor => Vec4::new(1.0, 1.0, 1.0, 1.0) - *dst,
+      BlendFactor::SrcAlpha => Vec4::new(src.w, src.w, src.w, src.w),
+      BlendFactor::OneMinusSrcAlpha => Vec4::new(1.0, 1.0, 1.0, 1.0) - src.w,
+      BlendFactor::DstAlpha => Vec4::new(dst.w, dst.w, dst.w, dst.w),
+      BlendFactor::OneMinusDstAlpha => Vec4::new(1.0, 1.0, 1.0, 1.0) - dst.w,
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BlendEquation {
+  #[default]
+  Add,
+  Subtract,
+  ReverseSubtract,
+  Min,
+  Max,
+}
+
+/// Mirrors the OpenGL blend state,
+/// `result = equation(src * src_factor, dst * dst_factor)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlendState {
+  pub src_factor: BlendFactor,
+  pub dst_factor: BlendFactor,
+  pub equation: BlendEquation,
+}
+
+impl Default for BlendState {
+  fn default() -> Self {
+    Self::over()
+  }
+}
+
+impl BlendState {
+  pub fn new(src_factor: BlendFactor, dst_factor: BlendFactor, equation: BlendEquation) -> Self {
+    Self {
+      src_factor,
+      dst_factor,
+      equation,
+    }
+  }
+
+  /// the classic "over" operator for straight alpha
+  pub fn over() -> Self {
+    Self::new(
+      BlendFactor::SrcAlpha,
+      BlendFactor::OneMinusSrcAlpha,
+      BlendEquation::Add,
+    )
+  }
+
+  pub fn additive() -> Self {
+    Self::new(BlendFactor::SrcAlpha, BlendFactor::One, BlendEquation::Add)
+  }
+
+  pub fn multiply() -> Self {
+    Self::new(BlendFactor::DstColor, BlendFactor::Zero, BlendEquation::Add)
+  }
+
+  pub fn blend(&self, src: &Vec4, dst: &Vec4) -> Vec4 {
+    let s = mul(src, &self.src_factor.weight(src, dst));
+    let d = mul(dst, &self.dst_factor.weight(src, dst));
+
+    match self.equation {
+      BlendEquation::Add => s + d,
+      BlendEquation::Subtract => s - d,
+      BlendEquation::ReverseSubtract => d - s,
+      // min & max ignore the factors, as OpenGL does
+      BlendEquation::Min => Vec4::new(
+        src.x.min(dst.x),
+        src.y.min(dst.y),
+        src.z.min(dst.z),
+        src.w.min(dst.w),
+      ),
+      BlendEquation::Max => Vec4::new(
+        src.x.max(dst.x),
+        src.y.max(dst.y),
+        src.z.max(dst.z),
+        src.w.max(dst.w),
+      ),
+    }
+  }
+}
+
+/// component-wise product
+fn mul(a: &Vec4, b: &Vec4) -> Vec4 {
+  Vec4::new(a.x * b.x, a.y * b.y, a.z * b.z, a.w * b.w)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // the windings are the ones of the screen coordinates, where y grows
+  // downwards: counter-clockwise on screen is clockwise in NDC
+  fn ccw() -> [Vec2; 3] {
+    [
+      Vec2::new(0.0, 0.0),
+      Vec2::new(10.0, 0.0),
+      Vec2::new(0.0, 10.0),
+    ]
+  }
+
+  fn cw() -> [Vec2; 3] {
+    [
+      Vec2::new(0.0, 0.0),
+      Vec2::new(0.0, 10.0),
+      Vec2::new(10.0, 0.0),
+    ]
+  }
+
+  #[test]
+  fn ccw_on_screen_is_back_facing() {
+    assert!(CullMode::Back.should_cull(&ccw()));
+    assert!(!CullMode::Front.should_cull(&ccw()));
+    assert!(!CullMode::None.should_cull(&ccw()));
+  }
+
+  #[test]
+  fn cw_on_screen_is_front_facing() {
+    assert!(!CullMode::Back.should_cull(&cw()));
+    assert!(CullMode::Front.should_cull(&cw()));
+    assert!(!CullMode::None.should_cull(&cw()));
+  }
+
+  #[track_caller]
+  fn assert_color(actual: Vec4, expected: Vec4) {
+    assert!(
+      (actual - expected).length() < 1e-6,
+      "{:?} != {:?}",
+      actual,
+      expected
+    );
+  }
+
+  #[test]
+  fn over_weights_by_the_source_alpha() {
+    let src = Vec4::new(1.0, 0.0, 0.0, 0.25);
+    let dst = Vec4::new(0.0, 0.0, 1.0, 1.0);
+    assert_color(
+      BlendState::over().blend(&src, &dst),
+      Vec4::new(0.25, 0.0, 0.75, 0.8125),
+    );
+
+    // an opaque source replaces the destination
+    let opaque = Vec4::new(0.5, 0.5, 0.5, 1.0);
+    assert_color(BlendState::over().blend(&opaque, &dst), opaque);
+  }
+
+  #[test]
+  fn additive_keeps_the_destination() {
+    let src = Vec4::new(0.5, 0.5, 0.0, 0.5);
+    let dst = Vec4::new(0.25, 0.0, 0.0, 1.0);
+    assert_color(
+      BlendState::additive().blend(&src, &dst),
+      Vec4::new(0.5, 0.25, 0.0, 1.25),
+    );
+  }
+
+  #[test]
+  fn multiply_and_subtract() {
+    let src = Vec4::new(0.5, 1.0, 0.25, 1.0);
+    let dst = Vec4::new(0.5, 0.5, 1.0, 1.0);
+    assert_color(
+      BlendState::multiply().blend(&src, &dst),
+      Vec4::new(0.25, 0.5, 0.25, 1.0),
+    );
+
+    let one = |equation| BlendState::new(BlendFactor::One, BlendFactor::One, equation);
+    assert_color(
+      one(BlendEquation::Subtract).blend(&src, &dst),
+      Vec4::new(0.0, 0.5, -0.75, 0.0),
+    );
+    assert_color(
+      one(BlendEquation::ReverseSubtract).blend(&src, &dst),
+      Vec4::new(0.0, -0.5, 0.75, 0.0),
+    );
+  }
+
+  #[test]
+  fn min_and_max_ignore_the_factors() {
+    let src = Vec4::new(0.2, 0.8, 0.5, 1.0);
+    let dst = Vec4::new(0.6, 0.4, 0.5, 0.5);
+    let zero = |equation| BlendState::new(BlendFactor::Zero, BlendFactor::Zero, equation);
+
+    assert_color(
+      zero(BlendEquation::Min).blend(&src, &dst),
+      Vec4::new(0.2, 0.4, 0.5, 0.5),
+    );
+    assert_color(
+      zero(BlendEquation::Max).blend(&src, &dst),
+      Vec4::new(0.6, 0.8, 0.5, 1.0),
+    );
+  }
+
+  #[test]
+  fn factors_of_the_destination() {
+    let src = Vec4::new(1.0, 1.0, 1.0, 1.0);
+    let dst = Vec4::new(0.5, 0.25, 0.0, 0.5);
+    let blend = |src_factor| BlendState::new(src_factor, BlendFactor::Zero, BlendEquation::Add);
+
+    assert_color(blend(BlendFactor::DstColor).blend(&src, &dst), dst);
+    assert_color(
+      blend(BlendFactor::OneMinusDstColor).blend(&src, &dst),
+      Vec4::new(0.5, 0.75, 1.0, 0.5),
+    );
+    assert_color(
+      blend(BlendFactor::DstAlpha).blend(&src, &dst),
+      Vec4::new(0.5, 0.5, 0.5, 0.5),
+    );
+    assert_color(
+      blend(BlendFactor::OneMinusDstAlpha).blend(&src, &dst),
+      Vec4::new(0.5, 0.5, 0.5, 0.5),
+    );
+  }
+}
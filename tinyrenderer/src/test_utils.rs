@@ -0,0 +1,76 @@
+//! The scenes of the tests, written as OBJ files and loaded the way the
+//! renderer loads them.
+
+use std::path::PathBuf;
+
+use crate::{
+  model::{from_obj_path, Scene},
+  obj_loader::{
+    material::Material,
+    shader::{uniform, Extract, Shader},
+  },
+};
+use math::{Vec3, Vec4};
+
+/// An empty directory of the test `name`, the tests running in parallel each
+/// write into their own one.
+pub fn dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("tinyrenderer-{}", name));
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+/// Loads `obj`, whose `mtllib scene.mtl` is `mtl`.
+pub fn scene(name: &str, obj: &str, mtl: &str) -> Scene {
+  scene_in(&dir(name), obj, mtl)
+}
+
+/// `scene` for a directory with more files, e.g. the textures of the MTL.
+pub fn scene_in(dir: &std::path::Path, obj: &str, mtl: &str) -> Scene {
+  std::fs::write(dir.join("scene.mtl"), mtl).unwrap();
+  let path = dir.join("scene.obj");
+  std::fs::write(&path, obj).unwrap();
+
+  from_obj_path(path.to_str().unwrap(), "scene").unwrap()
+}
+
+/// The 4 `v` of the square from `min` to `max` at the depth `z`, and its
+/// 2 faces, counter-clockwise from +z. `first` is the index of its first `v`.
+pub fn quad(min: (f32, f32), max: (f32, f32), z: f32, first: usize) -> String {
+  let (a, b, c, d) = (first, first + 1, first + 2, first + 3);
+  format!(
+    "v {x0} {y0} {z}\nv {x1} {y0} {z}\nv {x1} {y1} {z}\nv {x0} {y1} {z}\n\
+     f {a} {b} {c}\nf {a} {c} {d}\n",
+    x0 = min.0,
+    y0 = min.1,
+    x1 = max.0,
+    y1 = max.1,
+  )
+}
+
+/// Unlit, the `Kd` of the MTL material or white.
+pub fn flat_material() -> Material {
+  let shader = Shader {
+    fragment: Box::new(|uniforms, _, _| {
+      let kd = uniform!(uniforms, Vec3, "Kd").unwrap_or(Vec3::new(1.0, 1.0, 1.0));
+      Vec4::from_vec3(&kd, 1.0)
+    }),
+    ..Default::default()
+  };
+
+  Material {
+    shader,
+    ..Default::default()
+  }
+}
+
+#[track_caller]
+pub fn assert_color(actual: Vec4, expected: Vec3) {
+  assert!(
+    (actual.truncated_to_vec3() - expected).length() < 1e-4,
+    "{:?} != {:?}",
+    actual,
+    expected
+  );
+}